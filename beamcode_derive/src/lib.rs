@@ -7,6 +7,75 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields};
 pub fn derive_opcode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
+    if let Data::Enum(ref data) = input.data {
+        let arms = data.variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                assert_eq!(fields.unnamed.len(), 1);
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() => Self::#variant_name(_) => <#op as crate::instruction::Opcode>::CODE, }
+        });
+        let names = data.variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let snake_name = to_snake_case(&variant_name.to_string());
+            quote_spanned! { variant.span() => Self::#variant_name(_) => #snake_name, }
+        });
+        let opcodes = data.variants.iter().map(|variant| {
+            let snake_name = to_snake_case(&variant.ident.to_string());
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() => #snake_name => Some(<#op as crate::instruction::Opcode>::CODE), }
+        });
+        let froms = data.variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() =>
+                impl From<#op> for #name {
+                    fn from(x: #op) -> Self {
+                        Self::#variant_name(x)
+                    }
+                }
+            }
+        });
+        let expanded = quote! {
+            #(#froms)*
+
+            impl #name {
+                /// Returns the opcode of this instruction.
+                pub fn opcode(&self) -> u8 {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+
+                /// Returns the name of this instruction (e.g., `"call_ext_only"`).
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        #(#names)*
+                    }
+                }
+
+                /// Returns the opcode of the instruction named `name`.
+                pub fn opcode_from_name(name: &str) -> Option<u8> {
+                    match name {
+                        #(#opcodes)*
+                        _ => None,
+                    }
+                }
+            }
+        };
+        return proc_macro::TokenStream::from(expanded);
+    }
     let code = &input.attrs.last().expect("missing `#[opcode(N)]`").tokens;
     let expanded = quote! {
         impl crate::instruction::Opcode for #name {
@@ -16,6 +85,22 @@ pub fn derive_opcode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenS
     proc_macro::TokenStream::from(expanded)
 }
 
+// "CallExtOnly" => "call_ext_only"
+fn to_snake_case(name: &str) -> String {
+    let mut snake_name = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i != 0 {
+                snake_name.push('_');
+            }
+            snake_name.push(c.to_ascii_lowercase());
+        } else {
+            snake_name.push(c);
+        }
+    }
+    snake_name
+}
+
 #[proc_macro_derive(Decode)]
 pub fn derive_decode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -23,7 +108,11 @@ pub fn derive_decode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenS
     let decode = generate_decode_fun_body(&input.data);
     let expanded = quote! {
         impl crate::Decode for #name {
-            fn decode_with_tag<R: std::io::Read>(reader: &mut R, tag: u8) -> Result<Self, crate::DecodeError> {
+            fn decode_with_tag_limited<R: std::io::Read>(
+                reader: &mut R,
+                tag: u8,
+                limits: &crate::DecodeLimits,
+            ) -> Result<Self, crate::DecodeError> {
                 #decode
             }
         }
@@ -43,7 +132,7 @@ fn generate_decode_fun_body(data: &Data) -> TokenStream {
                     } else {
                         unimplemented!()
                     };
-                quote_spanned! { variant.span() => #op::CODE => crate::Decode::decode_with_tag(reader, tag).map(Self::#name), }
+                quote_spanned! { variant.span() => #op::CODE => crate::Decode::decode_with_tag_limited(reader, tag, limits).map(Self::#name), }
             });
             quote! {
                 match tag {
@@ -56,7 +145,7 @@ fn generate_decode_fun_body(data: &Data) -> TokenStream {
             Fields::Named(ref fields) => {
                 let decode = fields.named.iter().map(|f| {
                     let name = &f.ident;
-                    quote_spanned! { f.span() => #name: crate::Decode::decode(reader)? }
+                    quote_spanned! { f.span() => #name: crate::Decode::decode_limited(reader, limits)? }
                 });
                 quote! {
                     if tag != Self::CODE {
@@ -123,3 +212,118 @@ fn generate_encode_fun_body(data: &Data) -> TokenStream {
         _ => unimplemented!(),
     }
 }
+
+#[proc_macro_derive(Visit)]
+pub fn derive_visit_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let visit = generate_visit_fun_body(&input.data, quote! { visit });
+    let visit_mut = generate_visit_fun_body(&input.data, quote! { visit_mut });
+    let expanded = quote! {
+        impl crate::Visit for #name {
+            #[allow(unused_variables)]
+            fn visit<V: crate::Visitor>(&self, visitor: &mut V) {
+                #visit
+            }
+
+            #[allow(unused_variables)]
+            fn visit_mut<V: crate::VisitorMut>(&mut self, visitor: &mut V) {
+                #visit_mut
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn generate_visit_fun_body(data: &Data, method: TokenStream) -> TokenStream {
+    match *data {
+        Data::Enum(ref data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let name = &variant.ident;
+                if let Fields::Unnamed(fields) = &variant.fields {
+                    assert_eq!(fields.unnamed.len(), 1);
+                } else {
+                    unimplemented!();
+                }
+                quote_spanned! { variant.span() => Self::#name(x) => x.#method(visitor), }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let visit = fields.named.iter().map(|f| {
+                    let name = &f.ident;
+                    quote_spanned! { f.span() => self.#name.#method(visitor) }
+                });
+                quote! {
+                    #(#visit ;)*
+                }
+            }
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+#[proc_macro_derive(ProptestArbitrary)]
+pub fn derive_proptest_arbitrary_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let strategy = generate_proptest_strategy(&input.data);
+    let expanded = quote! {
+        impl proptest::arbitrary::Arbitrary for #name {
+            type Parameters = ();
+            type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with((): ()) -> Self::Strategy {
+                use proptest::strategy::Strategy as _;
+                #strategy
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn generate_proptest_strategy(data: &Data) -> TokenStream {
+    match *data {
+        Data::Enum(ref data) => {
+            // A flat union of boxed strategies (nested unions overflow the stack with this many variants).
+            let variants = data.variants.iter().map(|variant| {
+                let name = &variant.ident;
+                let op = if let Fields::Unnamed(fields) = &variant.fields {
+                    assert_eq!(fields.unnamed.len(), 1);
+                    &fields.unnamed.iter().next().expect("unreachable").ty
+                } else {
+                    unimplemented!()
+                };
+                quote_spanned! { variant.span() => proptest::arbitrary::any::<#op>().prop_map(Self::#name).boxed() }
+            });
+            quote! {
+                proptest::strategy::Union::new(vec![#(#variants ,)*]).boxed()
+            }
+        }
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                if fields.named.is_empty() {
+                    return quote! { proptest::strategy::Just(Self {}).boxed() };
+                }
+                let names = fields.named.iter().map(|f| &f.ident).collect::<Vec<_>>();
+                let strategies = fields.named.iter().map(|f| {
+                    let ty = &f.ty;
+                    quote_spanned! { f.span() => proptest::arbitrary::any::<#ty>() }
+                });
+                quote! {
+                    (#(#strategies ,)*)
+                        .prop_map(|(#(#names ,)*)| Self { #(#names ,)* })
+                        .boxed()
+                }
+            }
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
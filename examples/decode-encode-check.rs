@@ -1,5 +1,3 @@
-use beamcode::instruction::Instruction;
-use beamcode::Decode;
 use clap::Parser;
 
 #[derive(Parser)]
@@ -12,16 +10,22 @@ fn main() -> anyhow::Result<()> {
     let beam = beam_file::StandardBeamFile::from_file(&args.beam_file_path)?;
     for chunk in beam.chunks {
         if let beam_file::chunk::StandardChunk::Code(chunk) = chunk {
-            let instructions = beamcode::decode_instructions(&chunk.bytecode)?;
-            let mut reader = &chunk.bytecode[..];
-            for (i, instruction) in instructions.into_iter().enumerate() {
-                let start = chunk.bytecode.len() - reader.len();
-                let _ = Instruction::decode(&mut reader)?;
-                let end = chunk.bytecode.len() - reader.len();
-                let expected = &chunk.bytecode[start..end];
-
-                let encoded = beamcode::encode_instructions(&[instruction.clone()])?;
-                assert_eq!(encoded, expected, "[{}] {:?}", i, instruction);
+            let report = beamcode::roundtrip::verify_roundtrip(&chunk.bytecode);
+            for x in &report.mismatches {
+                println!(
+                    "[{}] {:?}: range={:?}, expected={:?}, actual={:?}",
+                    x.index, x.instruction, x.range, x.expected, x.actual
+                );
+            }
+            if let Some((index, offset, e)) = &report.decode_error {
+                anyhow::bail!("[{index}] failed to decode at byte {offset}: {e}");
+            }
+            if !report.is_ok() {
+                anyhow::bail!(
+                    "{} of {} instructions mismatched",
+                    report.mismatches.len(),
+                    report.instructions
+                );
             }
             return Ok(());
         }
@@ -0,0 +1,23 @@
+#![no_main]
+use beamcode::instruction::Instruction;
+use beamcode::{Decode, DecodeLimits, Encode};
+use libfuzzer_sys::fuzz_target;
+
+const LIMITS: DecodeLimits = DecodeLimits {
+    max_integer_bytes: 1024,
+    max_list_length: 1024,
+    max_instructions: 100_000,
+    max_nesting: 16,
+    max_literal_table_bytes: 1 << 20,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(instruction) = Instruction::decode_limited(&mut &data[..], &LIMITS) {
+        let mut buf = Vec::new();
+        instruction.encode(&mut buf).expect("encode failure");
+        assert_eq!(
+            Instruction::decode(&mut &buf[..]).expect("decode failure"),
+            instruction
+        );
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+use beamcode::term::Term;
+use beamcode::{Decode, DecodeLimits, Encode};
+use libfuzzer_sys::fuzz_target;
+
+const LIMITS: DecodeLimits = DecodeLimits {
+    max_integer_bytes: 1024,
+    max_list_length: 1024,
+    max_instructions: 100_000,
+    max_nesting: 16,
+    max_literal_table_bytes: 1 << 20,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(term) = Term::decode_limited(&mut &data[..], &LIMITS) {
+        let mut buf = Vec::new();
+        term.encode(&mut buf).expect("encode failure");
+        assert_eq!(Term::decode(&mut &buf[..]).expect("decode failure"), term);
+    }
+});
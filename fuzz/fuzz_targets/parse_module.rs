@@ -0,0 +1,23 @@
+#![no_main]
+use beamcode::module::Module;
+use beamcode::DecodeLimits;
+use libfuzzer_sys::fuzz_target;
+
+const LIMITS: DecodeLimits = DecodeLimits {
+    max_integer_bytes: 1024,
+    max_list_length: 1024,
+    max_instructions: 100_000,
+    max_nesting: 16,
+    max_literal_table_bytes: 1 << 20,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(module) = Module::from_reader_limited(data, &LIMITS) {
+        let instructions =
+            beamcode::encode_instructions(&module.code.instructions).expect("encode failure");
+        assert_eq!(
+            beamcode::decode_instructions(&instructions).expect("decode failure"),
+            module.code.instructions
+        );
+    }
+});
@@ -0,0 +1,234 @@
+//! [`arbitrary`] integration (enabled by the `arbitrary` feature).
+//!
+//! Unlike the `proptest` generators, these generate only well-typed instructions:
+//!
+//! - each operand is generated according to its role (e.g., a [`Register`] for the
+//!   destination of `move`, or a [`Term::Label`] for the fail label of `gc_bif2`),
+//! - any other [`Term`] operand is a source operand (a register, an integer, an atom or a
+//!   literal), and
+//! - the lists of some instructions have their expected layout
+//!   (e.g., the value / label pairs of `select_val`).
+//!
+//! Every generated instruction satisfies `decode(encode(x)) == x`.
+//!
+//! # Examples
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use beamcode::instruction::Instruction;
+//!
+//! let data = [7; 64];
+//! let instruction = Instruction::arbitrary(&mut Unstructured::new(&data))?;
+//! # Ok::<(), arbitrary::Error>(())
+//! ```
+use crate::operand_generators::MAX_LIST_ITEMS;
+use crate::term::{Allocation, List, Register, Term};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use num::BigInt;
+
+/// Generates a source operand.
+impl<'a> Arbitrary<'a> for Term {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => Register::arbitrary(u)?.into(),
+            1 => Self::Integer(BigInt::from(i128::arbitrary(u)?)),
+            2 => Self::Atom(u.arbitrary()?),
+            _ => Self::Literal(u.arbitrary()?),
+        })
+    }
+}
+
+/// Generates a list of source operands.
+impl<'a> Arbitrary<'a> for List {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        list(u, |u| Ok(vec![u.arbitrary()?]))
+    }
+}
+
+// A fail label or a jump target.
+pub(crate) fn label_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(Term::Label(u.arbitrary()?))
+}
+
+// An unsigned literal such as an arity, the number of live registers or an import index.
+pub(crate) fn usize_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(Term::Usize(u.arbitrary()?))
+}
+
+// A destination register.
+pub(crate) fn register_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(Register::arbitrary(u)?.into())
+}
+
+pub(crate) fn float_register_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(Term::FloatingPointRegister(u.arbitrary()?))
+}
+
+pub(crate) fn allocation_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(match Allocation::arbitrary(u)? {
+        Allocation::Words(words) => Term::Usize(words),
+        Allocation::List(list) => Term::AllocationList(list),
+    })
+}
+
+// The source of `fmove`: a floating point register or a source operand.
+pub(crate) fn float_source(u: &mut Unstructured) -> Result<Term> {
+    if u.arbitrary()? {
+        float_register_operand(u)
+    } else {
+        u.arbitrary()
+    }
+}
+
+// The destination of `fmove`: a floating point register or a register.
+pub(crate) fn float_destination(u: &mut Unstructured) -> Result<Term> {
+    if u.arbitrary()? {
+        float_register_operand(u)
+    } else {
+        register_operand(u)
+    }
+}
+
+// The fail operand of `bs_start_match4`: a label, `{atom,no_fail}` or `{atom,resume}`.
+pub(crate) fn label_or_atom(u: &mut Unstructured) -> Result<Term> {
+    if u.arbitrary()? {
+        label_operand(u)
+    } else {
+        atom_operand(u)
+    }
+}
+
+// An atom such as the tag of `call_fun2`.
+pub(crate) fn atom_operand(u: &mut Unstructured) -> Result<Term> {
+    Ok(Term::Atom(u.arbitrary()?))
+}
+
+// A list of source operands (e.g., the environment of `make_fun3`).
+pub(crate) fn source_list(u: &mut Unstructured) -> Result<Term> {
+    Ok(Term::List(u.arbitrary()?))
+}
+
+fn list<'a, F>(u: &mut Unstructured<'a>, mut f: F) -> Result<List>
+where
+    F: FnMut(&mut Unstructured<'a>) -> Result<Vec<Term>>,
+{
+    let mut items = Vec::new();
+    for _ in 0..u.int_in_range(0..=MAX_LIST_ITEMS)? {
+        items.extend(f(u)?);
+    }
+    Ok(List { items })
+}
+
+// The operands of `select_val`.
+pub(crate) fn value_label_pairs(u: &mut Unstructured) -> Result<List> {
+    list(u, |u| {
+        let value = if u.arbitrary()? {
+            Term::Integer(BigInt::from(i128::arbitrary(u)?))
+        } else {
+            Term::Atom(u.arbitrary()?)
+        };
+        Ok(vec![value, Term::Label(u.arbitrary()?)])
+    })
+}
+
+// The operands of `select_tuple_arity`.
+pub(crate) fn arity_label_pairs(u: &mut Unstructured) -> Result<Term> {
+    list(u, |u| {
+        Ok(vec![
+            Term::Usize(u.arbitrary()?),
+            Term::Label(u.arbitrary()?),
+        ])
+    })
+    .map(Term::List)
+}
+
+// The key / value pairs of `put_map_assoc` and `put_map_exact`.
+pub(crate) fn source_pairs(u: &mut Unstructured) -> Result<Term> {
+    list(u, |u| Ok(vec![u.arbitrary()?, u.arbitrary()?])).map(Term::List)
+}
+
+// The operands of `get_map_elements`.
+pub(crate) fn key_destination_pairs(u: &mut Unstructured) -> Result<Term> {
+    list(u, |u| {
+        Ok(vec![u.arbitrary()?, Register::arbitrary(u)?.into()])
+    })
+    .map(Term::List)
+}
+
+// The segments of `bs_create_bin`: `Type, Segment, Unit, Flags, Source, Size`.
+pub(crate) fn bs_create_bin_segments(u: &mut Unstructured) -> Result<Term> {
+    list(u, |u| {
+        Ok(vec![
+            Term::Atom(u.arbitrary()?),
+            Term::Usize(u.arbitrary()?),
+            Term::Usize(u.arbitrary()?),
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ])
+    })
+    .map(Term::List)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+    use crate::{Decode, Encode};
+
+    #[test]
+    fn arbitrary_instruction_works() {
+        let mut x: u64 = 88172645463325252;
+        let data = (0..1 << 16)
+            .map(|_| {
+                // xorshift64
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        let mut select_vals = 0;
+        while let Ok(instruction) = Instruction::arbitrary(&mut u) {
+            let mut buf = Vec::new();
+            instruction.encode(&mut buf).unwrap();
+            let mut reader = &buf[..];
+            assert_eq!(Instruction::decode(&mut reader).unwrap(), instruction);
+            assert!(reader.is_empty());
+
+            if let Instruction::SelectVal(x) = &instruction {
+                assert_eq!(x.destinations.items.len() % 2, 0);
+                assert!(x
+                    .destinations
+                    .items
+                    .iter()
+                    .skip(1)
+                    .step_by(2)
+                    .all(|x| matches!(x, Term::Label(_))));
+                select_vals += 1;
+            }
+            if let Instruction::GcBif2(x) = &instruction {
+                assert!(matches!(x.arg1, Term::Label(_)));
+                assert!(x.arg2.to_usize().is_some());
+                assert!(x.arg6.to_register().is_some());
+            }
+            if let Instruction::Fmove(x) = &instruction {
+                assert!(
+                    matches!(x.arg2, Term::FloatingPointRegister(_))
+                        || x.arg2.to_register().is_some()
+                );
+            }
+            if let Instruction::BsStartMatch4(x) = &instruction {
+                assert!(matches!(x.arg1, Term::Label(_) | Term::Atom(_)));
+            }
+            if let Instruction::CallFun2(x) = &instruction {
+                assert!(matches!(x.arg1, Term::Atom(_)));
+            }
+            if u.is_empty() {
+                break;
+            }
+        }
+        assert!(select_vals > 0);
+    }
+}
@@ -0,0 +1,933 @@
+//! Textual assembly of modules.
+//!
+//! [`disassemble()`] converts a module into a listing whose syntax is a subset of Erlang terms
+//! (similar to the `.S` files generated by `erlc -S`), and [`assemble()`] converts such a
+//! listing back into a module.
+//!
+//! A listing is a sequence of forms, each of which is terminated by `.`:
+//!
+//! ```text
+//! {atoms,[foo,bar]}.                       % the atom table (the first one is the module name)
+//! {imports,[{erlang,'+',2}]}.              % {Module, Function, Arity}
+//! {exports,[{bar,0,2}]}.                   % {Function, Arity, Label}
+//! {locals,[]}.                             % {Function, Arity, Label}
+//! {lambdas,[]}.                            % {Function, Arity, Label, Index, NumFree, OldUniq}
+//! {literals,["836a"]}.                     % the External Term Format in hex
+//! {chunk,"StrT",""}.                       % a chunk not interpreted by this crate
+//! {code,16,0,169,3,1}.                     % InfoSize, Version, OpcodeMax, LabelCount, FunctionCount
+//! {label,1}.
+//! {func_info,{atom,foo},{atom,bar},0}.
+//! {label,2}.
+//! {move,{literal,0},{x,0}}.                % []
+//! return.
+//! int_code_end.
+//! ```
+//!
+//! The operands of an instruction are written as follows:
+//!
+//! | Operand                | Syntax                                            |
+//! |------------------------|---------------------------------------------------|
+//! | unsigned integer       | `3`                                               |
+//! | integer                | `{integer,-3}`                                    |
+//! | atom                   | `{atom,foo}` (or `nil` for `[]`)                  |
+//! | x / y register         | `{x,0}` / `{y,0}`                                 |
+//! | typed register         | `{tr,{x,0},5}`                                    |
+//! | label                  | `{f,3}`                                           |
+//! | character              | `{char,97}`                                       |
+//! | inline float           | `{float,4614253070214989087}` (the IEEE 754 bits) |
+//! | list                   | `{list,[{atom,foo},{f,3}]}`                       |
+//! | floating point register| `{fr,0}`                                          |
+//! | allocation list        | `{alloc,[{words,1},{floats,0},{funs,0}]}`         |
+//! | literal                | `{literal,0}`                                     |
+//!
+//! If the `code` form is omitted, [`assemble()`] computes the header of the `Code` chunk
+//! from the instructions.
+use crate::builder::CODE_INFO_SIZE;
+use crate::etf::Value;
+use crate::instruction::Instruction;
+use crate::module::{Code, Export, Import, Lambda, Local, Module, OtherChunk};
+use crate::term::{
+    AllocationList, AllocationListItem, Atom, Float, FloatingPointRegister, Label, List, Literal,
+    Term, TypedRegister, XRegister, YRegister,
+};
+use crate::{Decode, Encode};
+use num::{BigInt, ToPrimitive};
+use std::fmt::Write as _;
+
+/// Converts `module` into a listing.
+pub fn disassemble(module: &Module) -> String {
+    let mut buf = String::new();
+    let atoms = module.atoms.iter().map(|x| atom(x)).collect::<Vec<_>>();
+    let _ = writeln!(buf, "{{atoms,[{}]}}.", atoms.join(","));
+    let imports = module
+        .imports
+        .iter()
+        .map(|x| {
+            format!(
+                "{{{},{},{}}}",
+                atom_operand(module, x.module),
+                atom_operand(module, x.function),
+                x.arity
+            )
+        })
+        .collect::<Vec<_>>();
+    let _ = writeln!(buf, "{{imports,[{}]}}.", imports.join(","));
+    let functions = |entries: &mut dyn Iterator<Item = (Atom, usize, Label)>| {
+        entries
+            .map(|(function, arity, label)| {
+                format!(
+                    "{{{},{},{}}}",
+                    atom_operand(module, function),
+                    arity,
+                    label.value
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let _ = writeln!(
+        buf,
+        "{{exports,[{}]}}.",
+        functions(
+            &mut module
+                .exports
+                .iter()
+                .map(|x| (x.function, x.arity, x.label))
+        )
+    );
+    let _ = writeln!(
+        buf,
+        "{{locals,[{}]}}.",
+        functions(&mut module.locals.iter().map(|x| (x.function, x.arity, x.label)))
+    );
+    let lambdas = module
+        .lambdas
+        .iter()
+        .map(|x| {
+            format!(
+                "{{{},{},{},{},{},{}}}",
+                atom_operand(module, x.function),
+                x.arity,
+                x.label.value,
+                x.index,
+                x.num_free,
+                x.old_uniq
+            )
+        })
+        .collect::<Vec<_>>();
+    let _ = writeln!(buf, "{{lambdas,[{}]}}.", lambdas.join(","));
+    buf.push_str("{literals,[");
+    for (i, literal) in module.literals.iter().enumerate() {
+        let separator = if i + 1 < module.literals.len() {
+            ","
+        } else {
+            ""
+        };
+        let _ = write!(buf, "\n  \"{}\"{separator}", hex(literal));
+        if let Ok(value) = Value::from_bytes(literal) {
+            let _ = write!(buf, " % {value}");
+        }
+    }
+    buf.push_str("\n]}.\n");
+    for chunk in &module.other_chunks {
+        let _ = writeln!(
+            buf,
+            "{{chunk,{},\"{}\"}}.",
+            string(&String::from_utf8_lossy(&chunk.id)),
+            hex(&chunk.data)
+        );
+    }
+    let code = &module.code;
+    let _ = writeln!(
+        buf,
+        "{{code,{},{},{},{},{}}}.",
+        code.info_size, code.version, code.opcode_max, code.label_count, code.function_count
+    );
+
+    for instruction in &code.instructions {
+        let mut comments = Vec::new();
+        let operands = instruction
+            .operands()
+            .iter()
+            .map(|x| operand(module, x, &mut comments))
+            .collect::<Vec<_>>();
+        if operands.is_empty() {
+            let _ = write!(buf, "{}.", instruction.name());
+        } else {
+            let _ = write!(buf, "{{{},{}}}.", instruction.name(), operands.join(","));
+        }
+        if !comments.is_empty() {
+            let _ = write!(buf, " % {}", comments.join(", "));
+        }
+        buf.push('\n');
+    }
+    buf
+}
+
+fn operand(module: &Module, term: &Term, comments: &mut Vec<String>) -> String {
+    match term {
+        Term::Usize(x) => x.to_string(),
+        Term::Integer(x) => format!("{{integer,{x}}}"),
+        Term::Atom(x) if x.value == 0 => "nil".to_owned(),
+        Term::Atom(x) => format!("{{atom,{}}}", atom_operand(module, *x)),
+        Term::XRegister(x) => format!("{{x,{}}}", x.value),
+        Term::YRegister(x) => format!("{{y,{}}}", x.value),
+        Term::Label(x) => format!("{{f,{}}}", x.value),
+        Term::Character(x) => format!("{{char,{}}}", u32::from(*x)),
+        Term::Float(x) => {
+            comments.push(format!("{:?}", x.value));
+            format!("{{float,{}}}", x.value.to_bits())
+        }
+        Term::List(x) => {
+            let items = x
+                .items
+                .iter()
+                .map(|x| operand(module, x, comments))
+                .collect::<Vec<_>>();
+            format!("{{list,[{}]}}", items.join(","))
+        }
+        Term::FloatingPointRegister(x) => format!("{{fr,{}}}", x.value),
+        Term::AllocationList(x) => {
+            let items = x
+                .items
+                .iter()
+                .map(|x| match x {
+                    AllocationListItem::Words(n) => format!("{{words,{n}}}"),
+                    AllocationListItem::Floats(n) => format!("{{floats,{n}}}"),
+                    AllocationListItem::Funs(n) => format!("{{funs,{n}}}"),
+                })
+                .collect::<Vec<_>>();
+            format!("{{alloc,[{}]}}", items.join(","))
+        }
+        Term::Literal(x) => {
+            if let Some(value) = module
+                .literals
+                .get(x.value)
+                .and_then(|x| Value::from_bytes(x).ok())
+            {
+                comments.push(value.to_string());
+            }
+            format!("{{literal,{}}}", x.value)
+        }
+        Term::TypedRegister(TypedRegister::X { register, ty }) => {
+            format!("{{tr,{{x,{}}},{ty}}}", register.value)
+        }
+        Term::TypedRegister(TypedRegister::Y { register, ty }) => {
+            format!("{{tr,{{y,{}}},{ty}}}", register.value)
+        }
+    }
+}
+
+// Atoms out of the range of the atom table are written as their indices.
+fn atom_operand(module: &Module, x: Atom) -> String {
+    match module.atom_name(x) {
+        Some(name) => atom(name),
+        None => x.value.to_string(),
+    }
+}
+
+fn atom(name: &str) -> String {
+    let is_simple = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@');
+    if is_simple && !RESERVED_WORDS.contains(&name) {
+        name.to_owned()
+    } else {
+        format!("'{}'", escape(name, '\''))
+    }
+}
+
+fn string(s: &str) -> String {
+    format!("\"{}\"", escape(s, '"'))
+}
+
+fn escape(s: &str, quote: char) -> String {
+    let mut buf = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            c if c == quote => {
+                buf.push('\\');
+                buf.push(c);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut buf, b| {
+        let _ = write!(buf, "{b:02x}");
+        buf
+    })
+}
+
+// Erlang reserved words, which must be quoted when used as atoms.
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "maybe", "not", "of", "or", "orelse",
+    "receive", "rem", "try", "when", "xor",
+];
+
+/// Assembly error.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {kind}")]
+pub struct AsmError {
+    /// The (one-based) line number where the offending form starts.
+    pub line: usize,
+
+    pub kind: AsmErrorKind,
+}
+
+/// Kinds of [`AsmError`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AsmErrorKind {
+    #[error("unexpected character: {c:?}")]
+    UnexpectedChar { c: char },
+
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+
+    #[error("invalid form: {form}")]
+    InvalidForm { form: String },
+
+    #[error("invalid operand: {operand}")]
+    InvalidOperand { operand: String },
+
+    #[error("unknown instruction: {name}")]
+    UnknownInstruction { name: String },
+
+    #[error("unknown atom: {name}")]
+    UnknownAtom { name: String },
+
+    #[error("invalid hex string: {s:?}")]
+    InvalidHex { s: String },
+
+    #[error("invalid operands of {name}: {reason}")]
+    InvalidOperands { name: String, reason: String },
+
+    #[error("the first form must be `{{atoms,[...]}}`")]
+    MissingAtoms,
+}
+
+/// Converts a listing (see the [module documentation](self)) into a module.
+pub fn assemble(text: &str) -> Result<Module, AsmError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+    let mut module = Module {
+        atoms: Vec::new(),
+        imports: Vec::new(),
+        exports: Vec::new(),
+        locals: Vec::new(),
+        lambdas: Vec::new(),
+        literals: Vec::new(),
+        code: Code::default(),
+        other_chunks: Vec::new(),
+    };
+    let mut code = None;
+    let mut has_atoms = false;
+    while let Some((line, form)) = parser.parse_form()? {
+        let error = |kind| AsmError { line, kind };
+        let invalid_form = || {
+            error(AsmErrorKind::InvalidForm {
+                form: form.to_string(),
+            })
+        };
+        let (name, args) = match &form {
+            Syntax::Atom(name) => (name.as_str(), &[][..]),
+            Syntax::Tuple(items) => match items.split_first() {
+                Some((Syntax::Atom(name), args)) => (name.as_str(), args),
+                _ => return Err(invalid_form()),
+            },
+            _ => return Err(invalid_form()),
+        };
+        if !has_atoms && name != "atoms" {
+            return Err(error(AsmErrorKind::MissingAtoms));
+        }
+        let assembler = Assembler { module: &module };
+        match (name, args) {
+            ("atoms", [Syntax::List(items)]) if !has_atoms => {
+                module.atoms = items
+                    .iter()
+                    .map(|x| match x {
+                        Syntax::Atom(x) => Ok(x.clone()),
+                        _ => Err(invalid_form()),
+                    })
+                    .collect::<Result<_, _>>()?;
+                has_atoms = true;
+            }
+            ("imports", [Syntax::List(items)]) => {
+                module.imports = items
+                    .iter()
+                    .map(|x| match x {
+                        Syntax::Tuple(x) => match &x[..] {
+                            [m, f, a] => Ok(Import {
+                                module: assembler.atom(m).map_err(error)?,
+                                function: assembler.atom(f).map_err(error)?,
+                                arity: usize_operand(a).map_err(error)?,
+                            }),
+                            _ => Err(invalid_form()),
+                        },
+                        _ => Err(invalid_form()),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            ("exports" | "locals", [Syntax::List(items)]) => {
+                let entries = items
+                    .iter()
+                    .map(|x| match x {
+                        Syntax::Tuple(x) => match &x[..] {
+                            [f, a, l] => Ok((
+                                assembler.atom(f).map_err(error)?,
+                                usize_operand(a).map_err(error)?,
+                                Label {
+                                    value: usize_operand(l).map_err(error)?,
+                                },
+                            )),
+                            _ => Err(invalid_form()),
+                        },
+                        _ => Err(invalid_form()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if name == "exports" {
+                    module.exports = entries
+                        .into_iter()
+                        .map(|(function, arity, label)| Export {
+                            function,
+                            arity,
+                            label,
+                        })
+                        .collect();
+                } else {
+                    module.locals = entries
+                        .into_iter()
+                        .map(|(function, arity, label)| Local {
+                            function,
+                            arity,
+                            label,
+                        })
+                        .collect();
+                }
+            }
+            ("lambdas", [Syntax::List(items)]) => {
+                module.lambdas = items
+                    .iter()
+                    .map(|x| match x {
+                        Syntax::Tuple(x) => match &x[..] {
+                            [f, a, l, i, n, u] => Ok(Lambda {
+                                function: assembler.atom(f).map_err(error)?,
+                                arity: usize_operand(a).map_err(error)?,
+                                label: Label {
+                                    value: usize_operand(l).map_err(error)?,
+                                },
+                                index: usize_operand(i).map_err(error)?,
+                                num_free: usize_operand(n).map_err(error)?,
+                                old_uniq: usize_operand(u)
+                                    .map_err(error)?
+                                    .try_into()
+                                    .map_err(|_| invalid_form())?,
+                            }),
+                            _ => Err(invalid_form()),
+                        },
+                        _ => Err(invalid_form()),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            ("literals", [Syntax::List(items)]) => {
+                module.literals = items
+                    .iter()
+                    .map(|x| match x {
+                        Syntax::String(x) => unhex(x).map_err(error),
+                        _ => Err(invalid_form()),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            ("chunk", [Syntax::String(id), Syntax::String(data)]) => {
+                let id = <[u8; 4]>::try_from(id.as_bytes()).map_err(|_| invalid_form())?;
+                module.other_chunks.push(OtherChunk {
+                    id,
+                    data: unhex(data).map_err(error)?,
+                });
+            }
+            ("code", [info_size, version, opcode_max, label_count, function_count]) => {
+                let header = [info_size, version, opcode_max, label_count, function_count]
+                    .into_iter()
+                    .map(|x| {
+                        usize_operand(x)
+                            .map_err(error)?
+                            .try_into()
+                            .map_err(|_| invalid_form())
+                    })
+                    .collect::<Result<Vec<u32>, _>>()?;
+                code = Some(header);
+            }
+            _ => {
+                let instruction = assembler.instruction(name, args).map_err(error)?;
+                module.code.instructions.push(instruction);
+            }
+        }
+    }
+    if !has_atoms {
+        return Err(AsmError {
+            line: parser.line,
+            kind: AsmErrorKind::MissingAtoms,
+        });
+    }
+
+    let instructions = &module.code.instructions;
+    let [info_size, version, opcode_max, label_count, function_count] = match code.as_deref() {
+        Some(&[a, b, c, d, e]) => [a, b, c, d, e],
+        _ => [
+            CODE_INFO_SIZE,
+            0,
+            u32::from(instructions.iter().map(|x| x.opcode()).max().unwrap_or(0)),
+            instructions
+                .iter()
+                .filter_map(|x| match x {
+                    Instruction::Label(x) => Some(x.literal as u32 + 1),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(1),
+            instructions
+                .iter()
+                .filter(|x| matches!(x, Instruction::FuncInfo(_)))
+                .count() as u32,
+        ],
+    };
+    module.code.info_size = info_size;
+    module.code.version = version;
+    module.code.opcode_max = opcode_max;
+    module.code.label_count = label_count;
+    module.code.function_count = function_count;
+    Ok(module)
+}
+
+fn usize_operand(x: &Syntax) -> Result<usize, AsmErrorKind> {
+    match x {
+        Syntax::Integer(n) => n.to_usize().ok_or_else(|| AsmErrorKind::InvalidOperand {
+            operand: x.to_string(),
+        }),
+        _ => Err(AsmErrorKind::InvalidOperand {
+            operand: x.to_string(),
+        }),
+    }
+}
+
+fn unhex(s: &str) -> Result<Vec<u8>, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidHex { s: s.to_owned() };
+    if !s.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+struct Assembler<'a> {
+    module: &'a Module,
+}
+
+impl<'a> Assembler<'a> {
+    fn atom(&self, x: &Syntax) -> Result<Atom, AsmErrorKind> {
+        match x {
+            Syntax::Atom(name) => self
+                .module
+                .atoms
+                .iter()
+                .position(|x| x == name)
+                .map(|i| Atom { value: i + 1 })
+                .ok_or_else(|| AsmErrorKind::UnknownAtom { name: name.clone() }),
+            Syntax::Integer(_) => usize_operand(x).map(|value| Atom { value }),
+            _ => Err(AsmErrorKind::InvalidOperand {
+                operand: x.to_string(),
+            }),
+        }
+    }
+
+    fn instruction(&self, name: &str, args: &[Syntax]) -> Result<Instruction, AsmErrorKind> {
+        let opcode = Instruction::opcode_from_name(name).ok_or_else(|| {
+            AsmErrorKind::UnknownInstruction {
+                name: name.to_owned(),
+            }
+        })?;
+        let mut bytes = vec![opcode];
+        for arg in args {
+            self.operand(arg)?
+                .encode(&mut bytes)
+                .map_err(|e| AsmErrorKind::InvalidOperands {
+                    name: name.to_owned(),
+                    reason: e.to_string(),
+                })?;
+        }
+        let invalid = |reason: String| AsmErrorKind::InvalidOperands {
+            name: name.to_owned(),
+            reason,
+        };
+        let mut reader = &bytes[..];
+        let instruction = Instruction::decode(&mut reader).map_err(|e| invalid(e.to_string()))?;
+        if !reader.is_empty() {
+            return Err(invalid("too many operands".to_owned()));
+        }
+        Ok(instruction)
+    }
+
+    fn operand(&self, x: &Syntax) -> Result<Term, AsmErrorKind> {
+        let invalid = || AsmErrorKind::InvalidOperand {
+            operand: x.to_string(),
+        };
+        let items = match x {
+            Syntax::Integer(_) => return usize_operand(x).map(Term::Usize),
+            Syntax::Atom(name) if name == "nil" => return Ok(Term::Atom(Atom { value: 0 })),
+            Syntax::Tuple(items) => items,
+            _ => return Err(invalid()),
+        };
+        let term = match &items[..] {
+            [Syntax::Atom(tag), value] => match (tag.as_str(), value) {
+                ("integer", Syntax::Integer(n)) => Term::Integer(n.clone()),
+                ("atom", value) => Term::Atom(self.atom(value)?),
+                ("x", value) => Term::x(usize_operand(value)?),
+                ("y", value) => Term::y(usize_operand(value)?),
+                ("f", value) => Term::Label(Label {
+                    value: usize_operand(value)?,
+                }),
+                ("char", value) => Term::Character(
+                    u32::try_from(usize_operand(value)?)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(invalid)?,
+                ),
+                ("float", Syntax::Integer(n)) => Term::Float(Float {
+                    value: f64::from_bits(n.to_u64().ok_or_else(invalid)?),
+                }),
+                ("list", Syntax::List(items)) => Term::List(List {
+                    items: items
+                        .iter()
+                        .map(|x| self.operand(x))
+                        .collect::<Result<_, _>>()?,
+                }),
+                ("fr", value) => Term::FloatingPointRegister(FloatingPointRegister {
+                    value: usize_operand(value)?,
+                }),
+                ("alloc", Syntax::List(items)) => Term::AllocationList(AllocationList {
+                    items: items
+                        .iter()
+                        .map(|x| match x {
+                            Syntax::Tuple(x) => match &x[..] {
+                                [Syntax::Atom(kind), n] => match kind.as_str() {
+                                    "words" => Ok(AllocationListItem::Words(usize_operand(n)?)),
+                                    "floats" => Ok(AllocationListItem::Floats(usize_operand(n)?)),
+                                    "funs" => Ok(AllocationListItem::Funs(usize_operand(n)?)),
+                                    _ => Err(invalid()),
+                                },
+                                _ => Err(invalid()),
+                            },
+                            _ => Err(invalid()),
+                        })
+                        .collect::<Result<_, _>>()?,
+                }),
+                ("literal", value) => Term::Literal(Literal {
+                    value: usize_operand(value)?,
+                }),
+                _ => return Err(invalid()),
+            },
+            [Syntax::Atom(tag), Syntax::Tuple(register), ty] if tag == "tr" => {
+                let ty = usize_operand(ty)?;
+                match &register[..] {
+                    [Syntax::Atom(kind), value] if kind == "x" => {
+                        Term::TypedRegister(TypedRegister::X {
+                            register: XRegister {
+                                value: usize_operand(value)?,
+                                ty: None,
+                            },
+                            ty,
+                        })
+                    }
+                    [Syntax::Atom(kind), value] if kind == "y" => {
+                        Term::TypedRegister(TypedRegister::Y {
+                            register: YRegister {
+                                value: usize_operand(value)?,
+                                ty: None,
+                            },
+                            ty,
+                        })
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(term)
+    }
+}
+
+// The subset of the Erlang term syntax used in listings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Syntax {
+    Atom(String),
+    Integer(BigInt),
+    String(String),
+    Tuple(Vec<Syntax>),
+    List(Vec<Syntax>),
+}
+
+impl std::fmt::Display for Syntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let join = |items: &[Syntax]| {
+            items
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self {
+            Self::Atom(x) => write!(f, "{}", atom(x)),
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::String(x) => write!(f, "{}", string(x)),
+            Self::Tuple(x) => write!(f, "{{{}}}", join(x)),
+            Self::List(x) => write!(f, "[{}]", join(x)),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    // Returns the line number and the term of the next form.
+    fn parse_form(&mut self) -> Result<Option<(usize, Syntax)>, AsmError> {
+        self.skip_whitespaces();
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let line = self.line;
+        let form = self.parse_term()?;
+        self.expect('.')?;
+        Ok(Some((line, form)))
+    }
+
+    fn parse_term(&mut self) -> Result<Syntax, AsmError> {
+        self.skip_whitespaces();
+        match self.peek() {
+            Some('{') => {
+                self.position += 1;
+                self.parse_items('}').map(Syntax::Tuple)
+            }
+            Some('[') => {
+                self.position += 1;
+                self.parse_items(']').map(Syntax::List)
+            }
+            Some('\'') => self.parse_quoted('\'').map(Syntax::Atom),
+            Some('"') => self.parse_quoted('"').map(Syntax::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                self.position += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+                let s = self.chars[start..self.position].iter().collect::<String>();
+                s.parse()
+                    .map(Syntax::Integer)
+                    .map_err(|_| self.error(AsmErrorKind::UnexpectedChar { c }))
+            }
+            Some(c) if c.is_ascii_lowercase() => {
+                let start = self.position;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+                {
+                    self.position += 1;
+                }
+                Ok(Syntax::Atom(
+                    self.chars[start..self.position].iter().collect(),
+                ))
+            }
+            Some(c) => Err(self.error(AsmErrorKind::UnexpectedChar { c })),
+            None => Err(self.error(AsmErrorKind::UnexpectedEof)),
+        }
+    }
+
+    fn parse_items(&mut self, close: char) -> Result<Vec<Syntax>, AsmError> {
+        let mut items = Vec::new();
+        self.skip_whitespaces();
+        if self.peek() == Some(close) {
+            self.position += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_term()?);
+            self.skip_whitespaces();
+            match self.next() {
+                Some(',') => {}
+                Some(c) if c == close => return Ok(items),
+                Some(c) => return Err(self.error(AsmErrorKind::UnexpectedChar { c })),
+                None => return Err(self.error(AsmErrorKind::UnexpectedEof)),
+            }
+        }
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> Result<String, AsmError> {
+        self.position += 1;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('\\') => match self.next() {
+                    Some('n') => s.push('\n'),
+                    Some(c) => s.push(c),
+                    None => return Err(self.error(AsmErrorKind::UnexpectedEof)),
+                },
+                Some(c) if c == quote => return Ok(s),
+                Some(c) => s.push(c),
+                None => return Err(self.error(AsmErrorKind::UnexpectedEof)),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), AsmError> {
+        self.skip_whitespaces();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(AsmErrorKind::UnexpectedChar { c })),
+            None => Err(self.error(AsmErrorKind::UnexpectedEof)),
+        }
+    }
+
+    // Skips whitespaces and comments.
+    fn skip_whitespaces(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '%' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.position += 1;
+                }
+            } else if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, kind: AsmErrorKind) -> AsmError {
+        AsmError {
+            line: self.line,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Allocate, CallExtOnly, Move, SelectVal};
+    use crate::term::{Allocation, Register};
+
+    #[test]
+    fn disassemble_and_assemble_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 1);
+        function.export();
+        let nil = function.literal(&[131, 106]);
+        let quoted = function.atom("Quoted 'atom'");
+        let erlang_error = function.import("erlang", "error", 1);
+        let fail = function.func_info_label();
+        function
+            .push(Allocate {
+                stack_need: Allocation::List(AllocationList {
+                    items: vec![AllocationListItem::Words(1), AllocationListItem::Floats(0)],
+                }),
+                live: 1,
+            })
+            .push(SelectVal {
+                arg: Term::TypedRegister(TypedRegister::X {
+                    register: XRegister { value: 0, ty: None },
+                    ty: 3,
+                }),
+                fail_label: fail,
+                destinations: List {
+                    items: vec![
+                        Term::Integer(BigInt::from(-1)),
+                        Term::Label(fail),
+                        Term::Atom(quoted),
+                        Term::Label(fail),
+                    ],
+                },
+            })
+            .push(Move {
+                src: Term::Literal(nil),
+                dst: Register::x(0),
+            })
+            .push(CallExtOnly {
+                arity: 1,
+                destination: erlang_error,
+            });
+        let module = builder.build();
+
+        let text = disassemble(&module);
+        assert!(text.contains("{move,{literal,0},{x,0}}. % []\n"));
+        assert_eq!(assemble(&text).unwrap(), module);
+
+        // Comments must not break lines.
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 0);
+        let literal = Value::atom("a\nb").to_bytes().unwrap();
+        let literal = function.literal(&literal);
+        function.push(Move {
+            src: Term::Literal(literal),
+            dst: Register::x(0),
+        });
+        let module = builder.build();
+        let text = disassemble(&module);
+        assert!(text.contains("{move,{literal,0},{x,0}}. % 'a\\nb'\n"));
+        assert_eq!(assemble(&text).unwrap(), module);
+
+        let module = assemble(
+            "{atoms,[foo,bar]}.\n\
+             {label,1}.\n\
+             {func_info,{atom,foo},{atom,bar},0}.\n\
+             {label,2}.\n\
+             {move,nil,{x,0}}.\n\
+             return.\n",
+        )
+        .unwrap();
+        assert_eq!(module.code.label_count, 3);
+        assert_eq!(module.code.function_count, 1);
+
+        assert_eq!(
+            assemble("{atoms,[foo]}.\n{move,{atom,baz},{x,0}}.").unwrap_err(),
+            AsmError {
+                line: 2,
+                kind: AsmErrorKind::UnknownAtom {
+                    name: "baz".to_owned()
+                }
+            }
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use beamcode::module::Module;
+use beamcode::stats::Stats;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Erlang BEAM file tool.
+#[derive(Parser)]
+#[clap(version)]
+enum Args {
+    /// Prints the symbolic listing of a `.beam` file.
+    Disasm {
+        beam_file_path: PathBuf,
+
+        /// Writes the listing to this file instead of the standard output.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Assembles a listing (generated by `disasm` or hand-written) into a `.beam` file.
+    Asm {
+        listing_path: PathBuf,
+
+        /// Output file path [default: LISTING_PATH with the extension `.beam`].
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Lists the chunks of a `.beam` file and their sizes.
+    Chunks { beam_file_path: PathBuf },
+
+    /// Checks that decoding and re-encoding the code of `.beam` files yields the same bytes.
+    Roundtrip {
+        #[clap(required = true)]
+        beam_file_paths: Vec<PathBuf>,
+    },
+
+    /// Prints statistics of a `.beam` file.
+    Stats {
+        beam_file_path: PathBuf,
+
+        /// Prints the statistics in JSON.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Prints the differences between the functions of two `.beam` files.
+    Diff {
+        old_beam_file_path: PathBuf,
+        new_beam_file_path: PathBuf,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    match Args::parse() {
+        Args::Disasm {
+            beam_file_path,
+            output,
+        } => {
+            let listing = beamcode::asm::disassemble(&Module::from_file(beam_file_path)?);
+            if let Some(output) = output {
+                std::fs::write(output, listing)?;
+            } else {
+                print!("{listing}");
+            }
+        }
+        Args::Asm {
+            listing_path,
+            output,
+        } => {
+            let module = beamcode::asm::assemble(&std::fs::read_to_string(&listing_path)?)?;
+            module.to_file(output.unwrap_or_else(|| listing_path.with_extension("beam")))?;
+        }
+        Args::Chunks { beam_file_path } => {
+            let beam = beam_file::RawBeamFile::from_file(beam_file_path)?;
+            for chunk in &beam.chunks {
+                println!(
+                    "{}\t{}",
+                    String::from_utf8_lossy(&chunk.id),
+                    chunk.data.len()
+                );
+            }
+        }
+        Args::Roundtrip { beam_file_paths } => {
+            let mut failures = 0;
+            for path in &beam_file_paths {
+                match roundtrip(path) {
+                    Ok(()) => println!("{}: ok", path.display()),
+                    Err(e) => {
+                        println!("{}: {e}", path.display());
+                        failures += 1;
+                    }
+                }
+            }
+            if failures > 0 {
+                anyhow::bail!("{failures} of {} files failed", beam_file_paths.len());
+            }
+        }
+        Args::Stats {
+            beam_file_path,
+            json,
+        } => {
+            let stats = Stats::from_module(&Module::from_file(beam_file_path)?)?;
+            if json {
+                println!("{}", stats.to_json());
+                return Ok(());
+            }
+            let mut instructions = stats.instructions.iter().collect::<Vec<_>>();
+            instructions.sort_by_key(|x| std::cmp::Reverse(x.1.count));
+            println!("instructions (count / encoded bytes):");
+            for (name, x) in instructions {
+                println!("  {name}: {} / {}", x.count, x.encoded_bytes);
+            }
+            println!("operands:");
+            for (kind, count) in &stats.term_kinds {
+                println!("  {kind:?}: {count}");
+            }
+            println!("registers (x / y):");
+            for x in &stats.functions {
+                println!("  {}: {} / {}", x.function, x.x_registers, x.y_registers);
+            }
+            println!("bignums: {}", stats.bignums);
+        }
+        Args::Diff {
+            old_beam_file_path,
+            new_beam_file_path,
+        } => {
+            let old = Module::from_file(old_beam_file_path)?;
+            let new = Module::from_file(new_beam_file_path)?;
+            print!("{}", beamcode::diff::diff_modules(&old, &new));
+        }
+    }
+    Ok(())
+}
+
+fn roundtrip(path: &PathBuf) -> anyhow::Result<()> {
+    let beam = beam_file::StandardBeamFile::from_file(path)?;
+    for chunk in beam.chunks {
+        if let beam_file::chunk::StandardChunk::Code(chunk) = chunk {
+            let report = beamcode::roundtrip::verify_roundtrip(&chunk.bytecode);
+            if let Some((index, offset, e)) = &report.decode_error {
+                anyhow::bail!(
+                    "failed to decode instruction {index} at byte {offset} ({} mismatches before it): {e}",
+                    report.mismatches.len()
+                );
+            }
+            if let Some(x) = report.first_mismatch() {
+                anyhow::bail!(
+                    "{} mismatches (first: instruction {} at bytes {:?}: {:?}, expected={:?}, actual={:?})",
+                    report.mismatches.len(),
+                    x.index,
+                    x.range,
+                    x.instruction,
+                    x.expected,
+                    x.actual
+                );
+            }
+            return Ok(());
+        }
+    }
+    anyhow::bail!("missing mandatory 'Code' chunk");
+}
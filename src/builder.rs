@@ -0,0 +1,352 @@
+//! Programmatic construction of modules.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::builder::ModuleBuilder;
+//! use beamcode::instruction::{CallExtOnly, Move};
+//! use beamcode::module::Module;
+//! use beamcode::term::{Register, Term};
+//!
+//! let mut module = ModuleBuilder::new("hello");
+//! let mut function = module.function("greet", 1);
+//! function.export();
+//! let print = function.import("io", "put_chars", 1);
+//! function
+//!     .push(Move {
+//!         src: Term::x(0),
+//!         dst: Register::x(1),
+//!     })
+//!     .push(CallExtOnly {
+//!         arity: 1,
+//!         destination: print,
+//!     });
+//! let bytes = module.to_bytes()?;
+//!
+//! let beam = beam_file::RawBeamFile::from_reader(&bytes[..])?;
+//! let ids = beam.chunks.iter().map(|x| &x.id).collect::<Vec<_>>();
+//! for id in [b"AtU8", b"Code", b"StrT", b"ImpT", b"ExpT"] {
+//!     assert!(ids.contains(&id));
+//! }
+//! let module = Module::from_reader(&bytes[..])?;
+//! assert_eq!(module.name(), Some("hello"));
+//! assert_eq!(module.exports.len(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::instruction::{self, FuncInfo, Instruction, IntCodeEnd, Line};
+use crate::module::{Code, Export, Import, Local, Module, OtherChunk};
+use crate::term::{Atom, Label, Literal};
+use crate::EncodeError;
+use std::collections::HashMap;
+
+/// The value of the `info_size` field of the `Code` chunk emitted by the compiler.
+pub(crate) const CODE_INFO_SIZE: u32 = 16;
+
+/// Builder of [`Module`].
+#[derive(Debug, Clone)]
+pub struct ModuleBuilder {
+    atoms: Vec<String>,
+    atom_indices: HashMap<String, Atom>,
+    literals: Vec<Vec<u8>>,
+    literal_indices: HashMap<Vec<u8>, Literal>,
+    imports: Vec<Import>,
+    import_indices: HashMap<Import, usize>,
+    exports: Vec<Export>,
+    functions: Vec<Local>,
+    instructions: Vec<Instruction>,
+    next_label: usize,
+}
+
+impl ModuleBuilder {
+    /// Makes a new builder of the module named `name`.
+    pub fn new(name: &str) -> Self {
+        let mut this = Self {
+            atoms: Vec::new(),
+            atom_indices: HashMap::new(),
+            literals: Vec::new(),
+            literal_indices: HashMap::new(),
+            imports: Vec::new(),
+            import_indices: HashMap::new(),
+            exports: Vec::new(),
+            functions: Vec::new(),
+            instructions: Vec::new(),
+            next_label: 1,
+        };
+        this.atom(name);
+        this
+    }
+
+    /// Returns the atom named `name`, adding it to the atom table if needed.
+    pub fn atom(&mut self, name: &str) -> Atom {
+        if let Some(atom) = self.atom_indices.get(name) {
+            return *atom;
+        }
+        self.atoms.push(name.to_owned());
+        let atom = Atom {
+            value: self.atoms.len(),
+        };
+        self.atom_indices.insert(name.to_owned(), atom);
+        atom
+    }
+
+    /// Returns the literal encoded in the External Term Format as `etf`
+    /// (including the leading version byte `131`), adding it to the literal table if needed.
+    pub fn literal(&mut self, etf: &[u8]) -> Literal {
+        if let Some(literal) = self.literal_indices.get(etf) {
+            return *literal;
+        }
+        let literal = Literal {
+            value: self.literals.len(),
+        };
+        self.literals.push(etf.to_owned());
+        self.literal_indices.insert(etf.to_owned(), literal);
+        literal
+    }
+
+    /// Returns the index of the import table entry for `module:function/arity`,
+    /// adding the entry if needed.
+    ///
+    /// The index can be used as the `destination` operand of `call_ext` and its variants.
+    pub fn import(&mut self, module: &str, function: &str, arity: usize) -> usize {
+        let import = Import {
+            module: self.atom(module),
+            function: self.atom(function),
+            arity,
+        };
+        if let Some(index) = self.import_indices.get(&import) {
+            return *index;
+        }
+        self.imports.push(import);
+        self.import_indices.insert(import, self.imports.len() - 1);
+        self.imports.len() - 1
+    }
+
+    /// Allocates a fresh label (the label is not placed anywhere).
+    pub fn new_label(&mut self) -> Label {
+        self.next_label += 1;
+        Label {
+            value: self.next_label - 1,
+        }
+    }
+
+    /// Starts a new function.
+    ///
+    /// This emits the function prologue (`label`, `line`, `func_info`, `label`).
+    /// Instructions pushed to the returned builder are appended to the module until
+    /// another function is started.
+    pub fn function(&mut self, name: &str, arity: usize) -> FunctionBuilder<'_> {
+        let function = self.atom(name);
+        let func_info_label = self.new_label();
+        let entry_label = self.new_label();
+        self.instructions.extend([
+            Instruction::Label(instruction::Label {
+                literal: func_info_label.value,
+            }),
+            Instruction::Line(Line { literal: 0 }),
+            Instruction::FuncInfo(FuncInfo {
+                module: Atom { value: 1 },
+                function,
+                arity,
+            }),
+            Instruction::Label(instruction::Label {
+                literal: entry_label.value,
+            }),
+        ]);
+        self.functions.push(Local {
+            function,
+            arity,
+            label: entry_label,
+        });
+        FunctionBuilder {
+            module: self,
+            func_info_label,
+            entry_label,
+        }
+    }
+
+    /// Finalizes the module.
+    ///
+    /// Functions which are not exported are registered in the local function table.
+    /// An empty `StrT` chunk is added as the compiler does, since some tools (e.g., `beam_lib`)
+    /// regard it as mandatory.
+    pub fn build(mut self) -> Module {
+        self.instructions
+            .push(Instruction::IntCodeEnd(IntCodeEnd {}));
+        let opcode_max = self
+            .instructions
+            .iter()
+            .map(|x| x.opcode())
+            .max()
+            .unwrap_or(0);
+        let locals = self
+            .functions
+            .iter()
+            .filter(|x| {
+                !self
+                    .exports
+                    .iter()
+                    .any(|e| e.function == x.function && e.arity == x.arity)
+            })
+            .copied()
+            .collect();
+        Module {
+            atoms: self.atoms,
+            imports: self.imports,
+            exports: self.exports,
+            locals,
+            lambdas: Vec::new(),
+            literals: self.literals,
+            code: Code {
+                info_size: CODE_INFO_SIZE,
+                version: 0,
+                opcode_max: u32::from(opcode_max),
+                label_count: self.next_label as u32,
+                function_count: self.functions.len() as u32,
+                instructions: self.instructions,
+            },
+            other_chunks: vec![OtherChunk {
+                id: *b"StrT",
+                data: Vec::new(),
+            }],
+        }
+    }
+
+    /// Finalizes the module and encodes it into the bytes of a `.beam` file.
+    pub fn to_bytes(self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+        self.build().to_writer(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Builder of a function (see [`ModuleBuilder::function()`]).
+#[derive(Debug)]
+pub struct FunctionBuilder<'a> {
+    module: &'a mut ModuleBuilder,
+    func_info_label: Label,
+    entry_label: Label,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    /// Returns the label just before `func_info` (a jump to this label raises a
+    /// `function_clause` error).
+    pub fn func_info_label(&self) -> Label {
+        self.func_info_label
+    }
+
+    /// Returns the entry label of this function (used for local calls).
+    pub fn entry_label(&self) -> Label {
+        self.entry_label
+    }
+
+    /// Adds this function to the export table.
+    pub fn export(&mut self) -> &mut Self {
+        let function = *self.module.functions.last().expect("unreachable");
+        if !self
+            .module
+            .exports
+            .iter()
+            .any(|x| x.function == function.function && x.arity == function.arity)
+        {
+            self.module.exports.push(Export {
+                function: function.function,
+                arity: function.arity,
+                label: function.label,
+            });
+        }
+        self
+    }
+
+    /// See [`ModuleBuilder::atom()`].
+    pub fn atom(&mut self, name: &str) -> Atom {
+        self.module.atom(name)
+    }
+
+    /// See [`ModuleBuilder::literal()`].
+    pub fn literal(&mut self, etf: &[u8]) -> Literal {
+        self.module.literal(etf)
+    }
+
+    /// See [`ModuleBuilder::import()`].
+    pub fn import(&mut self, module: &str, function: &str, arity: usize) -> usize {
+        self.module.import(module, function, arity)
+    }
+
+    /// See [`ModuleBuilder::new_label()`].
+    pub fn new_label(&mut self) -> Label {
+        self.module.new_label()
+    }
+
+    /// Places `label` (typically allocated by [`FunctionBuilder::new_label()`]) at the current position.
+    pub fn label(&mut self, label: Label) -> &mut Self {
+        self.push(instruction::Label {
+            literal: label.value,
+        })
+    }
+
+    /// Appends an instruction.
+    pub fn push<T: Into<Instruction>>(&mut self, instruction: T) -> &mut Self {
+        self.module.instructions.push(instruction.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{Call, CallExtOnly, IsNil, Jump, Move, Return};
+    use crate::term::{Register, Term};
+
+    #[test]
+    fn builder_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 1);
+        function.export();
+        let fail = function.func_info_label();
+        let nil = function.new_label();
+        function
+            .push(IsNil {
+                label: nil,
+                arg1: Term::x(0),
+            })
+            .push(Move {
+                src: Term::Atom(Atom { value: 0 }),
+                dst: Register::x(0),
+            })
+            .push(Return {})
+            .label(nil)
+            .push(Jump { label: fail });
+        let bar = function.entry_label();
+
+        let mut function = builder.function("baz", 0);
+        let length = function.import("erlang", "length", 1);
+        assert_eq!(function.import("erlang", "length", 1), length);
+        assert_eq!(function.atom("bar"), Atom { value: 2 });
+        function
+            .push(Move {
+                src: Term::Atom(Atom { value: 0 }),
+                dst: Register::x(0),
+            })
+            .push(Call {
+                arity: 1,
+                label: bar,
+            })
+            .push(CallExtOnly {
+                arity: 1,
+                destination: length,
+            });
+
+        let module = builder.build();
+        assert_eq!(module.atoms, ["foo", "bar", "baz", "erlang", "length"]);
+        assert_eq!(module.exports.len(), 1);
+        assert_eq!(module.locals.len(), 1);
+        assert_eq!(module.code.label_count, 6);
+        assert_eq!(module.code.function_count, 2);
+        assert_eq!(crate::validate::validate(&module), Ok(()));
+        assert_eq!(module.other_chunk(b"StrT"), Some(&[][..]));
+
+        let mut buf = Vec::new();
+        module.to_writer(&mut buf).unwrap();
+        assert_eq!(Module::from_reader(&buf[..]).unwrap(), module);
+    }
+}
@@ -0,0 +1,466 @@
+//! Call graphs.
+//!
+//! A [`CallGraph`] has a node for each function (identified by its [`Mfa`]) and an edge for
+//! each place where a function refers to another one:
+//!
+//! - local calls (`call`, `call_last`, `call_only`), whose labels are mapped to functions,
+//! - external calls (`call_ext`, `call_ext_last`, `call_ext_only`) through the import table,
+//! - BIF calls (`bif*`, `gc_bif*`) through the import table, and
+//! - fun creations (`make_fun*`) through the lambda table.
+//!
+//! Adding several modules to a graph makes an inter-module graph.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::call_graph::{CallGraph, Mfa};
+//! use beamcode::module::Module;
+//!
+//! let mut graph = CallGraph::new();
+//! for path in ["foo.beam", "bar.beam"] {
+//!     graph.add_module(&Module::from_file(path)?);
+//! }
+//! for caller in graph.callers(&Mfa::new("lists", "reverse", 2)) {
+//!     println!("{caller}");
+//! }
+//! # Ok::<(), beamcode::DecodeError>(())
+//! ```
+use crate::function::split_functions;
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::term::{Atom, Label, Term};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+
+/// Module, function name and arity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mfa {
+    pub module: String,
+    pub function: String,
+    pub arity: usize,
+}
+
+impl Mfa {
+    pub fn new(module: &str, function: &str, arity: usize) -> Self {
+        Self {
+            module: module.to_owned(),
+            function: function.to_owned(),
+            arity,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        format!(
+            r#"{{"module":{},"function":{},"arity":{}}}"#,
+            crate::json::string(&self.module),
+            crate::json::string(&self.function),
+            self.arity
+        )
+    }
+}
+
+impl std::fmt::Display for Mfa {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}/{}", self.module, self.function, self.arity)
+    }
+}
+
+/// Kinds of [`Edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EdgeKind {
+    /// `call`, `call_last` or `call_only`.
+    Local,
+
+    /// `call_ext`, `call_ext_last` or `call_ext_only`.
+    External,
+
+    /// `bif*` or `gc_bif*`.
+    Bif,
+
+    /// `make_fun*` (the callee is the function implementing the fun).
+    MakeFun,
+}
+
+impl EdgeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::External => "external",
+            Self::Bif => "bif",
+            Self::MakeFun => "make_fun",
+        }
+    }
+}
+
+/// An edge of [`CallGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Edge {
+    pub caller: Mfa,
+    pub callee: Mfa,
+    pub kind: EdgeKind,
+}
+
+/// Call graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    /// The functions defined in the added modules and the functions referred to by them.
+    pub nodes: BTreeSet<Mfa>,
+
+    pub edges: BTreeSet<Edge>,
+}
+
+impl CallGraph {
+    /// Makes an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the graph of `module`.
+    pub fn from_module(module: &Module) -> Self {
+        let mut this = Self::new();
+        this.add_module(module);
+        this
+    }
+
+    /// Adds the functions of `module` and the edges from them.
+    pub fn add_module(&mut self, module: &Module) {
+        let module_name = module.name().unwrap_or_default();
+        let instructions = &module.code.instructions;
+        let functions = split_functions(instructions);
+        let mfa = |function: Atom, arity| {
+            Mfa::new(
+                module_name,
+                module.atom_name(function).unwrap_or_default(),
+                arity,
+            )
+        };
+        let entries = functions
+            .iter()
+            .filter_map(|f| Some((f.entry_label?, mfa(f.name, f.arity))))
+            .collect::<HashMap<Label, Mfa>>();
+        let import = |index: usize| {
+            module.imports.get(index).map(|x| {
+                Mfa::new(
+                    module.atom_name(x.module).unwrap_or_default(),
+                    module.atom_name(x.function).unwrap_or_default(),
+                    x.arity,
+                )
+            })
+        };
+        let lambda = |index: usize| {
+            module
+                .lambdas
+                .get(index)
+                .and_then(|x| entries.get(&x.label))
+                .cloned()
+        };
+
+        for function in &functions {
+            let caller = mfa(function.name, function.arity);
+            self.nodes.insert(caller.clone());
+            for instruction in function.instructions(instructions) {
+                let (callee, kind) = match instruction {
+                    Instruction::Call(x) => (entries.get(&x.label).cloned(), EdgeKind::Local),
+                    Instruction::CallLast(x) => (
+                        x.arg2.to_label().and_then(|l| entries.get(&l).cloned()),
+                        EdgeKind::Local,
+                    ),
+                    Instruction::CallOnly(x) => (entries.get(&x.label).cloned(), EdgeKind::Local),
+                    Instruction::CallExt(x) => (import(x.destination), EdgeKind::External),
+                    Instruction::CallExtLast(x) => (import(x.destination), EdgeKind::External),
+                    Instruction::CallExtOnly(x) => (import(x.destination), EdgeKind::External),
+                    Instruction::Bif0(x) => (x.arg1.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::Bif1(x) => (x.arg2.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::Bif2(x) => (x.arg2.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::GcBif1(x) => (x.arg3.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::GcBif2(x) => (x.arg3.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::GcBif3(x) => (x.arg3.to_usize().and_then(import), EdgeKind::Bif),
+                    Instruction::MakeFun2(x) => {
+                        (x.arg1.to_usize().and_then(lambda), EdgeKind::MakeFun)
+                    }
+                    Instruction::MakeFun3(x) => {
+                        (x.arg1.to_usize().and_then(lambda), EdgeKind::MakeFun)
+                    }
+                    Instruction::MakeFun(x) => match &x.arg1 {
+                        Term::Label(label) => (entries.get(label).cloned(), EdgeKind::MakeFun),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                if let Some(callee) = callee {
+                    self.nodes.insert(callee.clone());
+                    self.edges.insert(Edge {
+                        caller: caller.clone(),
+                        callee,
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns the functions which refer to `callee`.
+    pub fn callers<'a>(&'a self, callee: &'a Mfa) -> impl 'a + Iterator<Item = &'a Mfa> {
+        let mut seen = BTreeSet::new();
+        self.edges
+            .iter()
+            .filter(move |x| &x.callee == callee && seen.insert(&x.caller))
+            .map(|x| &x.caller)
+    }
+
+    /// Returns the functions referred to by `caller`.
+    pub fn callees<'a>(&'a self, caller: &'a Mfa) -> impl 'a + Iterator<Item = &'a Mfa> {
+        let mut seen = BTreeSet::new();
+        self.edges
+            .iter()
+            .filter(move |x| &x.caller == caller && seen.insert(&x.callee))
+            .map(|x| &x.callee)
+    }
+
+    /// Returns the functions reachable from `roots` (e.g., the exported functions) through local
+    /// calls and fun creations, including `roots` themselves.
+    pub fn locally_reachable<I>(&self, roots: I) -> BTreeSet<Mfa>
+    where
+        I: IntoIterator<Item = Mfa>,
+    {
+        let mut callees = HashMap::<_, Vec<_>>::new();
+        for edge in &self.edges {
+            if matches!(edge.kind, EdgeKind::Local | EdgeKind::MakeFun) {
+                callees.entry(&edge.caller).or_default().push(&edge.callee);
+            }
+        }
+
+        let mut reachable = roots.into_iter().collect::<BTreeSet<_>>();
+        let mut stack = reachable.iter().cloned().collect::<Vec<_>>();
+        while let Some(caller) = stack.pop() {
+            for &callee in callees.get(&caller).into_iter().flatten() {
+                if reachable.insert(callee.clone()) {
+                    stack.push(callee.clone());
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns the subgraph consisting of the edges within a module.
+    pub fn intra_module(&self) -> Self {
+        self.subgraph(|x| x.caller.module == x.callee.module)
+    }
+
+    /// Returns the subgraph consisting of the edges between different modules.
+    pub fn inter_module(&self) -> Self {
+        self.subgraph(|x| x.caller.module != x.callee.module)
+    }
+
+    // Returns the subgraph consisting of the edges satisfying `f` and their endpoints.
+    fn subgraph<F>(&self, f: F) -> Self
+    where
+        F: Fn(&Edge) -> bool,
+    {
+        let edges = self
+            .edges
+            .iter()
+            .filter(|x| f(x))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let nodes = edges
+            .iter()
+            .flat_map(|x| [x.caller.clone(), x.callee.clone()])
+            .collect();
+        Self { nodes, edges }
+    }
+
+    /// Returns this graph in the Graphviz DOT language.
+    pub fn to_dot(&self) -> String {
+        let mut buf = String::from("digraph call_graph {\n");
+        for node in &self.nodes {
+            let _ = writeln!(buf, "  {};", dot_id(&node.to_string()));
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                buf,
+                "  {} -> {} [label={}];",
+                dot_id(&edge.caller.to_string()),
+                dot_id(&edge.callee.to_string()),
+                edge.kind.as_str()
+            );
+        }
+        buf.push_str("}\n");
+        buf
+    }
+
+    /// Returns this graph as a JSON object having `nodes` and `edges` arrays.
+    pub fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|x| x.to_json())
+            .collect::<Vec<_>>()
+            .join(",");
+        let edges = self
+            .edges
+            .iter()
+            .map(|x| {
+                format!(
+                    r#"{{"caller":{},"callee":{},"kind":"{}"}}"#,
+                    x.caller.to_json(),
+                    x.callee.to_json(),
+                    x.kind.as_str()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(r#"{{"nodes":[{nodes}],"edges":[{edges}]}}"#)
+    }
+}
+
+// Returns `s` as a double-quoted DOT identifier.
+fn dot_id(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len() + 2);
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Call, CallExtOnly, MakeFun3, Return};
+    use crate::module::Lambda;
+    use crate::term::List;
+
+    #[test]
+    fn call_graph_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 0);
+        let bar = function.entry_label();
+        let reverse = function.import("lists", "reverse", 2);
+        function.push(Return {});
+        let mut function = builder.function("baz", 0);
+        function
+            .push(Call {
+                arity: 0,
+                label: bar,
+            })
+            .push(MakeFun3 {
+                arg1: Term::Usize(0),
+                arg2: Term::x(0),
+                arg3: Term::List(List { items: Vec::new() }),
+            })
+            .push(CallExtOnly {
+                arity: 2,
+                destination: reverse,
+            });
+        let mut module = builder.build();
+        module.lambdas.push(Lambda {
+            function: Atom { value: 2 },
+            arity: 0,
+            label: bar,
+            index: 0,
+            num_free: 0,
+            old_uniq: 0,
+        });
+
+        let graph = CallGraph::from_module(&module);
+        let foo_bar = Mfa::new("foo", "bar", 0);
+        let foo_baz = Mfa::new("foo", "baz", 0);
+        let reverse = Mfa::new("lists", "reverse", 2);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.callers(&foo_bar).collect::<Vec<_>>(), [&foo_baz]);
+        assert_eq!(graph.callers(&reverse).collect::<Vec<_>>(), [&foo_baz]);
+        assert_eq!(graph.callees(&foo_bar).count(), 0);
+        assert_eq!(
+            graph.locally_reachable([foo_baz.clone()]),
+            [foo_bar.clone(), foo_baz.clone()].into_iter().collect()
+        );
+        assert_eq!(
+            graph.locally_reachable([foo_bar.clone()]),
+            [foo_bar.clone()].into_iter().collect()
+        );
+        assert_eq!(graph.intra_module().edges.len(), 2);
+        assert_eq!(graph.intra_module().nodes.len(), 2);
+        assert_eq!(graph.inter_module().nodes.len(), 2);
+        assert!(graph
+            .to_dot()
+            .contains(r#""foo:baz/0" -> "lists:reverse/2" [label=external];"#));
+        assert!(graph
+            .to_json()
+            .starts_with(r#"{"nodes":[{"module":"foo","function":"bar","arity":0},"#));
+    }
+
+    #[test]
+    fn add_modules_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 0);
+        let bar = function.entry_label();
+        let baz = function.import("baz", "qux", 0);
+        function
+            .push(Call {
+                arity: 0,
+                label: bar,
+            })
+            .push(Call {
+                arity: 0,
+                label: Label { value: 999 },
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: baz,
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: 999,
+            });
+        let foo = builder.build();
+        let mut builder = ModuleBuilder::new("baz");
+        builder.function("qux", 0).push(Return {});
+        let baz = builder.build();
+
+        let mut graph = CallGraph::from_module(&foo);
+        graph.add_module(&baz);
+        let foo_bar = Mfa::new("foo", "bar", 0);
+        let baz_qux = Mfa::new("baz", "qux", 0);
+        assert_eq!(graph.nodes.iter().collect::<Vec<_>>(), [&baz_qux, &foo_bar]);
+        assert_eq!(
+            graph.callees(&foo_bar).collect::<Vec<_>>(),
+            [&baz_qux, &foo_bar]
+        );
+        assert_eq!(graph.callers(&foo_bar).collect::<Vec<_>>(), [&foo_bar]);
+        assert_eq!(graph.intra_module().edges.len(), 1);
+        assert_eq!(graph.inter_module().edges.len(), 1);
+    }
+
+    #[test]
+    fn to_dot_quotes_ids_works() {
+        let mut graph = CallGraph::new();
+        let caller = Mfa::new("a\"b", "c\\", 0);
+        let callee = Mfa::new("d\ne", "f", 1);
+        graph.nodes.insert(caller.clone());
+        graph.nodes.insert(callee.clone());
+        graph.edges.insert(Edge {
+            caller,
+            callee,
+            kind: EdgeKind::External,
+        });
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph call_graph {
+  "a\"b:c\\/0";
+  "d\ne:f/1";
+  "a\"b:c\\/0" -> "d\ne:f/1" [label=external];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Detection of non-canonical operand encodings.
+//!
+//! The compact term encoding allows an integer to be encoded in several ways
+//! (e.g., `1` can be encoded in one byte, in two bytes using the 11-bit form,
+//! or in three bytes using the multi-byte form).
+//! [`find_non_canonical_encodings()`] reports every integer in bytecode whose encoding differs
+//! from the canonical (shortest) one produced by this crate, which is also what the Erlang compiler
+//! produces. Such encodings usually indicate a bug of the tool which generated the bytecode.
+//!
+//! Re-encoding the decoded instructions with [`encode_instructions()`](crate::encode_instructions)
+//! canonicalizes them.
+use crate::instruction::Instruction;
+use crate::term::{decode_index, decode_integer, decode_integer_length, encode_integer, TermKind};
+use crate::{Decode, DecodeError, DecodeLimits};
+use byteorder::ReadBytesExt as _;
+use num::BigInt;
+use std::ops::Range;
+
+/// A non-canonically encoded integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonCanonicalEncoding {
+    /// The index of the instruction containing the integer.
+    pub index: usize,
+
+    /// The byte range of the integer (including its tag byte) in the bytecode.
+    pub range: Range<usize>,
+
+    /// The kind of the term (or [`TermKind::Usize`] for the sizes of lists, etc).
+    pub term_kind: TermKind,
+
+    pub value: BigInt,
+
+    /// The bytes in the bytecode.
+    pub actual: Vec<u8>,
+
+    /// The canonical encoding.
+    pub canonical: Vec<u8>,
+
+    pub kind: NonCanonicalKind,
+}
+
+/// Kinds of [`NonCanonicalEncoding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonCanonicalKind {
+    /// The integer is encoded in a longer form than necessary
+    /// (e.g., `1` encoded in the 11-bit form).
+    WrongSizeClass,
+
+    /// The integer is encoded in the multi-byte form with redundant leading bytes.
+    ExcessBytes,
+}
+
+/// Decodes `bytecode` and returns the non-canonically encoded integers.
+pub fn find_non_canonical_encodings(
+    bytecode: &[u8],
+) -> Result<Vec<NonCanonicalEncoding>, DecodeError> {
+    let mut scanner = Scanner {
+        bytecode,
+        position: 0,
+        index: 0,
+        found: Vec::new(),
+    };
+    while scanner.position < bytecode.len() {
+        // Decodes the instruction first to validate it and know its end.
+        let mut reader = &bytecode[scanner.position..];
+        Instruction::decode(&mut reader)?;
+        let end = bytecode.len() - reader.len();
+
+        scanner.position += 1; // opcode
+        while scanner.position < end {
+            scanner.scan_operand()?;
+        }
+        scanner.index += 1;
+    }
+    Ok(scanner.found)
+}
+
+struct Scanner<'a> {
+    bytecode: &'a [u8],
+    position: usize,
+    index: usize,
+    found: Vec<NonCanonicalEncoding>,
+}
+
+impl<'a> Scanner<'a> {
+    fn scan_operand(&mut self) -> Result<(), DecodeError> {
+        let tag = self.read_u8()?;
+        match TermKind::from_tag(tag) {
+            TermKind::List => {
+                let size = self.scan_usize()?;
+                for _ in 0..size {
+                    self.scan_operand()?;
+                }
+            }
+            TermKind::AllocationList => {
+                let size = self.scan_usize()?;
+                for _ in 0..size {
+                    self.scan_usize()?;
+                    self.scan_usize()?;
+                }
+            }
+            TermKind::Float => {
+                self.position += 8;
+            }
+            TermKind::FloatingPointRegister | TermKind::Literal => {
+                self.scan_usize()?;
+            }
+            TermKind::TypedRegister => {
+                self.scan_operand()?;
+                self.scan_usize()?;
+            }
+            TermKind::Unknown(tag) => return Err(DecodeError::UnknownTermTag { tag }),
+            kind => {
+                self.scan_integer(tag, kind)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_usize(&mut self) -> Result<usize, DecodeError> {
+        let tag = self.read_u8()?;
+        TermKind::from_tag(tag).expect(&[TermKind::Usize])?;
+        let value = self.scan_integer(tag, TermKind::Usize)?;
+        decode_index(TermKind::Usize, value)
+    }
+
+    // `tag` has already been read.
+    fn scan_integer(&mut self, tag: u8, term_kind: TermKind) -> Result<BigInt, DecodeError> {
+        let start = self.position - 1;
+        let (value, length_prefix) = if size_class(tag) == 3 {
+            // The length prefix is a `u` term, so its encoding is checked on its own.
+            let prefix_start = self.position;
+            let byte_size = self.scan_usize()?.saturating_add(9);
+            let prefix = prefix_start..self.position;
+            let bytes = self
+                .bytecode
+                .get(self.position..)
+                .and_then(|x| x.get(..byte_size))
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+            self.position += byte_size;
+            (BigInt::from_signed_bytes_be(bytes), Some(prefix))
+        } else {
+            let mut reader = &self.bytecode[self.position..];
+            let value = decode_integer(tag, &mut reader, &DecodeLimits::default())?;
+            self.position = self.bytecode.len() - reader.len();
+            (value, None)
+        };
+
+        let mut canonical = Vec::new();
+        encode_integer(tag & 0b111, &value, &mut canonical)
+            .expect("encoding into a `Vec` never fails");
+        let actual = &self.bytecode[start..self.position];
+        let is_canonical = match length_prefix {
+            // Compares the encodings without their length prefixes
+            // not to report a non-canonical prefix twice.
+            Some(prefix) => {
+                size_class(canonical[0]) == 3
+                    && actual[0] == canonical[0]
+                    && self.bytecode[prefix.end..self.position] == *value_bytes(&canonical)
+            }
+            None => actual == canonical,
+        };
+        if !is_canonical {
+            let kind = if size_class(actual[0]) == size_class(canonical[0]) {
+                NonCanonicalKind::ExcessBytes
+            } else {
+                NonCanonicalKind::WrongSizeClass
+            };
+            self.found.push(NonCanonicalEncoding {
+                index: self.index,
+                range: start..self.position,
+                term_kind,
+                value: value.clone(),
+                actual: actual.to_owned(),
+                canonical,
+                kind,
+            });
+        }
+        Ok(value)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let mut reader = &self.bytecode[self.position..];
+        let b = reader.read_u8()?;
+        self.position += 1;
+        Ok(b)
+    }
+}
+
+// 0: 4-bit, 1: 11-bit, 2: multi-byte (up to 8 bytes), 3: multi-byte (more than 8 bytes).
+fn size_class(tag: u8) -> u8 {
+    if tag & 0b1000 == 0 {
+        0
+    } else if tag & 0b1_0000 == 0 {
+        1
+    } else if tag >> 5 != 0b111 {
+        2
+    } else {
+        3
+    }
+}
+
+// The bytes of an integer in the long form following its length prefix.
+fn value_bytes(encoded: &[u8]) -> &[u8] {
+    let mut reader = &encoded[1..];
+    decode_integer_length(&mut reader, &DecodeLimits::default())
+        .expect("the canonical encoding has a valid length prefix");
+    reader
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_non_canonical_encodings_works() {
+        let bytecode = [
+            19, // return
+            // {move,{x,0},{x,1}} where {x,1} is encoded in the 11-bit form
+            64,
+            3,
+            0b0000_1011,
+            1,
+            // {test_heap,2048,0} where 2048 has a redundant leading zero byte
+            16,
+            0b0011_1000,
+            0,
+            8,
+            0,
+            0,
+        ];
+        let found = find_non_canonical_encodings(&bytecode).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found[0],
+            NonCanonicalEncoding {
+                index: 1,
+                range: 3..5,
+                term_kind: TermKind::XRegister,
+                value: BigInt::from(1),
+                actual: vec![0b0000_1011, 1],
+                canonical: vec![19],
+                kind: NonCanonicalKind::WrongSizeClass,
+            }
+        );
+        assert_eq!(found[1].index, 2);
+        assert_eq!(found[1].range, 6..10);
+        assert_eq!(found[1].canonical, [0b0001_1000, 8, 0]);
+        assert_eq!(found[1].kind, NonCanonicalKind::ExcessBytes);
+    }
+
+    #[test]
+    fn find_non_canonical_length_prefix_works() {
+        // {move,{integer,1 bsl 64},{x,0}} where the length prefix (0) is encoded in the 11-bit form
+        let bytecode = [
+            64,
+            0b1111_1001,
+            0b0000_1000,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            3,
+        ];
+        let found = find_non_canonical_encodings(&bytecode).unwrap();
+        assert_eq!(
+            found,
+            [NonCanonicalEncoding {
+                index: 0,
+                range: 2..4,
+                term_kind: TermKind::Usize,
+                value: BigInt::from(0),
+                actual: vec![0b0000_1000, 0],
+                canonical: vec![0],
+                kind: NonCanonicalKind::WrongSizeClass,
+            }]
+        );
+
+        // The same integer with a redundant leading zero byte
+        let bytecode = [
+            64,
+            0b1111_1001,
+            0b0001_0000,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            3,
+        ];
+        let found = find_non_canonical_encodings(&bytecode).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].range, 1..13);
+        assert_eq!(found[0].term_kind, TermKind::Integer);
+        assert_eq!(found[0].value, BigInt::from(1u128 << 64));
+        assert_eq!(
+            found[0].canonical,
+            [0b1111_1001, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(found[0].kind, NonCanonicalKind::ExcessBytes);
+
+        let bytecode = [64, 0b1111_1001, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3];
+        assert_eq!(find_non_canonical_encodings(&bytecode).unwrap(), []);
+    }
+
+    #[test]
+    fn find_non_canonical_list_operands_works() {
+        // {select_val,{x,0},{f,1},{list,[{integer,1},{f,2}]}} where the list size and {f,2}
+        // are encoded in the 11-bit form
+        let bytecode = [
+            59,
+            3,
+            0b0001_0101,
+            0b0001_0111,
+            0b0000_1000,
+            2,
+            0b0001_0001,
+            0b0000_1101,
+            2,
+        ];
+        let found = find_non_canonical_encodings(&bytecode).unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|x| (x.range.clone(), x.term_kind, x.canonical.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (4..6, TermKind::Usize, vec![0b0010_0000]),
+                (7..9, TermKind::Label, vec![0b0010_0101]),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_non_canonical_allocation_list_operands_works() {
+        // {test_heap,{alloc,[{words,2048}]},0} where the list size is encoded in the 11-bit form
+        // and 2048 has a redundant leading zero byte
+        let bytecode = [16, 0b0011_0111, 0b0000_1000, 1, 0, 0b0011_1000, 0, 8, 0, 0];
+        let found = find_non_canonical_encodings(&bytecode).unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|x| (x.range.clone(), x.term_kind, x.kind))
+                .collect::<Vec<_>>(),
+            [
+                (2..4, TermKind::Usize, NonCanonicalKind::WrongSizeClass),
+                (5..9, TermKind::Usize, NonCanonicalKind::ExcessBytes),
+            ]
+        );
+    }
+}
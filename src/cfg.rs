@@ -0,0 +1,217 @@
+//! Control flow graphs.
+use crate::instruction::Instruction;
+use crate::term::Label;
+use crate::{Visit, Visitor};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A basic block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The labels defined at the beginning of this block.
+    pub labels: Vec<Label>,
+
+    /// The indices of the instructions of this block.
+    pub range: Range<usize>,
+
+    /// The indices of the successor blocks.
+    pub successors: Vec<usize>,
+
+    /// The indices of the predecessor blocks.
+    pub predecessors: Vec<usize>,
+}
+
+/// A control flow graph of a sequence of instructions (typically a function).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+
+    /// Map from labels to the indices of the blocks which define them.
+    pub label_to_block: BTreeMap<Label, usize>,
+}
+
+impl Cfg {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut blocks = Vec::new();
+        let mut label_to_block = BTreeMap::new();
+        let mut block = BasicBlock {
+            labels: Vec::new(),
+            range: 0..0,
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        };
+        for (i, instruction) in instructions.iter().enumerate() {
+            if let Instruction::Label(x) = instruction {
+                let only_labels = instructions[block.range.clone()]
+                    .iter()
+                    .all(|x| matches!(x, Instruction::Label(_)));
+                if !only_labels {
+                    block.range.end = i;
+                    blocks.push(block);
+                    block = BasicBlock {
+                        labels: Vec::new(),
+                        range: i..i,
+                        successors: Vec::new(),
+                        predecessors: Vec::new(),
+                    };
+                }
+                label_to_block.insert(Label { value: x.literal }, blocks.len());
+                block.labels.push(Label { value: x.literal });
+            }
+            block.range.end = i + 1;
+            if instruction.is_terminator() || !instruction.branch_targets().is_empty() {
+                blocks.push(block);
+                block = BasicBlock {
+                    labels: Vec::new(),
+                    range: i + 1..i + 1,
+                    successors: Vec::new(),
+                    predecessors: Vec::new(),
+                };
+            }
+        }
+        if !block.range.is_empty() || !block.labels.is_empty() {
+            blocks.push(block);
+        }
+
+        let n = blocks.len();
+        for (i, block) in blocks.iter_mut().enumerate() {
+            let Some(last) = block.range.clone().last().map(|j| &instructions[j]) else {
+                if i + 1 < n {
+                    block.successors.push(i + 1);
+                }
+                continue;
+            };
+            for label in last.branch_targets() {
+                if let Some(&j) = label_to_block.get(&label) {
+                    if !block.successors.contains(&j) {
+                        block.successors.push(j);
+                    }
+                }
+            }
+            if !last.is_terminator() && i + 1 < n && !block.successors.contains(&(i + 1)) {
+                block.successors.push(i + 1);
+            }
+        }
+        for i in 0..n {
+            for j in blocks[i].successors.clone() {
+                blocks[j].predecessors.push(i);
+            }
+        }
+        Self {
+            blocks,
+            label_to_block,
+        }
+    }
+
+    /// Returns the index of the block containing the `index`-th instruction.
+    pub fn block_of(&self, index: usize) -> Option<usize> {
+        self.blocks
+            .binary_search_by(|b| {
+                if b.range.end <= index {
+                    std::cmp::Ordering::Less
+                } else if index < b.range.start {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()
+    }
+}
+
+impl Instruction {
+    /// Returns `true` if the control never falls through to the next instruction.
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            Self::Jump(_)
+                | Self::Return(_)
+                | Self::CallLast(_)
+                | Self::CallOnly(_)
+                | Self::CallExtLast(_)
+                | Self::CallExtOnly(_)
+                | Self::ApplyLast(_)
+                | Self::SelectVal(_)
+                | Self::SelectTupleArity(_)
+                | Self::LoopRecEnd(_)
+                | Self::Wait(_)
+                | Self::FuncInfo(_)
+                | Self::Badmatch(_)
+                | Self::Badrecord(_)
+                | Self::CaseEnd(_)
+                | Self::IfEnd(_)
+                | Self::TryCaseEnd(_)
+                | Self::Raise(_)
+                | Self::RawRaise(_)
+                | Self::IntCodeEnd(_)
+        )
+    }
+
+    /// Returns the labels to which this instruction may transfer the control
+    /// (within the same function).
+    ///
+    /// Labels of local calls and of `recv_mark` / `recv_set` are not included.
+    /// The label `0` (no fail label) is not included either.
+    pub fn branch_targets(&self) -> Vec<Label> {
+        if matches!(
+            self,
+            Self::Call(_)
+                | Self::CallLast(_)
+                | Self::CallOnly(_)
+                | Self::RecvMark(_)
+                | Self::RecvSet(_)
+                | Self::Label(_)
+        ) {
+            return Vec::new();
+        }
+
+        #[derive(Default)]
+        struct Collector(Vec<Label>);
+        impl Visitor for Collector {
+            fn visit_label(&mut self, label: &Label) {
+                if label.value != 0 && !self.0.contains(label) {
+                    self.0.push(*label);
+                }
+            }
+        }
+        let mut collector = Collector::default();
+        self.visit(&mut collector);
+        collector.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, IsNil, Jump, Return};
+    use crate::term::{Term, XRegister};
+
+    #[test]
+    fn cfg_works() {
+        let label = |literal| Instruction::Label(instruction::Label { literal });
+        let instructions = vec![
+            label(1),
+            Instruction::IsNil(IsNil {
+                label: Label { value: 2 },
+                arg1: Term::XRegister(XRegister { value: 0, ty: None }),
+            }),
+            Instruction::Jump(Jump {
+                label: Label { value: 3 },
+            }),
+            label(2),
+            label(3),
+            Instruction::Return(Return {}),
+        ];
+        let cfg = Cfg::new(&instructions);
+        assert_eq!(cfg.blocks.len(), 3);
+        assert_eq!(cfg.blocks[0].range, 0..2);
+        assert_eq!(cfg.blocks[0].successors, vec![2, 1]);
+        assert_eq!(cfg.blocks[1].successors, vec![2]);
+        assert_eq!(
+            cfg.blocks[2].labels,
+            vec![Label { value: 2 }, Label { value: 3 }]
+        );
+        assert_eq!(cfg.blocks[2].predecessors, vec![0, 1]);
+        assert_eq!(cfg.block_of(4), Some(2));
+    }
+}
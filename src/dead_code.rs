@@ -0,0 +1,225 @@
+//! Dead code detection.
+//!
+//! [`find_dead_code()`] reports the following (per function):
+//!
+//! - labels which are never referred to (except the labels of function headers),
+//! - basic blocks which are unreachable from the entry label of the function, and
+//! - local functions which are not reachable from the exported functions through local calls
+//!   (or `make_fun*`), including recursive functions which only call themselves or each other.
+//!
+//! Note that a function referred to only by the `on_load` attribute is reported as uncalled.
+use crate::call_graph::{CallGraph, Mfa};
+use crate::cfg::Cfg;
+use crate::function::{split_functions, Function};
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::optimize::referred_labels;
+use crate::term::Label;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// A piece of dead code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadCode {
+    /// The name and arity of the function (e.g., `"foo/2"`).
+    pub function: String,
+
+    pub kind: DeadCodeKind,
+}
+
+/// Kinds of [`DeadCode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadCodeKind {
+    /// A label which is never referred to.
+    UnreferencedLabel { label: Label },
+
+    /// A basic block which is unreachable from the entry label.
+    ///
+    /// `range` is the indices of the instructions in [`Code::instructions`](crate::module::Code).
+    UnreachableBlock { range: Range<usize> },
+
+    /// A function which is not reachable from the exported functions.
+    UncalledFunction,
+}
+
+/// Finds dead code in `module`.
+pub fn find_dead_code(module: &Module) -> Vec<DeadCode> {
+    let instructions = &module.code.instructions;
+    let mut referred = referred_labels(instructions);
+    referred.extend(module.exports.iter().map(|x| x.label));
+    referred.extend(module.locals.iter().map(|x| x.label));
+    referred.extend(module.lambdas.iter().map(|x| x.label));
+
+    let module_name = module.name().unwrap_or_default();
+    let exports = module.exports.iter().map(|x| {
+        let name = module.atom_name(x.function).unwrap_or_default();
+        Mfa::new(module_name, name, x.arity)
+    });
+    let reachable = CallGraph::from_module(module).locally_reachable(exports);
+
+    let mut dead_code = Vec::new();
+    for function in split_functions(instructions) {
+        let name = module.atom_name(function.name).unwrap_or_default();
+        let report = |kind| DeadCode {
+            function: format!("{}/{}", name, function.arity),
+            kind,
+        };
+
+        if !reachable.contains(&Mfa::new(module_name, name, function.arity)) {
+            dead_code.push(report(DeadCodeKind::UncalledFunction));
+        }
+        for label in defined_labels(module, &function) {
+            if !referred.contains(&label)
+                && Some(label) != function.func_info_label
+                && Some(label) != function.entry_label
+            {
+                dead_code.push(report(DeadCodeKind::UnreferencedLabel { label }));
+            }
+        }
+        for range in unreachable_blocks(module, &function) {
+            dead_code.push(report(DeadCodeKind::UnreachableBlock { range }));
+        }
+    }
+    dead_code
+}
+
+fn defined_labels(module: &Module, function: &Function) -> Vec<Label> {
+    function
+        .instructions(&module.code.instructions)
+        .iter()
+        .filter_map(|x| match x {
+            Instruction::Label(x) => Some(Label { value: x.literal }),
+            _ => None,
+        })
+        .collect()
+}
+
+// The function header block (`label`, `line`, `func_info`) is not reported.
+fn unreachable_blocks(module: &Module, function: &Function) -> Vec<Range<usize>> {
+    let instructions = function.instructions(&module.code.instructions);
+    let cfg = Cfg::new(instructions);
+    let Some(&entry) = function
+        .entry_label
+        .as_ref()
+        .and_then(|x| cfg.label_to_block.get(x))
+    else {
+        return Vec::new();
+    };
+
+    let mut reachable = BTreeSet::from([entry]);
+    let mut stack = vec![entry];
+    while let Some(i) = stack.pop() {
+        for &j in &cfg.blocks[i].successors {
+            if reachable.insert(j) {
+                stack.push(j);
+            }
+        }
+    }
+    cfg.blocks
+        .iter()
+        .enumerate()
+        .filter(|(i, block)| {
+            !reachable.contains(i)
+                && !block.range.is_empty()
+                && !instructions[block.range.clone()]
+                    .iter()
+                    .any(|x| matches!(x, Instruction::FuncInfo(_)))
+        })
+        .map(|(_, block)| {
+            block.range.start + function.range.start..block.range.end + function.range.start
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Call, CallOnly, Jump, Return};
+
+    #[test]
+    fn find_dead_code_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("main", 0);
+        function.export();
+        let unused = function.new_label();
+        let unreachable = function.new_label();
+        function
+            .push(Return {})
+            .label(unused)
+            .push(Return {})
+            .label(unreachable)
+            .push(Jump { label: unreachable });
+        let mut function = builder.function("dead", 0);
+        let dead = function.entry_label();
+        function.push(Call {
+            arity: 0,
+            label: dead,
+        });
+        function.push(Return {});
+        let module = builder.build();
+
+        assert_eq!(
+            find_dead_code(&module),
+            [
+                DeadCode {
+                    function: "main/0".to_owned(),
+                    kind: DeadCodeKind::UnreferencedLabel { label: unused },
+                },
+                DeadCode {
+                    function: "main/0".to_owned(),
+                    kind: DeadCodeKind::UnreachableBlock { range: 5..7 },
+                },
+                DeadCode {
+                    function: "main/0".to_owned(),
+                    kind: DeadCodeKind::UnreachableBlock { range: 7..9 },
+                },
+                DeadCode {
+                    function: "dead/0".to_owned(),
+                    kind: DeadCodeKind::UncalledFunction,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn find_uncalled_recursive_functions_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("used", 0);
+        let used = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("main", 0);
+        function.export().push(CallOnly {
+            arity: 0,
+            label: used,
+        });
+        let mut function = builder.function("ping", 0);
+        let ping = function.entry_label();
+        function.push(CallOnly {
+            arity: 0,
+            label: ping,
+        });
+        let mut function = builder.function("pong", 0);
+        function
+            .push(Call {
+                arity: 0,
+                label: used,
+            })
+            .push(CallOnly {
+                arity: 0,
+                label: ping,
+            });
+        let module = builder.build();
+
+        assert_eq!(
+            find_dead_code(&module)
+                .into_iter()
+                .map(|x| (x.function, x.kind))
+                .collect::<Vec<_>>(),
+            [
+                ("ping/0".to_owned(), DeadCodeKind::UncalledFunction),
+                ("pong/0".to_owned(), DeadCodeKind::UncalledFunction),
+            ]
+        );
+    }
+}
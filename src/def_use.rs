@@ -0,0 +1,1019 @@
+//! Register read / write (def-use) information of instructions.
+//!
+//! [`Instruction::uses()`] returns the registers an instruction reads and
+//! [`Instruction::defs()`] returns the registers it writes (or clobbers).
+//! Besides the explicit operands, the following implicit effects are taken into account:
+//!
+//! | Instruction                                             | Uses                       | Defs                                   |
+//! |---------------------------------------------------------|----------------------------|----------------------------------------|
+//! | `call`, `call_ext`, `send` (`Arity=2`)                  | `x0..x{Arity-1}`           | `x0`, clobbers `x1..`                  |
+//! | `call_last`, `call_only`, `call_ext_last`, `call_ext_only` | `x0..x{Arity-1}`        | clobbers all y (for `*_last`)          |
+//! | `call_fun`                                              | `x0..x{Arity}`             | `x0`, clobbers `x1..`                  |
+//! | `call_fun2`                                             | `x0..x{Arity-1}`, `Func`   | `x0`, clobbers `x1..`                  |
+//! | `apply`                                                 | `x0..x{Arity+1}`           | `x0`, clobbers `x1..`                  |
+//! | `apply_last`                                            | `x0..x{Arity+1}`           | clobbers all y                         |
+//! | `bif0`, `bif1`, `bif2`                                  | arguments                  | destination                            |
+//! | `gc_bif1`, `gc_bif2`, `gc_bif3`                         | arguments, `x0..x{Live-1}` | destination, clobbers `x{Live}..`      |
+//! | `allocate`, `allocate_heap`                             | `x0..x{Live-1}`            | clobbers all y and `x{Live}..`         |
+//! | `allocate_zero`, `allocate_heap_zero`                   | `x0..x{Live-1}`            | `y0..y{N-1}`, clobbers all y and `x{Live}..` |
+//! | `test_heap`                                             | `x0..x{Live-1}`            | clobbers `x{Live}..`                   |
+//! | `deallocate`                                            |                            | clobbers all y                         |
+//! | `trim`                                                  |                            | clobbers all y (see below)             |
+//! | `return`                                                | `x0`                       |                                        |
+//! | `func_info`                                             | `x0..x{Arity-1}`           |                                        |
+//! | `make_fun2`                                             | (see below)                | `x0`, clobbers `x1..`                  |
+//! | `loop_rec`                                              |                            | `x0`                                   |
+//! | `wait`, `wait_timeout`                                  | timeout                    | clobbers all x                         |
+//! | `try_case`                                              | the y register             | `x0..x2`, the y register, clobbers `x3..` |
+//! | `catch_end`                                             | the y register             | `x0`, the y register, clobbers `x1..`  |
+//! | `try_end`                                               |                            | the y register                         |
+//! | `build_stacktrace`                                      | `x0`                       | `x0`, clobbers `x1..`                  |
+//! | `raw_raise`                                             | `x0..x2`                   |                                        |
+//! | other instructions having a `live` operand              | operands, `x0..x{Live-1}`  | destination, clobbers `x{Live}..`      |
+//!
+//! Note that it is impossible to express the register renaming performed by `trim` using sets.
+//! It is reported as clobbering all y registers, so analyses need to handle it specially.
+//! The number of the free variables of `make_fun2` is defined by the lambda table of the module,
+//! thus it is reported as using no registers.
+//!
+//! Deprecated instructions which are no longer emitted by the compiler are reported as having no
+//! effects.
+use crate::instruction::Instruction;
+use crate::term::{Allocation, FloatingPointRegister, List, Register, Term, XRegister, YRegister};
+use std::collections::BTreeSet;
+
+/// A set of registers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    /// X and Y registers (without type information).
+    pub registers: BTreeSet<Register>,
+
+    pub float_registers: BTreeSet<FloatingPointRegister>,
+
+    /// If `Some(n)`, all x registers numbered `n` or greater are also included in this set.
+    pub x_registers_from: Option<usize>,
+
+    /// If `Some(n)`, all y registers numbered `n` or greater are also included in this set.
+    pub y_registers_from: Option<usize>,
+}
+
+impl RegisterSet {
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+            && self.float_registers.is_empty()
+            && self.x_registers_from.is_none()
+            && self.y_registers_from.is_none()
+    }
+
+    pub fn contains(&self, register: &Register) -> bool {
+        match register.untyped() {
+            Register::X(x) if self.x_registers_from.is_some_and(|n| n <= x.value) => true,
+            Register::Y(x) if self.y_registers_from.is_some_and(|n| n <= x.value) => true,
+            register => self.registers.contains(&register),
+        }
+    }
+
+    pub fn contains_float(&self, register: &FloatingPointRegister) -> bool {
+        self.float_registers.contains(register)
+    }
+
+    pub fn insert(&mut self, register: &Register) {
+        self.registers.insert(register.untyped());
+    }
+
+    pub fn insert_float(&mut self, register: FloatingPointRegister) {
+        self.float_registers.insert(register);
+    }
+
+    /// Inserts the registers (including those in lists) contained in `term`.
+    pub fn insert_term(&mut self, term: &Term) {
+        match term {
+            Term::List(x) => self.insert_list(x),
+            Term::FloatingPointRegister(x) => self.insert_float(*x),
+            _ => {
+                if let Some(x) = term.to_register() {
+                    self.insert(&x);
+                }
+            }
+        }
+    }
+
+    fn insert_list(&mut self, list: &List) {
+        for x in &list.items {
+            self.insert_term(x);
+        }
+    }
+
+    /// Inserts `x0..x{n-1}`.
+    pub fn insert_x_registers(&mut self, n: usize) {
+        for value in 0..n {
+            self.insert(&Register::X(XRegister { value, ty: None }));
+        }
+    }
+
+    /// Inserts `y0..y{n-1}`.
+    pub fn insert_y_registers(&mut self, n: usize) {
+        for value in 0..n {
+            self.insert(&Register::Y(YRegister { value, ty: None }));
+        }
+    }
+
+    /// Inserts all x registers numbered `n` or greater.
+    pub fn insert_x_registers_from(&mut self, n: usize) {
+        self.x_registers_from = Some(self.x_registers_from.map_or(n, |m| m.min(n)));
+    }
+
+    /// Inserts all y registers numbered `n` or greater.
+    pub fn insert_y_registers_from(&mut self, n: usize) {
+        self.y_registers_from = Some(self.y_registers_from.map_or(n, |m| m.min(n)));
+    }
+
+    /// Returns the numbers of the x registers explicitly contained in this set.
+    pub fn x_registers(&self) -> impl '_ + Iterator<Item = usize> {
+        self.registers.iter().filter_map(|x| match x {
+            Register::X(x) => Some(x.value),
+            _ => None,
+        })
+    }
+
+    /// Returns the numbers of the y registers explicitly contained in this set.
+    pub fn y_registers(&self) -> impl '_ + Iterator<Item = usize> {
+        self.registers.iter().filter_map(|x| match x {
+            Register::Y(x) => Some(x.value),
+            _ => None,
+        })
+    }
+}
+
+impl Instruction {
+    /// Returns the registers read by this instruction.
+    ///
+    /// See [the module documentation](self) for the implicit uses.
+    pub fn uses(&self) -> RegisterSet {
+        self.uses_impl(true)
+    }
+
+    /// Same as [`Instruction::uses()`] except that `x0..x{Live-1}` implied by `live` operands are
+    /// not included unless they are also explicit operands.
+    pub fn explicit_uses(&self) -> RegisterSet {
+        self.uses_impl(false)
+    }
+
+    fn uses_impl(&self, include_live: bool) -> RegisterSet {
+        let mut set = RegisterSet::default();
+        match self {
+            Self::Call(x) => set.insert_x_registers(x.arity),
+            Self::CallLast(x) => set.insert_x_registers(x.arg1.to_usize().unwrap_or(0)),
+            Self::CallOnly(x) => set.insert_x_registers(x.arity),
+            Self::CallExt(x) => set.insert_x_registers(x.arity),
+            Self::CallExtLast(x) => set.insert_x_registers(x.arity),
+            Self::CallExtOnly(x) => set.insert_x_registers(x.arity),
+            Self::CallFun(x) => set.insert_x_registers(x.arg1.to_usize().unwrap_or(0) + 1),
+            Self::CallFun2(x) => {
+                set.insert_x_registers(x.arg2.to_usize().unwrap_or(0));
+                set.insert_term(&x.arg3);
+            }
+            Self::Apply(x) => set.insert_x_registers(x.arg1.to_usize().unwrap_or(0) + 2),
+            Self::ApplyLast(x) => set.insert_x_registers(x.arg1.to_usize().unwrap_or(0) + 2),
+            Self::Send(_) => set.insert_x_registers(2),
+            Self::Return(_) => set.insert_x_registers(1),
+            Self::FuncInfo(x) => set.insert_x_registers(x.arity),
+            Self::BuildStacktrace(_) => set.insert_x_registers(1),
+            Self::RawRaise(_) => set.insert_x_registers(3),
+            Self::Bif1(x) => set.insert_term(&x.arg3),
+            Self::Bif2(x) => {
+                set.insert_term(&x.arg3);
+                set.insert_term(&x.arg4);
+            }
+            Self::GcBif1(x) => set.insert_term(&x.arg4),
+            Self::GcBif2(x) => {
+                set.insert_term(&x.arg4);
+                set.insert_term(&x.arg5);
+            }
+            Self::GcBif3(x) => {
+                set.insert_term(&x.arg4);
+                set.insert_term(&x.arg5);
+                set.insert_term(&x.arg6);
+            }
+            Self::WaitTimeout(x) => set.insert_term(&x.arg2),
+            Self::IsLt(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::IsGe(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::IsEq(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::IsNe(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::IsEqExact(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::IsNeExact(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::IsInteger(x) => set.insert_term(&x.arg1),
+            Self::IsFloat(x) => set.insert_term(&x.arg1),
+            Self::IsNumber(x) => set.insert_term(&x.arg1),
+            Self::IsAtom(x) => set.insert_term(&x.arg1),
+            Self::IsPid(x) => set.insert_term(&x.arg1),
+            Self::IsReference(x) => set.insert_term(&x.arg1),
+            Self::IsPort(x) => set.insert_term(&x.arg1),
+            Self::IsNil(x) => set.insert_term(&x.arg1),
+            Self::IsBinary(x) => set.insert_term(&x.arg1),
+            Self::IsList(x) => set.insert_term(&x.arg1),
+            Self::IsNonemptyList(x) => set.insert_term(&x.arg1),
+            Self::IsTuple(x) => set.insert_term(&x.arg1),
+            Self::IsFunction(x) => set.insert_term(&x.arg2),
+            Self::IsBoolean(x) => set.insert_term(&x.arg2),
+            Self::IsBitstr(x) => set.insert_term(&x.arg2),
+            Self::IsMap(x) => set.insert_term(&x.arg2),
+            Self::IsFunction2(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::TestArity(x) => set.insert_term(&x.arg1),
+            Self::IsTaggedTuple(x) => set.insert(&x.register),
+            Self::SelectVal(x) => set.insert_term(&x.arg),
+            Self::SelectTupleArity(x) => set.insert_term(&x.arg1),
+            Self::Move(x) => set.insert_term(&x.src),
+            Self::Swap(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::GetList(x) => set.insert_term(&x.source),
+            Self::GetHd(x) => set.insert_term(&x.arg1),
+            Self::GetTl(x) => set.insert_term(&x.arg1),
+            Self::GetTupleElement(x) => set.insert(&x.source),
+            Self::SetTupleElement(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::PutList(x) => {
+                set.insert_term(&x.head);
+                set.insert_term(&x.tail);
+            }
+            Self::Put(x) => set.insert_term(&x.arg1),
+            Self::PutTuple2(x) => set.insert_list(&x.elements),
+            Self::MakeFun3(x) => set.insert_term(&x.arg3),
+            Self::Badmatch(x) => set.insert_term(&x.arg1),
+            Self::CaseEnd(x) => set.insert_term(&x.arg1),
+            Self::TryCaseEnd(x) => set.insert_term(&x.arg1),
+            Self::Badrecord(x) => set.insert_term(&x.arg1),
+            Self::Raise(x) => {
+                set.insert_term(&x.stacktrace);
+                set.insert_term(&x.exc_value);
+            }
+            Self::TryCase(x) => set.insert(&x.register),
+            Self::CatchEnd(x) => set.insert_term(&x.arg1),
+            Self::Fmove(x) => set.insert_term(&x.arg1),
+            Self::Fconv(x) => set.insert_term(&x.arg1),
+            Self::Fadd(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::Fsub(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::Fmul(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::Fdiv(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::Fnegate(x) => set.insert_term(&x.arg2),
+            Self::PutMapAssoc(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg5);
+            }
+            Self::PutMapExact(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg5);
+            }
+            Self::HasMapFields(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::GetMapElement(x) => {
+                set.insert_term(&x.arg2);
+                if let Term::List(pairs) = &x.arg3 {
+                    for key in pairs.items.iter().step_by(2) {
+                        set.insert_term(key);
+                    }
+                }
+            }
+            Self::RecvMarkerBind(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::RecvMarkerClear(x) => set.insert_term(&x.arg1),
+            Self::RecvMarkerUse(x) => set.insert_term(&x.arg1),
+            Self::BsStartMatch2(x) => set.insert_term(&x.arg2),
+            Self::BsStartMatch3(x) => set.insert_term(&x.bin),
+            Self::BsStartMatch4(x) => set.insert_term(&x.arg3),
+            Self::BsGetInteger2(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg4);
+            }
+            Self::BsGetFloat2(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg4);
+            }
+            Self::BsGetBinary2(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg4);
+            }
+            Self::BsSkipBits2(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::BsTestTail2(x) => set.insert_term(&x.arg2),
+            Self::BsTestUnit(x) => set.insert_term(&x.arg2),
+            Self::BsMatchString(x) => set.insert_term(&x.arg2),
+            Self::BsSave2(x) => set.insert_term(&x.arg1),
+            Self::BsRestore2(x) => set.insert_term(&x.arg1),
+            Self::BsContextToBinary(x) => set.insert_term(&x.arg1),
+            Self::BsGetTail(x) => set.insert_term(&x.context),
+            Self::BsGetPosition(x) => set.insert_term(&x.context),
+            Self::BsSetPosition(x) => {
+                set.insert_term(&x.context);
+                set.insert_term(&x.position);
+            }
+            Self::BsGetUtf8(x) => set.insert_term(&x.arg2),
+            Self::BsGetUtf16(x) => set.insert_term(&x.arg2),
+            Self::BsGetUtf32(x) => set.insert_term(&x.arg2),
+            Self::BsSkipUtf8(x) => set.insert_term(&x.arg2),
+            Self::BsSkipUtf16(x) => set.insert_term(&x.arg2),
+            Self::BsSkipUtf32(x) => set.insert_term(&x.arg2),
+            Self::BsUtf8Size(x) => set.insert_term(&x.arg2),
+            Self::BsUtf16Size(x) => set.insert_term(&x.arg2),
+            Self::BsPutInteger(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg5);
+            }
+            Self::BsPutBinary(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg5);
+            }
+            Self::BsPutFloat(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg5);
+            }
+            Self::BsPutUtf8(x) => set.insert_term(&x.arg3),
+            Self::BsPutUtf16(x) => set.insert_term(&x.arg3),
+            Self::BsPutUtf32(x) => set.insert_term(&x.arg3),
+            Self::BsInit2(x) => set.insert_term(&x.arg2),
+            Self::BsInitBits(x) => set.insert_term(&x.arg2),
+            Self::BsAdd(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg3);
+            }
+            Self::BsAppend(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg6);
+            }
+            Self::BsPrivateAppend(x) => {
+                set.insert_term(&x.arg2);
+                set.insert_term(&x.arg4);
+            }
+            Self::BsCreateBin(x) => set.insert_term(&x.arg6),
+            Self::Allocate(_)
+            | Self::AllocateHeap(_)
+            | Self::AllocateHeapZero(_)
+            | Self::AllocateZero(_)
+            | Self::Bif0(_)
+            | Self::BsBitsToBytes(_)
+            | Self::BsBitsToBytes2(_)
+            | Self::BsFinal(_)
+            | Self::BsFinal2(_)
+            | Self::BsGetBinary(_)
+            | Self::BsGetFloat(_)
+            | Self::BsGetInteger(_)
+            | Self::BsInit(_)
+            | Self::BsInitWritable(_)
+            | Self::BsNeedBuf(_)
+            | Self::BsPutString(_)
+            | Self::BsRestore(_)
+            | Self::BsSave(_)
+            | Self::BsSkipBits(_)
+            | Self::BsStartMatch(_)
+            | Self::BsTestTail(_)
+            | Self::Catch(_)
+            | Self::Deallocate(_)
+            | Self::Fcheckerror(_)
+            | Self::Fclearerror(_)
+            | Self::IfEnd(_)
+            | Self::Init(_)
+            | Self::InitYregs(_)
+            | Self::IntBand(_)
+            | Self::IntBnot(_)
+            | Self::IntBor(_)
+            | Self::IntBsl(_)
+            | Self::IntBsr(_)
+            | Self::IntBxor(_)
+            | Self::IntCodeEnd(_)
+            | Self::IntDiv(_)
+            | Self::IntRem(_)
+            | Self::IsConstant(_)
+            | Self::Jump(_)
+            | Self::Label(_)
+            | Self::Line(_)
+            | Self::LoopRec(_)
+            | Self::LoopRecEnd(_)
+            | Self::MakeFun(_)
+            | Self::MakeFun2(_)
+            | Self::MDiv(_)
+            | Self::MPlus(_)
+            | Self::MMinus(_)
+            | Self::MTimes(_)
+            | Self::NifStart(_)
+            | Self::OnLoad(_)
+            | Self::PutLiteral(_)
+            | Self::PutString(_)
+            | Self::PutTuple(_)
+            | Self::RecvMark(_)
+            | Self::RecvMarkerReserve(_)
+            | Self::RecvSet(_)
+            | Self::RemoveMessage(_)
+            | Self::TestHeap(_)
+            | Self::Timeout(_)
+            | Self::Trim(_)
+            | Self::Try(_)
+            | Self::TryEnd(_)
+            | Self::Wait(_) => {}
+        }
+        if include_live {
+            if let Some(live) = self.live() {
+                set.insert_x_registers(live);
+            }
+        }
+        set
+    }
+
+    /// Returns the registers written (or clobbered) by this instruction.
+    ///
+    /// See [the module documentation](self) for the implicit definitions.
+    pub fn defs(&self) -> RegisterSet {
+        self.defs_impl(true)
+    }
+
+    /// Same as [`Instruction::defs()`] except that `x{Live}..` clobbered due to `live` operands
+    /// are not included unless they are also explicit operands.
+    pub fn explicit_defs(&self) -> RegisterSet {
+        self.defs_impl(false)
+    }
+
+    fn defs_impl(&self, include_live: bool) -> RegisterSet {
+        let mut set = RegisterSet::default();
+        match self {
+            Self::Call(_)
+            | Self::CallExt(_)
+            | Self::CallFun(_)
+            | Self::CallFun2(_)
+            | Self::Apply(_)
+            | Self::Send(_)
+            | Self::MakeFun2(_)
+            | Self::BuildStacktrace(_) => {
+                set.insert_x_registers(1);
+                set.insert_x_registers_from(1);
+            }
+            Self::CallLast(_) | Self::CallExtLast(_) | Self::ApplyLast(_) => {
+                set.insert_y_registers_from(0);
+            }
+            Self::Allocate(_) | Self::AllocateHeap(_) => {
+                set.insert_y_registers_from(0);
+            }
+            Self::AllocateZero(x) => {
+                if let Term::Usize(n) = x.arg1 {
+                    set.insert_y_registers(n);
+                }
+                set.insert_y_registers_from(0);
+            }
+            Self::AllocateHeapZero(x) => {
+                if let Allocation::Words(n) = x.stack_need {
+                    set.insert_y_registers(n);
+                }
+                set.insert_y_registers_from(0);
+            }
+            Self::Deallocate(_) | Self::Trim(_) => set.insert_y_registers_from(0),
+            Self::LoopRec(x) => set.insert_term(&x.arg2),
+            Self::Wait(_) | Self::WaitTimeout(_) => set.insert_x_registers_from(0),
+            Self::Try(x) => set.insert(&x.register),
+            Self::TryEnd(x) => set.insert(&x.register),
+            Self::TryCase(x) => {
+                set.insert(&x.register);
+                set.insert_x_registers(3);
+                set.insert_x_registers_from(3);
+            }
+            Self::Catch(x) => set.insert_term(&x.arg1),
+            Self::CatchEnd(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_x_registers(1);
+                set.insert_x_registers_from(1);
+            }
+            Self::Init(x) => set.insert_term(&x.arg1),
+            Self::InitYregs(x) => {
+                for r in &x.registers {
+                    set.insert(&Register::Y(*r));
+                }
+            }
+            Self::Bif0(x) => set.insert_term(&x.arg2),
+            Self::Bif1(x) => set.insert_term(&x.arg4),
+            Self::Bif2(x) => set.insert_term(&x.arg5),
+            Self::GcBif1(x) => set.insert_term(&x.arg5),
+            Self::GcBif2(x) => set.insert_term(&x.arg6),
+            Self::GcBif3(x) => set.insert_term(&x.arg7),
+            Self::Move(x) => set.insert(&x.dst),
+            Self::Swap(x) => {
+                set.insert_term(&x.arg1);
+                set.insert_term(&x.arg2);
+            }
+            Self::GetList(x) => {
+                set.insert(&x.head);
+                set.insert(&x.tail);
+            }
+            Self::GetHd(x) => set.insert_term(&x.arg2),
+            Self::GetTl(x) => set.insert_term(&x.arg2),
+            Self::GetTupleElement(x) => set.insert(&x.destination),
+            Self::PutList(x) => set.insert(&x.destination),
+            Self::PutTuple(x) => set.insert_term(&x.arg2),
+            Self::PutTuple2(x) => set.insert(&x.destination),
+            Self::MakeFun3(x) => set.insert_term(&x.arg2),
+            Self::Fmove(x) => set.insert_term(&x.arg2),
+            Self::Fconv(x) => set.insert_term(&x.arg2),
+            Self::Fadd(x) => set.insert_term(&x.arg4),
+            Self::Fsub(x) => set.insert_term(&x.arg4),
+            Self::Fmul(x) => set.insert_term(&x.arg4),
+            Self::Fdiv(x) => set.insert_term(&x.arg4),
+            Self::Fnegate(x) => set.insert_term(&x.arg3),
+            Self::PutMapAssoc(x) => set.insert_term(&x.arg3),
+            Self::PutMapExact(x) => set.insert_term(&x.arg3),
+            Self::GetMapElement(x) => {
+                if let Term::List(pairs) = &x.arg3 {
+                    for dst in pairs.items.iter().skip(1).step_by(2) {
+                        set.insert_term(dst);
+                    }
+                }
+            }
+            Self::RecvMarkerReserve(x) => set.insert_term(&x.arg1),
+            Self::BsStartMatch2(x) => set.insert_term(&x.arg5),
+            Self::BsStartMatch3(x) => set.insert(&x.destination),
+            Self::BsStartMatch4(x) => set.insert_term(&x.arg4),
+            Self::BsGetInteger2(x) => set.insert_term(&x.arg7),
+            Self::BsGetFloat2(x) => set.insert_term(&x.arg7),
+            Self::BsGetBinary2(x) => set.insert_term(&x.arg7),
+            Self::BsContextToBinary(x) => set.insert_term(&x.arg1),
+            Self::BsGetTail(x) => set.insert(&x.destination),
+            Self::BsGetPosition(x) => set.insert(&x.destination),
+            Self::BsGetUtf8(x) => set.insert_term(&x.arg5),
+            Self::BsGetUtf16(x) => set.insert_term(&x.arg5),
+            Self::BsGetUtf32(x) => set.insert_term(&x.arg5),
+            Self::BsUtf8Size(x) => set.insert_term(&x.arg3),
+            Self::BsUtf16Size(x) => set.insert_term(&x.arg3),
+            Self::BsInit2(x) => set.insert_term(&x.arg6),
+            Self::BsInitBits(x) => set.insert_term(&x.arg6),
+            Self::BsAdd(x) => set.insert_term(&x.arg5),
+            Self::BsAppend(x) => set.insert_term(&x.arg8),
+            Self::BsPrivateAppend(x) => set.insert_term(&x.arg6),
+            Self::BsCreateBin(x) => set.insert_term(&x.arg5),
+            Self::BsBitsToBytes(_)
+            | Self::BsBitsToBytes2(_)
+            | Self::BsFinal(_)
+            | Self::BsFinal2(_)
+            | Self::BsGetBinary(_)
+            | Self::BsGetFloat(_)
+            | Self::BsGetInteger(_)
+            | Self::BsInit(_)
+            | Self::BsInitWritable(_)
+            | Self::BsMatchString(_)
+            | Self::BsNeedBuf(_)
+            | Self::BsPutBinary(_)
+            | Self::BsPutFloat(_)
+            | Self::BsPutInteger(_)
+            | Self::BsPutString(_)
+            | Self::BsPutUtf16(_)
+            | Self::BsPutUtf32(_)
+            | Self::BsPutUtf8(_)
+            | Self::BsRestore(_)
+            | Self::BsRestore2(_)
+            | Self::BsSave(_)
+            | Self::BsSave2(_)
+            | Self::BsSetPosition(_)
+            | Self::BsSkipBits(_)
+            | Self::BsSkipBits2(_)
+            | Self::BsSkipUtf16(_)
+            | Self::BsSkipUtf32(_)
+            | Self::BsSkipUtf8(_)
+            | Self::BsStartMatch(_)
+            | Self::BsTestTail(_)
+            | Self::BsTestTail2(_)
+            | Self::BsTestUnit(_)
+            | Self::Badmatch(_)
+            | Self::Badrecord(_)
+            | Self::CallExtOnly(_)
+            | Self::CallOnly(_)
+            | Self::CaseEnd(_)
+            | Self::Fcheckerror(_)
+            | Self::Fclearerror(_)
+            | Self::FuncInfo(_)
+            | Self::HasMapFields(_)
+            | Self::IfEnd(_)
+            | Self::IntBand(_)
+            | Self::IntBnot(_)
+            | Self::IntBor(_)
+            | Self::IntBsl(_)
+            | Self::IntBsr(_)
+            | Self::IntBxor(_)
+            | Self::IntCodeEnd(_)
+            | Self::IntDiv(_)
+            | Self::IntRem(_)
+            | Self::IsAtom(_)
+            | Self::IsBinary(_)
+            | Self::IsBitstr(_)
+            | Self::IsBoolean(_)
+            | Self::IsConstant(_)
+            | Self::IsEq(_)
+            | Self::IsEqExact(_)
+            | Self::IsFloat(_)
+            | Self::IsFunction(_)
+            | Self::IsFunction2(_)
+            | Self::IsGe(_)
+            | Self::IsInteger(_)
+            | Self::IsList(_)
+            | Self::IsLt(_)
+            | Self::IsMap(_)
+            | Self::IsNe(_)
+            | Self::IsNeExact(_)
+            | Self::IsNil(_)
+            | Self::IsNonemptyList(_)
+            | Self::IsNumber(_)
+            | Self::IsPid(_)
+            | Self::IsPort(_)
+            | Self::IsReference(_)
+            | Self::IsTaggedTuple(_)
+            | Self::IsTuple(_)
+            | Self::Jump(_)
+            | Self::Label(_)
+            | Self::Line(_)
+            | Self::LoopRecEnd(_)
+            | Self::MakeFun(_)
+            | Self::MDiv(_)
+            | Self::MPlus(_)
+            | Self::MMinus(_)
+            | Self::MTimes(_)
+            | Self::NifStart(_)
+            | Self::OnLoad(_)
+            | Self::Put(_)
+            | Self::PutLiteral(_)
+            | Self::PutString(_)
+            | Self::Raise(_)
+            | Self::RawRaise(_)
+            | Self::RecvMark(_)
+            | Self::RecvMarkerBind(_)
+            | Self::RecvMarkerClear(_)
+            | Self::RecvMarkerUse(_)
+            | Self::RecvSet(_)
+            | Self::RemoveMessage(_)
+            | Self::Return(_)
+            | Self::SelectTupleArity(_)
+            | Self::SelectVal(_)
+            | Self::SetTupleElement(_)
+            | Self::TestArity(_)
+            | Self::TestHeap(_)
+            | Self::Timeout(_)
+            | Self::TryCaseEnd(_) => {}
+        }
+        if include_live {
+            if let Some(live) = self.gc_live() {
+                set.insert_x_registers_from(live);
+            }
+        }
+        set
+    }
+
+    /// Returns the value of the `live` operand of this instruction, if any.
+    ///
+    /// The operand tells the number of x registers (`x0..x{Live-1}`) which
+    /// must be preserved by the instruction.
+    pub fn live(&self) -> Option<usize> {
+        match self {
+            Self::BsSkipUtf8(x) => x.arg3.to_usize(),
+            Self::BsSkipUtf16(x) => x.arg3.to_usize(),
+            Self::BsSkipUtf32(x) => x.arg3.to_usize(),
+            _ => self.gc_live(),
+        }
+    }
+
+    // The `live` operand of the instructions which may trigger a garbage collection.
+    fn gc_live(&self) -> Option<usize> {
+        match self {
+            Self::Allocate(x) => Some(x.live),
+            Self::AllocateHeap(x) => Some(x.live),
+            Self::AllocateZero(x) => x.arg2.to_usize(),
+            Self::AllocateHeapZero(x) => Some(x.live),
+            Self::TestHeap(x) => Some(x.live),
+            Self::GcBif1(x) => x.arg2.to_usize(),
+            Self::GcBif2(x) => x.arg2.to_usize(),
+            Self::GcBif3(x) => x.arg2.to_usize(),
+            Self::BsInit2(x) => x.arg4.to_usize(),
+            Self::BsInitBits(x) => x.arg4.to_usize(),
+            Self::BsAppend(x) => x.arg4.to_usize(),
+            Self::BsCreateBin(x) => x.arg3.to_usize(),
+            Self::BsStartMatch2(x) => x.arg3.to_usize(),
+            Self::BsStartMatch3(x) => Some(x.live),
+            Self::BsStartMatch4(x) => x.arg2.to_usize(),
+            Self::BsGetInteger2(x) => x.arg3.to_usize(),
+            Self::BsGetFloat2(x) => x.arg3.to_usize(),
+            Self::BsGetBinary2(x) => x.arg3.to_usize(),
+            Self::BsGetUtf8(x) => x.arg3.to_usize(),
+            Self::BsGetUtf16(x) => x.arg3.to_usize(),
+            Self::BsGetUtf32(x) => x.arg3.to_usize(),
+            Self::BsGetTail(x) => Some(x.live),
+            Self::BsGetPosition(x) => Some(x.live),
+            Self::PutMapAssoc(x) => x.arg4.to_usize(),
+            Self::PutMapExact(x) => x.arg4.to_usize(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        Allocate, AllocateZero, BsCreateBin, BsGetInteger2, BsPutInteger, Call, CallExt, CallFun2,
+        CallLast, Fadd, Fconv, Fmove, GcBif1, GcBif2, GcBif3, Move,
+    };
+    use crate::term::{Atom, Label, TypedRegister};
+
+    fn x(value: usize) -> Register {
+        Register::X(XRegister { value, ty: None })
+    }
+
+    fn y(value: usize) -> Register {
+        Register::Y(YRegister { value, ty: None })
+    }
+
+    fn fr(value: usize) -> FloatingPointRegister {
+        FloatingPointRegister { value }
+    }
+
+    #[test]
+    fn def_use_works() {
+        let instruction = Instruction::Move(Move {
+            src: Term::TypedRegister(TypedRegister::X {
+                register: XRegister { value: 1, ty: None },
+                ty: 3,
+            }),
+            dst: x(0),
+        });
+        assert_eq!(instruction.uses().registers, [x(1)].into_iter().collect());
+        assert_eq!(instruction.defs().registers, [x(0)].into_iter().collect());
+
+        let instruction = Instruction::Call(Call {
+            arity: 2,
+            label: Label { value: 10 },
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(1)].into_iter().collect()
+        );
+        let defs = instruction.defs();
+        assert!(defs.contains(&x(0)));
+        assert!(defs.contains(&x(5)));
+
+        let instruction = Instruction::GcBif2(GcBif2 {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::Usize(3),
+            arg3: Term::Usize(0),
+            arg4: Term::XRegister(XRegister { value: 0, ty: None }),
+            arg5: Term::Usize(1),
+            arg6: x(4).into(),
+        });
+        assert_eq!(instruction.live(), Some(3));
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(1), x(2)].into_iter().collect()
+        );
+        assert_eq!(
+            instruction.explicit_uses().registers,
+            [x(0)].into_iter().collect()
+        );
+        let defs = instruction.defs();
+        assert!(defs.contains(&x(4)));
+        assert!(defs.contains(&x(3)));
+        assert!(!defs.contains(&x(2)));
+    }
+
+    #[test]
+    fn call_clobbers_x_registers_works() {
+        let instruction = Instruction::CallExt(CallExt {
+            arity: 2,
+            destination: 0,
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(1)].into_iter().collect()
+        );
+        let defs = instruction.defs();
+        assert_eq!(defs.registers, [x(0)].into_iter().collect());
+        assert_eq!(defs.x_registers_from, Some(1));
+        assert_eq!(defs.y_registers_from, None);
+
+        let instruction = Instruction::CallFun2(CallFun2 {
+            arg1: Term::Atom(Atom { value: 1 }),
+            arg2: Term::Usize(1),
+            arg3: Term::x(3),
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(3)].into_iter().collect()
+        );
+        assert!(instruction.defs().contains(&x(3)));
+
+        let instruction = Instruction::CallLast(CallLast {
+            arg1: Term::Usize(1),
+            arg2: Term::Label(Label { value: 10 }),
+            arg3: Term::Usize(2),
+        });
+        assert_eq!(instruction.uses().registers, [x(0)].into_iter().collect());
+        let defs = instruction.defs();
+        assert_eq!(defs.x_registers_from, None);
+        assert_eq!(defs.y_registers_from, Some(0));
+    }
+
+    #[test]
+    fn allocate_defs_y_registers_works() {
+        let instruction = Instruction::Allocate(Allocate {
+            stack_need: Allocation::Words(2),
+            live: 1,
+        });
+        assert_eq!(instruction.uses().registers, [x(0)].into_iter().collect());
+        let defs = instruction.defs();
+        assert!(defs.registers.is_empty());
+        assert_eq!(defs.x_registers_from, Some(1));
+        assert_eq!(defs.y_registers_from, Some(0));
+
+        let instruction = Instruction::AllocateZero(AllocateZero {
+            arg1: Term::Usize(2),
+            arg2: Term::Usize(1),
+        });
+        assert_eq!(instruction.live(), Some(1));
+        let defs = instruction.defs();
+        assert_eq!(defs.registers, [y(0), y(1)].into_iter().collect());
+        assert_eq!(defs.x_registers_from, Some(1));
+        assert_eq!(defs.y_registers_from, Some(0));
+    }
+
+    #[test]
+    fn gc_bif_live_works() {
+        let instruction = Instruction::GcBif1(GcBif1 {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::Usize(1),
+            arg3: Term::Usize(0),
+            arg4: Term::y(0),
+            arg5: Term::x(1),
+        });
+        assert_eq!(instruction.live(), Some(1));
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), y(0)].into_iter().collect()
+        );
+        assert_eq!(
+            instruction.explicit_defs().registers,
+            [x(1)].into_iter().collect()
+        );
+        assert_eq!(instruction.explicit_defs().x_registers_from, None);
+        assert_eq!(instruction.defs().x_registers_from, Some(1));
+
+        let instruction = Instruction::GcBif3(GcBif3 {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::Usize(0),
+            arg3: Term::Usize(0),
+            arg4: Term::x(0),
+            arg5: Term::x(1),
+            arg6: Term::Integer(3.into()),
+            arg7: Term::x(0),
+        });
+        assert_eq!(instruction.live(), Some(0));
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(1)].into_iter().collect()
+        );
+        assert_eq!(instruction.defs().x_registers_from, Some(0));
+
+        // A malformed `live` operand is ignored.
+        let instruction = Instruction::GcBif2(GcBif2 {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::x(0),
+            arg3: Term::Usize(0),
+            arg4: Term::x(1),
+            arg5: Term::x(2),
+            arg6: Term::x(3),
+        });
+        assert_eq!(instruction.live(), None);
+        assert_eq!(instruction.defs().x_registers_from, None);
+    }
+
+    #[test]
+    fn bs_def_use_works() {
+        let instruction = Instruction::BsGetInteger2(BsGetInteger2 {
+            arg1: Term::Label(Label { value: 3 }),
+            arg2: Term::x(0),
+            arg3: Term::Usize(2),
+            arg4: Term::y(1),
+            arg5: Term::Usize(1),
+            arg6: Term::Usize(0),
+            arg7: Term::x(2),
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), x(1), y(1)].into_iter().collect()
+        );
+        let defs = instruction.defs();
+        assert_eq!(defs.registers, [x(2)].into_iter().collect());
+        assert_eq!(defs.x_registers_from, Some(2));
+
+        let instruction = Instruction::BsPutInteger(BsPutInteger {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::x(1),
+            arg3: Term::Usize(1),
+            arg4: Term::Usize(0),
+            arg5: Term::y(0),
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(1), y(0)].into_iter().collect()
+        );
+        assert!(instruction.defs().is_empty());
+
+        let instruction = Instruction::BsCreateBin(BsCreateBin {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::Usize(0),
+            arg3: Term::Usize(1),
+            arg4: Term::Usize(1),
+            arg5: Term::x(1),
+            arg6: Term::List(List {
+                items: vec![
+                    Term::Atom(Atom { value: 1 }),
+                    Term::Usize(1),
+                    Term::Usize(8),
+                    Term::Usize(0),
+                    Term::y(2),
+                    Term::x(3),
+                ],
+            }),
+        });
+        assert_eq!(
+            instruction.uses().registers,
+            [x(0), y(2), x(3)].into_iter().collect()
+        );
+        let defs = instruction.defs();
+        assert_eq!(defs.registers, [x(1)].into_iter().collect());
+        assert_eq!(defs.x_registers_from, Some(1));
+    }
+
+    #[test]
+    fn float_def_use_works() {
+        let instruction = Instruction::Fconv(Fconv {
+            arg1: Term::x(0),
+            arg2: Term::FloatingPointRegister(fr(0)),
+        });
+        assert_eq!(instruction.uses().registers, [x(0)].into_iter().collect());
+        let defs = instruction.defs();
+        assert!(defs.registers.is_empty());
+        assert_eq!(defs.float_registers, [fr(0)].into_iter().collect());
+
+        let instruction = Instruction::Fadd(Fadd {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::FloatingPointRegister(fr(0)),
+            arg3: Term::FloatingPointRegister(fr(1)),
+            arg4: Term::FloatingPointRegister(fr(2)),
+        });
+        let uses = instruction.uses();
+        assert!(uses.registers.is_empty());
+        assert_eq!(uses.float_registers, [fr(0), fr(1)].into_iter().collect());
+        assert!(instruction.defs().contains_float(&fr(2)));
+
+        let instruction = Instruction::Fmove(Fmove {
+            arg1: Term::FloatingPointRegister(fr(2)),
+            arg2: Term::y(0),
+        });
+        assert_eq!(
+            instruction.uses().float_registers,
+            [fr(2)].into_iter().collect()
+        );
+        assert_eq!(instruction.defs().registers, [y(0)].into_iter().collect());
+    }
+}
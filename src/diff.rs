@@ -0,0 +1,462 @@
+//! Structured diff of two modules.
+//!
+//! [`diff_modules()`] aligns the functions of two modules by name and arity,
+//! and computes an instruction-level diff for each function existing in both.
+//!
+//! Instructions are compared after normalizing labels:
+//! the labels defined in a function are renumbered in order of their definitions
+//! (as [`relabel()`](crate::relabel::relabel) does), and references to the header labels of other
+//! functions are replaced with the name and arity of those functions.
+//! Thus, the label shifts caused by adding or removing code elsewhere do not appear in the diff.
+//!
+//! The edit script of a changed function is minimal unless the parts of the two functions which
+//! differ are very large (millions of instruction pairs),
+//! in which case the whole differing part is reported as removed and then added.
+//!
+//! Other operands (e.g., atom and literal table indices) are compared as-is.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::diff::diff_modules;
+//! use beamcode::module::Module;
+//!
+//! let old = Module::from_file("old/foo.beam")?;
+//! let new = Module::from_file("new/foo.beam")?;
+//! print!("{}", diff_modules(&old, &new));
+//! # Ok::<(), beamcode::DecodeError>(())
+//! ```
+use crate::function::split_functions;
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::relabel::relabel;
+use crate::term::Label;
+use crate::{Visit, VisitorMut};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The result of [`diff_modules()`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleDiff {
+    /// The functions (e.g., `"foo/2"`) which exist only in the new module.
+    pub added: Vec<String>,
+
+    /// The functions which exist only in the old module.
+    pub removed: Vec<String>,
+
+    /// The functions whose (normalized) instructions differ.
+    pub changed: Vec<FunctionDiff>,
+}
+
+impl ModuleDiff {
+    /// Returns `true` if the two modules have the same functions and instructions.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for ModuleDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for x in &self.removed {
+            writeln!(f, "removed: {x}")?;
+        }
+        for x in &self.added {
+            writeln!(f, "added: {x}")?;
+        }
+        for x in &self.changed {
+            write!(f, "{x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The instruction-level diff of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDiff {
+    /// The name and arity of the function (e.g., `"foo/2"`).
+    pub function: String,
+
+    pub edits: Vec<Edit>,
+}
+
+impl std::fmt::Display for FunctionDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "changed: {}", self.function)?;
+        for edit in &self.edits {
+            match edit {
+                Edit::Unchanged(x) => writeln!(f, "    {x:?}")?,
+                Edit::Removed(x) => writeln!(f, "  - {x:?}")?,
+                Edit::Added(x) => writeln!(f, "  + {x:?}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An element of [`FunctionDiff::edits`].
+///
+/// Instructions are the original (not normalized) ones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// An instruction existing in both functions (taken from the new module).
+    Unchanged(Instruction),
+
+    /// An instruction existing only in the old function.
+    Removed(Instruction),
+
+    /// An instruction existing only in the new function.
+    Added(Instruction),
+}
+
+/// Computes the diff from `old` to `new`.
+pub fn diff_modules(old: &Module, new: &Module) -> ModuleDiff {
+    let old_functions = functions(old);
+    let new_functions = functions(new);
+    let keys = old_functions
+        .keys()
+        .chain(new_functions.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let old_normalizer = Normalizer::new(old, &keys);
+    let new_normalizer = Normalizer::new(new, &keys);
+
+    let mut diff = ModuleDiff::default();
+    for key in &keys {
+        let name = format!("{}/{}", key.0, key.1);
+        let (old_instructions, new_instructions) =
+            match (old_functions.get(key), new_functions.get(key)) {
+                (Some(x), Some(y)) => (*x, *y),
+                (Some(_), None) => {
+                    diff.removed.push(name);
+                    continue;
+                }
+                (None, _) => {
+                    diff.added.push(name);
+                    continue;
+                }
+            };
+        let old_normalized = old_normalizer.normalize(old_instructions);
+        let new_normalized = new_normalizer.normalize(new_instructions);
+        if old_normalized != new_normalized {
+            diff.changed.push(FunctionDiff {
+                function: name,
+                edits: edits(
+                    old_instructions,
+                    new_instructions,
+                    &old_normalized,
+                    &new_normalized,
+                ),
+            });
+        }
+    }
+    diff
+}
+
+type FunctionKey = (String, usize);
+
+fn functions(module: &Module) -> BTreeMap<FunctionKey, &[Instruction]> {
+    split_functions(&module.code.instructions)
+        .into_iter()
+        .map(|x| {
+            let name = module.atom_name(x.name).unwrap_or_default().to_owned();
+            ((name, x.arity), x.instructions(&module.code.instructions))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct Normalizer {
+    // The header labels of the functions in the module, mapped to the labels
+    // which depend only on the names and arities of the functions.
+    header_labels: BTreeMap<Label, Label>,
+}
+
+impl Normalizer {
+    fn new(module: &Module, keys: &BTreeSet<FunctionKey>) -> Self {
+        let mut header_labels = BTreeMap::new();
+        for function in split_functions(&module.code.instructions) {
+            let key = (
+                module
+                    .atom_name(function.name)
+                    .unwrap_or_default()
+                    .to_owned(),
+                function.arity,
+            );
+            let Some(i) = keys.iter().position(|x| *x == key) else {
+                continue;
+            };
+            // Counting down from `usize::MAX` so as not to collide with the local labels.
+            if let Some(label) = function.func_info_label {
+                header_labels.insert(
+                    label,
+                    Label {
+                        value: usize::MAX - i * 2 - 1,
+                    },
+                );
+            }
+            if let Some(label) = function.entry_label {
+                header_labels.insert(
+                    label,
+                    Label {
+                        value: usize::MAX - i * 2,
+                    },
+                );
+            }
+        }
+        Self { header_labels }
+    }
+
+    fn normalize(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut instructions = instructions.to_vec();
+        let local_labels = instructions
+            .iter()
+            .filter_map(|x| match x {
+                Instruction::Label(x) => Some(Label { value: x.literal }),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        // The header labels are replaced first so that `relabel()` does not confuse them with
+        // the renumbered local labels.
+        let mut relabeler = HeaderRelabeler {
+            local_labels: &local_labels,
+            header_labels: &self.header_labels,
+        };
+        for instruction in &mut instructions {
+            instruction.visit_mut(&mut relabeler);
+        }
+        relabel(&mut instructions, 1);
+        instructions
+    }
+}
+
+#[derive(Debug)]
+struct HeaderRelabeler<'a> {
+    local_labels: &'a BTreeSet<Label>,
+    header_labels: &'a BTreeMap<Label, Label>,
+}
+
+impl<'a> VisitorMut for HeaderRelabeler<'a> {
+    fn visit_label_mut(&mut self, label: &mut Label) {
+        if self.local_labels.contains(label) {
+            return;
+        }
+        if let Some(x) = self.header_labels.get(label) {
+            *label = *x;
+        }
+    }
+}
+
+// The maximum number of the cells of the LCS table (about 16 MiB).
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+// Computes the edit script by the longest common subsequence of the normalized instructions.
+//
+// The common prefix and suffix are matched first. If the LCS table of the rest would exceed
+// `MAX_LCS_CELLS`, the rest is reported as removed and then added as a whole.
+fn edits(
+    old: &[Instruction],
+    new: &[Instruction],
+    old_normalized: &[Instruction],
+    new_normalized: &[Instruction],
+) -> Vec<Edit> {
+    let prefix = old_normalized
+        .iter()
+        .zip(new_normalized)
+        .take_while(|(x, y)| x == y)
+        .count();
+    let suffix = old_normalized[prefix..]
+        .iter()
+        .rev()
+        .zip(new_normalized[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (n, m) = (old.len() - suffix, new.len() - suffix);
+
+    let mut edits = new[..prefix]
+        .iter()
+        .cloned()
+        .map(Edit::Unchanged)
+        .collect::<Vec<_>>();
+    let cells = (n - prefix + 1).saturating_mul(m - prefix + 1);
+    if cells > MAX_LCS_CELLS {
+        edits.extend(old[prefix..n].iter().cloned().map(Edit::Removed));
+        edits.extend(new[prefix..m].iter().cloned().map(Edit::Added));
+    } else {
+        let width = m - prefix + 1;
+        let index = |i: usize, j: usize| (i - prefix) * width + (j - prefix);
+        let mut lcs = vec![0u32; cells];
+        for i in (prefix..n).rev() {
+            for j in (prefix..m).rev() {
+                lcs[index(i, j)] = if old_normalized[i] == new_normalized[j] {
+                    lcs[index(i + 1, j + 1)] + 1
+                } else {
+                    lcs[index(i + 1, j)].max(lcs[index(i, j + 1)])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (prefix, prefix);
+        while i < n || j < m {
+            if i < n && j < m && old_normalized[i] == new_normalized[j] {
+                edits.push(Edit::Unchanged(new[j].clone()));
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || lcs[index(i, j + 1)] >= lcs[index(i + 1, j)]) {
+                edits.push(Edit::Added(new[j].clone()));
+                j += 1;
+            } else {
+                edits.push(Edit::Removed(old[i].clone()));
+                i += 1;
+            }
+        }
+    }
+    edits.extend(new[m..].iter().cloned().map(Edit::Unchanged));
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{CallOnly, Move, Return};
+    use crate::term::{Register, Term};
+
+    #[test]
+    fn diff_modules_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("a", 0);
+        let a = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("b", 0);
+        function.push(CallOnly { arity: 0, label: a });
+        let mut function = builder.function("c", 0);
+        function.push(Return {});
+        let old = builder.build();
+
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("a", 0);
+        let a = function.entry_label();
+        let label = function.new_label();
+        function
+            .label(label)
+            .push(Move {
+                src: Term::x(1),
+                dst: Register::x(0),
+            })
+            .push(Return {});
+        let mut function = builder.function("b", 0);
+        function.push(CallOnly { arity: 0, label: a });
+        let mut function = builder.function("new", 0);
+        function.push(Return {});
+        let new = builder.build();
+
+        let diff = diff_modules(&old, &new);
+        assert_eq!(diff.added, ["new/0"]);
+        assert_eq!(diff.removed, ["c/0"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].function, "a/0");
+        assert_eq!(
+            diff.changed[0]
+                .edits
+                .iter()
+                .filter(|x| !matches!(x, Edit::Unchanged(_)))
+                .collect::<Vec<_>>(),
+            [
+                &Edit::Added(Instruction::Label(crate::instruction::Label {
+                    literal: label.value
+                })),
+                &Edit::Added(Instruction::Move(Move {
+                    src: Term::x(1),
+                    dst: Register::x(0),
+                })),
+            ]
+        );
+        assert!(diff_modules(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn diff_modules_with_same_labels_works() {
+        let mov = Instruction::Move(Move {
+            src: Term::x(1),
+            dst: Register::x(0),
+        });
+
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("a", 0);
+        let a = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("b", 0);
+        function.push(CallOnly { arity: 0, label: a });
+        let mut function = builder.function("d", 0);
+        function.push(mov.clone()).push(Return {});
+        let old = builder.build();
+
+        // `c/0` has the same labels as `a/0` in the old module.
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("c", 0);
+        let c = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("b", 0);
+        function.push(CallOnly { arity: 0, label: c });
+        let mut function = builder.function("d", 0);
+        function.push(Return {});
+        let new = builder.build();
+        assert_eq!(a, c);
+
+        let diff = diff_modules(&old, &new);
+        assert_eq!(diff.added, ["c/0"]);
+        assert_eq!(diff.removed, ["a/0"]);
+        let changed = diff
+            .changed
+            .iter()
+            .map(|x| {
+                let edits = x
+                    .edits
+                    .iter()
+                    .filter(|x| !matches!(x, Edit::Unchanged(_)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (x.function.as_str(), edits)
+            })
+            .collect::<Vec<_>>();
+        let call = Instruction::CallOnly(CallOnly { arity: 0, label: a });
+        assert_eq!(
+            changed,
+            [
+                ("b/0", vec![Edit::Added(call.clone()), Edit::Removed(call)]),
+                ("d/0", vec![Edit::Removed(mov)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_large_functions_works() {
+        let mov = |src, dst| {
+            Instruction::Move(Move {
+                src: Term::x(src),
+                dst: Register::x(dst),
+            })
+        };
+        let n = 3000;
+        let old = (0..n).map(|i| mov(i, 0)).collect::<Vec<_>>();
+        let mut new = (0..n).map(|i| mov(0, i)).collect::<Vec<_>>();
+        new.insert(0, old[0].clone());
+        new.push(Instruction::Return(Return {}));
+
+        // The LCS table of the middle part would exceed `MAX_LCS_CELLS`.
+        let script = edits(&old, &new, &old, &new);
+        assert_eq!(script.len(), 1 + (n - 1) + n + 1);
+        assert_eq!(script[0], Edit::Unchanged(old[0].clone()));
+        assert!(script[1..n].iter().all(|x| matches!(x, Edit::Removed(_))));
+        assert!(script[n..].iter().all(|x| matches!(x, Edit::Added(_))));
+        assert_eq!(script[n], Edit::Added(mov(0, 0)));
+
+        let script = edits(&old[..10], &new[..10], &old[..10], &new[..10]);
+        assert_eq!(
+            script
+                .iter()
+                .filter(|x| matches!(x, Edit::Unchanged(_)))
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,878 @@
+//! Downgrading instructions for older OTP releases.
+//!
+//! [`downgrade()`] rewrites the instructions introduced after OTP 22 into equivalent sequences of
+//! instructions which OTP 22 can load:
+//!
+//! | Instruction                     | Downgraded                                              |
+//! |---------------------------------|---------------------------------------------------------|
+//! | `swap`                          | three `move`s via a free x register                     |
+//! | `bs_start_match4`               | `bs_start_match3` (or `move`)                           |
+//! | `make_fun3`                     | `move`s, `make_fun2` and `move`                         |
+//! | `init_yregs`                    | `init` for each y register                              |
+//! | `recv_marker_*`                 | removed (`recv_marker_reserve` becomes a `move` of `[]`) |
+//! | `bs_create_bin`                 | `bs_add`, `bs_init_bits` and `bs_put_*`                  |
+//! | `call_fun2`                     | `move` and `call_fun`                                   |
+//! | `nif_start`                     | removed                                                 |
+//! | `badrecord`                     | `move`, `test_heap`, `put_tuple2` and `erlang:error/1`  |
+//!
+//! Typed registers are replaced with plain registers.
+//!
+//! Some instructions cannot be downgraded in general: e.g., `make_fun2` clobbers all x registers
+//! (unlike `make_fun3`), and `bs_create_bin` may contain `append` segments.
+//! Such instructions are reported as [`DowngradeError`]s.
+use crate::function::{split_functions, Function};
+use crate::instruction::{
+    BsAdd, BsCreateBin, BsInitBits, BsPutBinary, BsPutFloat, BsPutInteger, BsPutString, BsPutUtf16,
+    BsPutUtf32, BsPutUtf8, BsStartMatch3, BsUtf16Size, BsUtf8Size, CallExtOnly, CallFun, GcBif1,
+    Init, Instruction, MakeFun2, MakeFun3, Move, PutTuple2, TestHeap,
+};
+use crate::liveness::{LiveRegisters, Liveness};
+use crate::module::Module;
+use crate::term::{Allocation, Atom, Label, List, Register, Term};
+use crate::{Visit, VisitorMut};
+use num::BigInt;
+use std::collections::HashMap;
+
+/// The largest opcode known to OTP 22.
+pub const OTP22_OPCODE_MAX: u8 = 168;
+
+/// An instruction which could not be downgraded.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("instruction {index}: {kind}")]
+pub struct DowngradeError {
+    /// The index of the instruction in [`Code::instructions`](crate::module::Code)
+    /// (before the downgrade).
+    pub index: usize,
+
+    pub instruction: Instruction,
+    pub kind: DowngradeErrorKind,
+}
+
+/// Kinds of [`DowngradeError`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DowngradeErrorKind {
+    #[error("instruction is not supported by OTP 22")]
+    Unsupported,
+
+    #[error("unexpected operands")]
+    InvalidOperands,
+
+    #[error("{register:?} is live but would be clobbered")]
+    ClobbersLiveRegister { register: Register },
+
+    #[error("environment cannot be moved to x0..x{num_free} without clobbering itself")]
+    OverlappingEnvironment { num_free: usize },
+
+    #[error("unsupported binary segment type: {ty}")]
+    UnsupportedSegment { ty: String },
+}
+
+/// Downgrades the code of `module` so that it can be loaded by OTP 22.
+///
+/// `module` is modified only if every instruction could be downgraded.
+pub fn downgrade(module: &mut Module) -> Result<(), Vec<DowngradeError>> {
+    let mut target = module.clone();
+    let mut old = std::mem::take(&mut target.code.instructions);
+    for instruction in &mut old {
+        instruction.visit_mut(&mut Untyped);
+    }
+
+    let mut downgrader = Downgrader {
+        module: &mut target,
+        functions: split_functions(&old),
+        liveness: HashMap::new(),
+        instructions: &old,
+    };
+    let mut instructions = Vec::with_capacity(old.len());
+    let mut errors = Vec::new();
+    for (index, instruction) in old.iter().enumerate() {
+        match downgrader.downgrade(index) {
+            Ok(Some(new)) => instructions.extend(new),
+            Ok(None) => instructions.push(instruction.clone()),
+            Err(kind) => errors.push(DowngradeError {
+                index,
+                instruction: module.code.instructions[index].clone(),
+                kind,
+            }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    target.code.opcode_max = instructions
+        .iter()
+        .map(|x| u32::from(x.opcode()))
+        .max()
+        .unwrap_or(0);
+    target.code.instructions = instructions;
+    *module = target;
+    Ok(())
+}
+
+struct Untyped;
+
+impl VisitorMut for Untyped {
+    fn visit_term_mut(&mut self, term: &mut Term) {
+        if let Term::TypedRegister(x) = term {
+            *term = Register::Typed(*x).untyped().into();
+        }
+    }
+
+    fn visit_register_mut(&mut self, register: &mut Register) {
+        *register = register.untyped();
+    }
+}
+
+struct Downgrader<'a> {
+    module: &'a mut Module,
+    functions: Vec<Function>,
+    liveness: HashMap<usize, Liveness>,
+    instructions: &'a [Instruction],
+}
+
+impl<'a> Downgrader<'a> {
+    // Returns `None` if the `i`-th instruction can be loaded by OTP 22 as-is.
+    fn downgrade(&mut self, i: usize) -> Result<Option<Vec<Instruction>>, DowngradeErrorKind> {
+        let new = match &self.instructions[i] {
+            Instruction::Swap(x) => {
+                let (Some(register1), Some(register2)) =
+                    (x.arg1.to_register(), x.arg2.to_register())
+                else {
+                    return Err(DowngradeErrorKind::InvalidOperands);
+                };
+                let temp = self.free_x_register(i, &[&register1, &register2]);
+                vec![
+                    mov(register1.clone().into(), temp.clone()),
+                    mov(register2.clone().into(), register1),
+                    mov(temp.into(), register2),
+                ]
+            }
+            Instruction::BsStartMatch4(x) => {
+                let (Some(live), Some(destination)) = (x.arg2.to_usize(), x.arg4.to_register())
+                else {
+                    return Err(DowngradeErrorKind::InvalidOperands);
+                };
+                let source = x.arg3.clone();
+                match &x.arg1 {
+                    Term::Label(fail) => vec![bs_start_match3(*fail, source, live, destination)],
+                    Term::Atom(a) if self.module.atom_name(*a) == Some("no_fail") => {
+                        vec![bs_start_match3(
+                            Label { value: 0 },
+                            source,
+                            live,
+                            destination,
+                        )]
+                    }
+                    Term::Atom(a) if self.module.atom_name(*a) == Some("resume") => {
+                        if source.to_register().as_ref() == Some(&destination) {
+                            Vec::new()
+                        } else {
+                            vec![mov(source, destination)]
+                        }
+                    }
+                    _ => return Err(DowngradeErrorKind::InvalidOperands),
+                }
+            }
+            Instruction::MakeFun3(x) => self.make_fun3(i, x)?,
+            Instruction::InitYregs(x) => x
+                .registers
+                .iter()
+                .map(|&y| {
+                    Instruction::Init(Init {
+                        arg1: Term::YRegister(y),
+                    })
+                })
+                .collect(),
+            Instruction::RecvMarkerReserve(x) => {
+                let marker = x
+                    .arg1
+                    .to_register()
+                    .ok_or(DowngradeErrorKind::InvalidOperands)?;
+                vec![mov(Term::Atom(Atom { value: 0 }), marker)]
+            }
+            Instruction::RecvMarkerBind(_)
+            | Instruction::RecvMarkerClear(_)
+            | Instruction::RecvMarkerUse(_)
+            | Instruction::NifStart(_) => Vec::new(),
+            Instruction::BsCreateBin(x) => self.bs_create_bin(x)?,
+            Instruction::CallFun2(x) => {
+                let arity = x
+                    .arg2
+                    .to_usize()
+                    .ok_or(DowngradeErrorKind::InvalidOperands)?;
+                let mut new = Vec::new();
+                if x.arg3 != Term::x(arity) {
+                    new.push(mov(x.arg3.clone(), Register::x(arity)));
+                }
+                new.push(Instruction::CallFun(CallFun {
+                    arg1: Term::Usize(arity),
+                }));
+                new
+            }
+            Instruction::Badrecord(x) => {
+                // The value is moved to `x0` first so that it is the only live register
+                // when allocating the tuple.
+                let badrecord = self.module.intern_atom("badrecord");
+                let mut new = Vec::new();
+                if x.arg1 != Term::x(0) {
+                    new.push(mov(x.arg1.clone(), Register::x(0)));
+                }
+                new.extend([
+                    Instruction::TestHeap(TestHeap {
+                        heap_need: Allocation::Words(3),
+                        live: 1,
+                    }),
+                    Instruction::PutTuple2(PutTuple2 {
+                        destination: Register::x(0),
+                        elements: List {
+                            items: vec![Term::Atom(badrecord), Term::x(0)],
+                        },
+                    }),
+                    Instruction::CallExtOnly(CallExtOnly {
+                        arity: 1,
+                        destination: self.module.intern_import("erlang", "error", 1),
+                    }),
+                ]);
+                new
+            }
+            x if x.opcode() > OTP22_OPCODE_MAX => return Err(DowngradeErrorKind::Unsupported),
+            _ => return Ok(None),
+        };
+        Ok(Some(new))
+    }
+
+    // `make_fun2` takes the free variables from `x0..x{NumFree-1}`, returns the fun in `x0`
+    // and clobbers the other x registers.
+    fn make_fun3(
+        &mut self,
+        i: usize,
+        x: &MakeFun3,
+    ) -> Result<Vec<Instruction>, DowngradeErrorKind> {
+        let (Some(lambda), Some(destination), Term::List(environment)) =
+            (x.arg1.to_usize(), x.arg2.to_register(), &x.arg3)
+        else {
+            return Err(DowngradeErrorKind::InvalidOperands);
+        };
+        let num_free = environment.items.len();
+        for (j, item) in environment.items.iter().enumerate() {
+            if matches!(item, Term::XRegister(r) if r.value < num_free && r.value != j) {
+                return Err(DowngradeErrorKind::OverlappingEnvironment { num_free });
+            }
+        }
+        if let Some(live_out) = self.live_out(i) {
+            if let Some(register) = live_out
+                .into_iter()
+                .find(|r| matches!(r, Register::X(_)) && *r != destination)
+            {
+                return Err(DowngradeErrorKind::ClobbersLiveRegister { register });
+            }
+        }
+
+        let mut new = environment
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(j, item)| **item != Term::x(*j))
+            .map(|(j, item)| mov(item.clone(), Register::x(j)))
+            .collect::<Vec<_>>();
+        new.push(Instruction::MakeFun2(MakeFun2 {
+            arg1: Term::Usize(lambda),
+        }));
+        if destination != Register::x(0) {
+            new.push(mov(Term::x(0), destination));
+        }
+        Ok(new)
+    }
+
+    // Follows the code generated by OTP 24 for binary construction: the size of the binary
+    // (in bits) is computed by `bs_add` into a temporary register, and then the segments are
+    // written by `bs_put_*` after `bs_init_bits`.
+    fn bs_create_bin(&mut self, x: &BsCreateBin) -> Result<Vec<Instruction>, DowngradeErrorKind> {
+        let (Some(_), Some(alloc), Some(live), Some(destination), Term::List(operands)) = (
+            x.arg1.to_label(),
+            x.arg2.to_allocation(),
+            x.arg3.to_usize(),
+            x.arg5.to_register(),
+            &x.arg6,
+        ) else {
+            return Err(DowngradeErrorKind::InvalidOperands);
+        };
+        let fail = &x.arg1;
+        let segments = operands
+            .items
+            .chunks(6)
+            .map(|x| self.segment(x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Temporary registers are allocated after the live registers and the x registers read
+        // by the segments (as sources or sizes).
+        let mut next_temp = segments
+            .iter()
+            .flat_map(|x| [&x.source, &x.size])
+            .filter_map(|x| match x.to_register()?.untyped() {
+                Register::X(x) => Some(x.value + 1),
+                Register::Y(_) | Register::Typed(_) => None,
+            })
+            .chain(std::iter::once(live))
+            .max()
+            .expect("unreachable");
+        let live = next_temp;
+        let mut new = Vec::new();
+        let mut static_bits = BigInt::from(0);
+        let mut dynamic_sizes = Vec::new();
+        for segment in &segments {
+            match (segment.ty.as_str(), &segment.size) {
+                ("utf8" | "utf16", _) => match &segment.source {
+                    Term::Integer(c) => {
+                        let c = u32::try_from(c)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or(DowngradeErrorKind::InvalidOperands)?;
+                        let bytes = if segment.ty == "utf8" {
+                            c.len_utf8()
+                        } else {
+                            c.len_utf16() * 2
+                        };
+                        static_bits += bytes * 8;
+                    }
+                    _ => {
+                        let temp = Register::x(next_temp);
+                        next_temp += 1;
+                        new.push(if segment.ty == "utf8" {
+                            Instruction::BsUtf8Size(BsUtf8Size {
+                                arg1: fail.clone(),
+                                arg2: segment.source.clone(),
+                                arg3: temp.clone().into(),
+                            })
+                        } else {
+                            Instruction::BsUtf16Size(BsUtf16Size {
+                                arg1: fail.clone(),
+                                arg2: segment.source.clone(),
+                                arg3: temp.clone().into(),
+                            })
+                        });
+                        dynamic_sizes.push((temp.into(), 8));
+                    }
+                },
+                ("utf32", _) => static_bits += 32,
+                ("binary", Term::Atom(a)) if self.module.atom_name(*a) == Some("all") => {
+                    let temp = Register::x(next_temp);
+                    let bif = self.module.intern_import("erlang", "bit_size", 1);
+                    new.push(Instruction::GcBif1(GcBif1 {
+                        arg1: fail.clone(),
+                        arg2: Term::Usize(next_temp),
+                        arg3: Term::Usize(bif),
+                        arg4: segment.source.clone(),
+                        arg5: temp.clone().into(),
+                    }));
+                    next_temp += 1;
+                    dynamic_sizes.push((temp.into(), 1));
+                }
+                (_, Term::Integer(size)) => static_bits += size * segment.unit,
+                (_, Term::Usize(size)) => static_bits += size * segment.unit,
+                (_, size) if size.to_register().is_some() => {
+                    dynamic_sizes.push((size.clone(), segment.unit))
+                }
+                _ => return Err(DowngradeErrorKind::InvalidOperands),
+            }
+        }
+
+        let mut size = Term::Integer(static_bits);
+        let total = Register::x(next_temp);
+        if !dynamic_sizes.is_empty() {
+            for (term, unit) in dynamic_sizes {
+                new.push(Instruction::BsAdd(BsAdd {
+                    arg1: fail.clone(),
+                    arg2: size,
+                    arg3: term,
+                    arg4: Term::Usize(unit),
+                    arg5: total.clone().into(),
+                }));
+                size = total.clone().into();
+            }
+        }
+        // The destination must not be overwritten while the segments are read.
+        let binary = if segments
+            .iter()
+            .flat_map(|s| [&s.source, &s.size])
+            .any(|s| s.to_register().map(|r| r.untyped()) == Some(destination.untyped()))
+        {
+            Register::x(next_temp + 1)
+        } else {
+            destination.clone()
+        };
+        new.push(Instruction::BsInitBits(BsInitBits {
+            arg1: fail.clone(),
+            arg2: size,
+            arg3: Term::Usize(alloc.words()),
+            arg4: Term::Usize(live),
+            arg5: Term::Usize(0),
+            arg6: binary.clone().into(),
+        }));
+
+        for segment in segments {
+            let size = match (segment.ty.as_str(), segment.size) {
+                ("binary", Term::Atom(a)) => Term::Atom(a),
+                (_, Term::Usize(n)) => Term::Integer(n.into()),
+                (_, size) => size,
+            };
+            new.push(match segment.ty.as_str() {
+                "integer" => Instruction::BsPutInteger(BsPutInteger {
+                    arg1: fail.clone(),
+                    arg2: size,
+                    arg3: Term::Usize(segment.unit),
+                    arg4: Term::Usize(segment.flags),
+                    arg5: segment.source,
+                }),
+                "float" => Instruction::BsPutFloat(BsPutFloat {
+                    arg1: fail.clone(),
+                    arg2: size,
+                    arg3: Term::Usize(segment.unit),
+                    arg4: Term::Usize(segment.flags),
+                    arg5: segment.source,
+                }),
+                "binary" => Instruction::BsPutBinary(BsPutBinary {
+                    arg1: fail.clone(),
+                    arg2: size,
+                    arg3: Term::Usize(segment.unit),
+                    arg4: Term::Usize(segment.flags),
+                    arg5: segment.source,
+                }),
+                "string" => {
+                    let (Some(offset), Term::Integer(length)) = (as_usize(&segment.source), size)
+                    else {
+                        return Err(DowngradeErrorKind::InvalidOperands);
+                    };
+                    let length =
+                        usize::try_from(length).map_err(|_| DowngradeErrorKind::InvalidOperands)?;
+                    Instruction::BsPutString(BsPutString {
+                        arg1: Term::Usize(length),
+                        arg2: Term::Usize(offset),
+                    })
+                }
+                "utf8" => Instruction::BsPutUtf8(BsPutUtf8 {
+                    arg1: fail.clone(),
+                    arg2: Term::Usize(segment.flags),
+                    arg3: segment.source,
+                }),
+                "utf16" => Instruction::BsPutUtf16(BsPutUtf16 {
+                    arg1: fail.clone(),
+                    arg2: Term::Usize(segment.flags),
+                    arg3: segment.source,
+                }),
+                _ => Instruction::BsPutUtf32(BsPutUtf32 {
+                    arg1: fail.clone(),
+                    arg2: Term::Usize(segment.flags),
+                    arg3: segment.source,
+                }),
+            });
+        }
+        if binary != destination {
+            new.push(mov(binary.into(), destination));
+        }
+        Ok(new)
+    }
+
+    // Parses a segment of `bs_create_bin`: `Type, Segment, Unit, Flags, Source, Size`.
+    fn segment(&self, operands: &[Term]) -> Result<Segment, DowngradeErrorKind> {
+        let [ty, _, unit, flags, source, size] = operands else {
+            return Err(DowngradeErrorKind::InvalidOperands);
+        };
+        let ty = match ty {
+            Term::Atom(a) => self.module.atom_name(*a).unwrap_or_default().to_owned(),
+            _ => return Err(DowngradeErrorKind::InvalidOperands),
+        };
+        if !matches!(
+            ty.as_str(),
+            "integer" | "float" | "binary" | "string" | "utf8" | "utf16" | "utf32"
+        ) {
+            return Err(DowngradeErrorKind::UnsupportedSegment { ty });
+        }
+        Ok(Segment {
+            ty,
+            unit: as_usize(unit).unwrap_or(1),
+            flags: self.flags(flags)?,
+            source: source.clone(),
+            size: size.clone(),
+        })
+    }
+
+    // Converts segment flags (a literal list of atoms) into the bit field used by `bs_put_*`.
+    fn flags(&self, flags: &Term) -> Result<usize, DowngradeErrorKind> {
+        let items = match flags {
+            Term::Usize(_) | Term::Integer(_) => {
+                return as_usize(flags).ok_or(DowngradeErrorKind::InvalidOperands)
+            }
+            Term::Atom(Atom { value: 0 }) => Vec::new(),
+            Term::Literal(x) => self
+                .module
+                .literals
+                .get(x.value)
+                .and_then(|x| crate::etf::Value::from_bytes(x).ok())
+                .and_then(|x| x.to_vec())
+                .ok_or(DowngradeErrorKind::InvalidOperands)?,
+            _ => return Err(DowngradeErrorKind::InvalidOperands),
+        };
+        let mut bits = 0;
+        for item in items {
+            bits |= match item {
+                crate::etf::Value::Atom(x) => match x.as_str() {
+                    "aligned" => 1,
+                    "little" => 2,
+                    "signed" => 4,
+                    "exact" => 8,
+                    "native" => 16,
+                    _ => 0,
+                },
+                _ => return Err(DowngradeErrorKind::InvalidOperands),
+            };
+        }
+        Ok(bits)
+    }
+
+    // Returns the smallest x register which is neither live after the `i`-th instruction
+    // nor in `excludes`.
+    fn free_x_register(&mut self, i: usize, excludes: &[&Register]) -> Register {
+        let live_out = self.live_out(i).unwrap_or_default();
+        (0..)
+            .map(Register::x)
+            .find(|r| !live_out.contains(r) && !excludes.contains(&r))
+            .expect("unreachable")
+    }
+
+    // Returns the registers live after the `i`-th instruction (`None` if it is not in any function).
+    fn live_out(&mut self, i: usize) -> Option<LiveRegisters> {
+        let f = self.functions.iter().position(|f| f.range.contains(&i))?;
+        let function = &self.functions[f];
+        let instructions = self.instructions;
+        let liveness = self
+            .liveness
+            .entry(f)
+            .or_insert_with(|| Liveness::analyze(function.instructions(instructions)));
+        Some(
+            liveness.instructions[i - function.range.start]
+                .live_out
+                .clone(),
+        )
+    }
+}
+
+struct Segment {
+    ty: String,
+    unit: usize,
+    flags: usize,
+    source: Term,
+    size: Term,
+}
+
+fn mov(src: Term, dst: Register) -> Instruction {
+    Instruction::Move(Move { src, dst })
+}
+
+fn bs_start_match3(fail: Label, bin: Term, live: usize, destination: Register) -> Instruction {
+    Instruction::BsStartMatch3(BsStartMatch3 {
+        fail,
+        bin,
+        live,
+        destination,
+    })
+}
+
+fn as_usize(term: &Term) -> Option<usize> {
+    match term {
+        Term::Usize(x) => Some(*x),
+        Term::Integer(x) => x.try_into().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Badrecord, BsCreateBin, CallFun2, InitYregs, Return, Swap};
+    use crate::module::Lambda;
+    use crate::term::YRegister;
+    use crate::validate::validate;
+
+    #[test]
+    fn downgrade_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let integer = builder.atom("integer");
+        let binary = builder.atom("binary");
+        let all = builder.atom("all");
+        let safe = builder.atom("safe");
+        let mut function = builder.function("bar", 2);
+        function
+            .push(InitYregs {
+                registers: vec![YRegister { value: 0, ty: None }],
+            })
+            .push(Swap {
+                arg1: Term::x(0),
+                arg2: Term::x(1),
+            })
+            .push(BsCreateBin {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(0),
+                arg3: Term::Usize(2),
+                arg4: Term::Usize(1),
+                arg5: Term::x(0),
+                arg6: Term::List(List {
+                    items: vec![
+                        Term::Atom(integer),
+                        Term::Usize(1),
+                        Term::Usize(1),
+                        Term::Usize(0),
+                        Term::x(0),
+                        Term::Integer(8.into()),
+                        Term::Atom(binary),
+                        Term::Usize(2),
+                        Term::Usize(8),
+                        Term::Usize(0),
+                        Term::x(1),
+                        Term::Atom(all),
+                    ],
+                }),
+            })
+            .push(CallFun2 {
+                arg1: Term::Atom(safe),
+                arg2: Term::Usize(1),
+                arg3: Term::x(0),
+            })
+            .push(Return {});
+        let mut function = builder.function("baz", 1);
+        let entry = function.entry_label();
+        function
+            .push(MakeFun3 {
+                arg1: Term::Usize(0),
+                arg2: Term::x(0),
+                arg3: Term::List(List {
+                    items: vec![Term::x(0)],
+                }),
+            })
+            .push(Return {});
+        let mut module = builder.build();
+        module.lambdas.push(Lambda {
+            function: Atom { value: 7 },
+            arity: 1,
+            label: entry,
+            index: 0,
+            num_free: 1,
+            old_uniq: 0,
+        });
+
+        let mut broken = module.clone();
+        broken.code.instructions.insert(
+            14,
+            Instruction::MakeFun3(MakeFun3 {
+                arg1: Term::Usize(0),
+                arg2: Term::x(1),
+                arg3: Term::List(List { items: Vec::new() }),
+            }),
+        );
+        let original = broken.clone();
+        let errors = downgrade(&mut broken).unwrap_err();
+        assert_eq!(broken, original);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].index, 14);
+        assert_eq!(
+            errors[0].kind,
+            DowngradeErrorKind::ClobbersLiveRegister {
+                register: Register::x(0)
+            }
+        );
+
+        downgrade(&mut module).unwrap();
+        assert!(module.code.opcode_max <= u32::from(OTP22_OPCODE_MAX));
+        let x = |n| Register::x(n);
+        assert_eq!(
+            module.code.instructions[4..18],
+            [
+                Instruction::Init(Init { arg1: Term::y(0) }),
+                mov(Term::x(0), x(2)),
+                mov(Term::x(1), x(0)),
+                mov(Term::x(2), x(1)),
+                Instruction::GcBif1(GcBif1 {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Usize(2),
+                    arg3: Term::Usize(0),
+                    arg4: Term::x(1),
+                    arg5: Term::x(2),
+                }),
+                Instruction::BsAdd(BsAdd {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Integer(8.into()),
+                    arg3: Term::x(2),
+                    arg4: Term::Usize(1),
+                    arg5: Term::x(3),
+                }),
+                Instruction::BsInitBits(BsInitBits {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::x(3),
+                    arg3: Term::Usize(0),
+                    arg4: Term::Usize(2),
+                    arg5: Term::Usize(0),
+                    arg6: Term::x(4),
+                }),
+                Instruction::BsPutInteger(BsPutInteger {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Integer(8.into()),
+                    arg3: Term::Usize(1),
+                    arg4: Term::Usize(0),
+                    arg5: Term::x(0),
+                }),
+                Instruction::BsPutBinary(BsPutBinary {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Atom(all),
+                    arg3: Term::Usize(8),
+                    arg4: Term::Usize(0),
+                    arg5: Term::x(1),
+                }),
+                mov(Term::x(4), x(0)),
+                mov(Term::x(0), x(1)),
+                Instruction::CallFun(CallFun {
+                    arg1: Term::Usize(1)
+                }),
+                Instruction::Return(Return {}),
+                module.code.instructions[17].clone(),
+            ]
+        );
+        assert_eq!(
+            module.code.instructions[21],
+            Instruction::MakeFun2(MakeFun2 {
+                arg1: Term::Usize(0)
+            })
+        );
+
+        let mut buf = Vec::new();
+        module.to_writer(&mut buf).unwrap();
+        assert_eq!(Module::from_reader(&buf[..]).unwrap(), module);
+    }
+
+    #[test]
+    fn downgrade_bs_create_bin_with_size_register_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let integer = builder.atom("integer");
+        let binary = builder.atom("binary");
+        let all = builder.atom("all");
+        let mut function = builder.function("bar", 4);
+        function
+            .push(BsCreateBin {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(0),
+                arg3: Term::Usize(2),
+                arg4: Term::Usize(1),
+                arg5: Term::x(1),
+                arg6: Term::List(List {
+                    items: vec![
+                        Term::Atom(integer),
+                        Term::Usize(1),
+                        Term::Usize(1),
+                        Term::Usize(0),
+                        Term::x(0),
+                        Term::x(3),
+                        Term::Atom(binary),
+                        Term::Usize(2),
+                        Term::Usize(8),
+                        Term::Usize(0),
+                        Term::x(1),
+                        Term::Atom(all),
+                    ],
+                }),
+            })
+            .push(Return {});
+        let mut module = builder.build();
+
+        downgrade(&mut module).unwrap();
+        let x = |n| Register::x(n);
+        assert_eq!(
+            module.code.instructions[4..12],
+            [
+                Instruction::GcBif1(GcBif1 {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Usize(4),
+                    arg3: Term::Usize(0),
+                    arg4: Term::x(1),
+                    arg5: Term::x(4),
+                }),
+                Instruction::BsAdd(BsAdd {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Integer(0.into()),
+                    arg3: Term::x(3),
+                    arg4: Term::Usize(1),
+                    arg5: Term::x(5),
+                }),
+                Instruction::BsAdd(BsAdd {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::x(5),
+                    arg3: Term::x(4),
+                    arg4: Term::Usize(1),
+                    arg5: Term::x(5),
+                }),
+                Instruction::BsInitBits(BsInitBits {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::x(5),
+                    arg3: Term::Usize(0),
+                    arg4: Term::Usize(4),
+                    arg5: Term::Usize(0),
+                    arg6: Term::x(6),
+                }),
+                Instruction::BsPutInteger(BsPutInteger {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::x(3),
+                    arg3: Term::Usize(1),
+                    arg4: Term::Usize(0),
+                    arg5: Term::x(0),
+                }),
+                Instruction::BsPutBinary(BsPutBinary {
+                    arg1: Term::Label(Label { value: 0 }),
+                    arg2: Term::Atom(all),
+                    arg3: Term::Usize(8),
+                    arg4: Term::Usize(0),
+                    arg5: Term::x(1),
+                }),
+                mov(Term::x(6), x(1)),
+                Instruction::Return(Return {}),
+            ]
+        );
+    }
+
+    #[test]
+    fn downgrade_badrecord_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        builder
+            .function("bar", 2)
+            .push(Badrecord { arg1: Term::x(1) });
+        builder
+            .function("baz", 1)
+            .push(Badrecord { arg1: Term::x(0) });
+        let mut module = builder.build();
+
+        downgrade(&mut module).unwrap();
+        assert_eq!(validate(&module), Ok(()));
+        let badrecord = module.intern_atom("badrecord");
+        let expected = |destination| {
+            vec![
+                Instruction::TestHeap(TestHeap {
+                    heap_need: Allocation::Words(3),
+                    live: 1,
+                }),
+                Instruction::PutTuple2(PutTuple2 {
+                    destination: Register::x(0),
+                    elements: List {
+                        items: vec![Term::Atom(badrecord), Term::x(0)],
+                    },
+                }),
+                Instruction::CallExtOnly(CallExtOnly {
+                    arity: 1,
+                    destination,
+                }),
+            ]
+        };
+        let error = module.intern_import("erlang", "error", 1);
+        assert_eq!(module.code.instructions[4], mov(Term::x(1), Register::x(0)));
+        assert_eq!(module.code.instructions[5..8], expected(error));
+        assert_eq!(module.code.instructions[12..15], expected(error));
+    }
+}
@@ -0,0 +1,786 @@
+//! Erlang External Term Format.
+//!
+//! This module provides [`Value`], a representation of Erlang terms, which can be decoded from /
+//! encoded into the External Term Format (e.g., the entries of the literal table).
+//! Only the data types which can appear in literals of ordinary modules are supported
+//! (pids, ports, references and local funs are not).
+//!
+//! # References
+//!
+//! - [External Term Format](https://www.erlang.org/doc/apps/erts/erl_ext_dist.html)
+use crate::{DecodeError, EncodeError};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use num::bigint::Sign;
+use num::{BigInt, FromPrimitive as _, ToPrimitive as _, Zero as _};
+use std::cmp::Ordering;
+use std::io::{Read, Write};
+
+const VERSION: u8 = 131;
+const NEW_FLOAT_EXT: u8 = 70;
+const BIT_BINARY_EXT: u8 = 77;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const EXPORT_EXT: u8 = 113;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+// The maximum nesting depth of decoded terms.
+const MAX_NESTING: usize = 512;
+
+/// An Erlang term.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(BigInt),
+    Float(f64),
+    Atom(String),
+    Tuple(Vec<Value>),
+
+    /// `[]`.
+    Nil,
+
+    /// `[Item1, ..., ItemN | Tail]` (`Tail` is `Nil` for a proper list).
+    ///
+    /// The items are never empty and the tail is never a `List`
+    /// (use [`Value::improper_list()`] to keep this invariant).
+    List(Vec<Value>, Box<Value>),
+
+    Binary(Vec<u8>),
+
+    /// A bitstring whose size is not divisible by 8.
+    ///
+    /// `bits` is the number of the bits used in the last byte (`1..=7`).
+    BitString {
+        bytes: Vec<u8>,
+        bits: u8,
+    },
+
+    Map(Vec<(Value, Value)>),
+
+    /// `fun Module:Function/Arity`.
+    Export {
+        module: String,
+        function: String,
+        arity: u8,
+    },
+}
+
+impl Value {
+    /// Makes an atom.
+    pub fn atom(name: &str) -> Self {
+        Self::Atom(name.to_owned())
+    }
+
+    /// Makes an integer.
+    pub fn integer<T: Into<BigInt>>(value: T) -> Self {
+        Self::Integer(value.into())
+    }
+
+    /// Makes a proper list.
+    pub fn list<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        Self::improper_list(items.into_iter().collect(), Self::Nil)
+    }
+
+    /// Makes a list `[Items... | Tail]` (`tail` itself if `items` is empty).
+    pub fn improper_list(mut items: Vec<Value>, tail: Value) -> Self {
+        match tail {
+            _ if items.is_empty() => tail,
+            Self::List(rest, tail) => {
+                items.extend(rest);
+                Self::List(items, tail)
+            }
+            _ => Self::List(items, Box::new(tail)),
+        }
+    }
+
+    /// Makes a list `[Head | Tail]`.
+    pub fn cons(head: Value, tail: Value) -> Self {
+        Self::improper_list(vec![head], tail)
+    }
+
+    /// Returns the elements of this value if it is a proper list.
+    pub fn to_vec(&self) -> Option<Vec<Value>> {
+        match self {
+            Self::Nil => Some(Vec::new()),
+            Self::List(items, tail) if **tail == Self::Nil => Some(items.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this value is the atom `true` or `false`.
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, Self::Atom(x) if x == "true" || x == "false")
+    }
+
+    /// Returns `true` if this value is an integer or a float.
+    pub fn is_number(&self) -> bool {
+        matches!(self, Self::Integer(_) | Self::Float(_))
+    }
+
+    /// Compares two values in the Erlang term order
+    /// (`number < atom < fun < tuple < map < nil < list < bit string`).
+    ///
+    /// Integers and floats are compared by their numeric values (as `==` / `<` in Erlang).
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+            (Self::Integer(a), Self::Float(b)) => compare_integer_float(a, *b),
+            (Self::Float(a), Self::Integer(b)) => compare_integer_float(b, *a).reverse(),
+            (Self::Atom(a), Self::Atom(b)) => a.cmp(b),
+            (
+                Self::Export {
+                    module: m0,
+                    function: f0,
+                    arity: a0,
+                },
+                Self::Export {
+                    module: m1,
+                    function: f1,
+                    arity: a1,
+                },
+            ) => (m0, f0, a0).cmp(&(m1, f1, a1)),
+            (Self::Tuple(a), Self::Tuple(b)) => {
+                a.len().cmp(&b.len()).then_with(|| compare_slices(a, b))
+            }
+            (Self::Map(a), Self::Map(b)) => a.len().cmp(&b.len()).then_with(|| {
+                // Maps of the same size are compared by their keys and then by their values
+                // (both in key order).
+                let mut a = a.iter().collect::<Vec<_>>();
+                let mut b = b.iter().collect::<Vec<_>>();
+                a.sort_by(|x, y| x.0.compare(&y.0));
+                b.sort_by(|x, y| x.0.compare(&y.0));
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| x.0.compare(&y.0))
+                    .chain(a.iter().zip(b.iter()).map(|(x, y)| x.1.compare(&y.1)))
+                    .find(|x| *x != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            }),
+            (Self::Nil, Self::Nil) => Ordering::Equal,
+            (Self::List(a, a_tail), Self::List(b, b_tail)) => {
+                let n = a.len().min(b.len());
+                compare_slices(&a[..n], &b[..n]).then_with(|| {
+                    // The rest of the longer one is compared with the tail of the other.
+                    match (a.len() > n, b.len() > n) {
+                        (true, _) => self.type_order().cmp(&b_tail.type_order()),
+                        (_, true) => a_tail.type_order().cmp(&other.type_order()),
+                        _ => a_tail.compare(b_tail),
+                    }
+                })
+            }
+            (
+                Self::Binary(_) | Self::BitString { .. },
+                Self::Binary(_) | Self::BitString { .. },
+            ) => self.bits().cmp(&other.bits()),
+            _ => self.type_order().cmp(&other.type_order()),
+        }
+    }
+
+    fn type_order(&self) -> u8 {
+        match self {
+            Self::Integer(_) | Self::Float(_) => 0,
+            Self::Atom(_) => 1,
+            Self::Export { .. } => 2,
+            Self::Tuple(_) => 3,
+            Self::Map(_) => 4,
+            Self::Nil => 5,
+            Self::List(..) => 6,
+            Self::Binary(_) | Self::BitString { .. } => 7,
+        }
+    }
+
+    fn bits(&self) -> Vec<bool> {
+        let (bytes, last_bits) = match self {
+            Self::Binary(x) => (x.as_slice(), 8),
+            Self::BitString { bytes, bits } => (bytes.as_slice(), *bits),
+            _ => return Vec::new(),
+        };
+        let mut bits = Vec::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            let n = if i + 1 == bytes.len() { last_bits } else { 8 };
+            bits.extend((0..n).map(|j| byte & (0x80 >> j) != 0));
+        }
+        bits
+    }
+
+    /// Decodes a value (including the leading version byte).
+    ///
+    /// Terms nested deeper than 512 levels are rejected with [`DecodeError::TooDeepNesting`].
+    pub fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let version = reader.read_u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnknownEtfVersion { version });
+        }
+        Self::decode_term(reader, 0)
+    }
+
+    /// Decodes a value from `bytes` (e.g., an entry of the literal table).
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::decode(&mut bytes)
+    }
+
+    fn decode_term<R: Read>(reader: &mut R, depth: usize) -> Result<Self, DecodeError> {
+        if depth > MAX_NESTING {
+            return Err(DecodeError::TooDeepNesting { max: MAX_NESTING });
+        }
+        let tag = reader.read_u8()?;
+        match tag {
+            SMALL_TUPLE_EXT => {
+                let n = reader.read_u8()? as usize;
+                decode_terms(reader, n, depth + 1).map(Self::Tuple)
+            }
+            LARGE_TUPLE_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                decode_terms(reader, n, depth + 1).map(Self::Tuple)
+            }
+            LIST_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                let items = decode_terms(reader, n, depth + 1)?;
+                let tail = Self::decode_term(reader, depth + 1)?;
+                Ok(Self::improper_list(items, tail))
+            }
+            MAP_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                let mut pairs = Vec::new();
+                for _ in 0..n {
+                    let key = Self::decode_term(reader, depth + 1)?;
+                    let value = Self::decode_term(reader, depth + 1)?;
+                    pairs.push((key, value));
+                }
+                Ok(Self::Map(pairs))
+            }
+            _ => Self::decode_scalar(reader, tag),
+        }
+    }
+
+    // Decodes a term which has no nested terms (other than the parts of `EXPORT_EXT`).
+    //
+    // This is separated from `decode_term()` to keep the stack frames of the recursion small.
+    #[inline(never)]
+    fn decode_scalar<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+        match tag {
+            SMALL_INTEGER_EXT => Ok(Self::integer(reader.read_u8()?)),
+            INTEGER_EXT => Ok(Self::integer(reader.read_i32::<BigEndian>()?)),
+            SMALL_BIG_EXT => {
+                let n = reader.read_u8()? as usize;
+                decode_big(reader, n)
+            }
+            LARGE_BIG_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                decode_big(reader, n)
+            }
+            NEW_FLOAT_EXT => Ok(Self::Float(reader.read_f64::<BigEndian>()?)),
+            FLOAT_EXT => {
+                let mut buf = [0; 31];
+                reader.read_exact(&mut buf)?;
+                let s = String::from_utf8_lossy(&buf);
+                s.trim_end_matches('\0')
+                    .parse()
+                    .map(Self::Float)
+                    .map_err(|_| DecodeError::UnknownEtfTag { tag })
+            }
+            ATOM_EXT => {
+                let n = reader.read_u16::<BigEndian>()? as usize;
+                decode_latin1(reader, n).map(Self::Atom)
+            }
+            SMALL_ATOM_EXT => {
+                let n = reader.read_u8()? as usize;
+                decode_latin1(reader, n).map(Self::Atom)
+            }
+            ATOM_UTF8_EXT => {
+                let n = reader.read_u16::<BigEndian>()? as usize;
+                decode_utf8(reader, n).map(Self::Atom)
+            }
+            SMALL_ATOM_UTF8_EXT => {
+                let n = reader.read_u8()? as usize;
+                decode_utf8(reader, n).map(Self::Atom)
+            }
+            NIL_EXT => Ok(Self::Nil),
+            STRING_EXT => {
+                let n = reader.read_u16::<BigEndian>()? as usize;
+                let bytes = read_bytes(reader, n)?;
+                Ok(Self::list(bytes.into_iter().map(Self::integer)))
+            }
+            BINARY_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                read_bytes(reader, n).map(Self::Binary)
+            }
+            BIT_BINARY_EXT => {
+                let n = reader.read_u32::<BigEndian>()? as usize;
+                let bits = reader.read_u8()?;
+                let bytes = read_bytes(reader, n)?;
+                if bits == 8 {
+                    Ok(Self::Binary(bytes))
+                } else {
+                    Ok(Self::BitString { bytes, bits })
+                }
+            }
+            EXPORT_EXT => {
+                let mut part = || {
+                    let tag = reader.read_u8()?;
+                    Self::decode_scalar(reader, tag)
+                };
+                let (Self::Atom(module), Self::Atom(function), Self::Integer(arity)) =
+                    (part()?, part()?, part()?)
+                else {
+                    return Err(DecodeError::UnknownEtfTag { tag });
+                };
+                Ok(Self::Export {
+                    module,
+                    function,
+                    arity: arity.try_into()?,
+                })
+            }
+            _ => Err(DecodeError::UnknownEtfTag { tag }),
+        }
+    }
+
+    /// Encodes this value (including the leading version byte).
+    pub fn encode<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_u8(VERSION)?;
+        self.encode_term(writer)
+    }
+
+    /// Encodes this value into a byte vector (e.g., an entry of the literal table).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn encode_term<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        match self {
+            Self::Integer(x) => {
+                if let Some(x) = x.to_u8() {
+                    writer.write_u8(SMALL_INTEGER_EXT)?;
+                    writer.write_u8(x)?;
+                } else if let Some(x) = x.to_i32() {
+                    writer.write_u8(INTEGER_EXT)?;
+                    writer.write_i32::<BigEndian>(x)?;
+                } else {
+                    let (sign, bytes) = x.to_bytes_le();
+                    if bytes.len() <= 255 {
+                        writer.write_u8(SMALL_BIG_EXT)?;
+                        writer.write_u8(bytes.len() as u8)?;
+                    } else {
+                        writer.write_u8(LARGE_BIG_EXT)?;
+                        writer.write_u32::<BigEndian>(bytes.len() as u32)?;
+                    }
+                    writer.write_u8((sign == Sign::Minus) as u8)?;
+                    writer.write_all(&bytes)?;
+                }
+            }
+            Self::Float(x) => {
+                writer.write_u8(NEW_FLOAT_EXT)?;
+                writer.write_f64::<BigEndian>(*x)?;
+            }
+            Self::Atom(x) => encode_atom(x, writer)?,
+            Self::Tuple(x) => {
+                if x.len() <= 255 {
+                    writer.write_u8(SMALL_TUPLE_EXT)?;
+                    writer.write_u8(x.len() as u8)?;
+                } else {
+                    writer.write_u8(LARGE_TUPLE_EXT)?;
+                    writer.write_u32::<BigEndian>(x.len() as u32)?;
+                }
+                for x in x {
+                    x.encode_term(writer)?;
+                }
+            }
+            Self::Nil => writer.write_u8(NIL_EXT)?,
+            Self::List(items, tail) => {
+                let bytes = items
+                    .iter()
+                    .map(|x| match x {
+                        Self::Integer(x) => x.to_u8(),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match bytes {
+                    Some(bytes) if **tail == Self::Nil && bytes.len() <= 0xFFFF => {
+                        writer.write_u8(STRING_EXT)?;
+                        writer.write_u16::<BigEndian>(bytes.len() as u16)?;
+                        writer.write_all(&bytes)?;
+                    }
+                    _ => {
+                        writer.write_u8(LIST_EXT)?;
+                        writer.write_u32::<BigEndian>(items.len() as u32)?;
+                        for x in items {
+                            x.encode_term(writer)?;
+                        }
+                        tail.encode_term(writer)?;
+                    }
+                }
+            }
+            Self::Binary(x) => {
+                writer.write_u8(BINARY_EXT)?;
+                writer.write_u32::<BigEndian>(x.len() as u32)?;
+                writer.write_all(x)?;
+            }
+            Self::BitString { bytes, bits } => {
+                writer.write_u8(BIT_BINARY_EXT)?;
+                writer.write_u32::<BigEndian>(bytes.len() as u32)?;
+                writer.write_u8(*bits)?;
+                writer.write_all(bytes)?;
+            }
+            Self::Map(x) => {
+                writer.write_u8(MAP_EXT)?;
+                writer.write_u32::<BigEndian>(x.len() as u32)?;
+                for (k, v) in x {
+                    k.encode_term(writer)?;
+                    v.encode_term(writer)?;
+                }
+            }
+            Self::Export {
+                module,
+                function,
+                arity,
+            } => {
+                writer.write_u8(EXPORT_EXT)?;
+                encode_atom(module, writer)?;
+                encode_atom(function, writer)?;
+                writer.write_u8(SMALL_INTEGER_EXT)?;
+                writer.write_u8(*arity)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Atom(x) => {
+                let is_simple = x.starts_with(|c: char| c.is_ascii_lowercase())
+                    && x.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@');
+                if is_simple {
+                    return write!(f, "{x}");
+                }
+                // Control characters are escaped to keep the output on one line.
+                write!(f, "'")?;
+                for c in x.chars() {
+                    match c {
+                        '\\' => write!(f, "\\\\")?,
+                        '\'' => write!(f, "\\'")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\t' => write!(f, "\\t")?,
+                        c if c.is_control() => write!(f, "\\x{{{:x}}}", u32::from(c))?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "'")
+            }
+            Self::Tuple(x) => {
+                write!(f, "{{")?;
+                for (i, x) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                write!(f, "}}")
+            }
+            Self::Nil => write!(f, "[]"),
+            Self::List(items, tail) => {
+                write!(f, "[")?;
+                for (i, x) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                if **tail != Self::Nil {
+                    write!(f, "|{tail}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Binary(x) => {
+                write!(f, "<<")?;
+                for (i, x) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                write!(f, ">>")
+            }
+            Self::BitString { bytes, bits } => {
+                write!(f, "<<")?;
+                for (i, x) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    if i + 1 == bytes.len() {
+                        write!(f, "{}:{}", x >> (8 - bits), bits)?;
+                    } else {
+                        write!(f, "{x}")?;
+                    }
+                }
+                write!(f, ">>")
+            }
+            Self::Map(x) => {
+                write!(f, "#{{")?;
+                for (i, (k, v)) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{k} => {v}")?;
+                }
+                write!(f, "}}")
+            }
+            Self::Export {
+                module,
+                function,
+                arity,
+            } => write!(
+                f,
+                "fun {}:{}/{}",
+                Self::atom(module),
+                Self::atom(function),
+                arity
+            ),
+        }
+    }
+}
+
+fn compare_slices(a: &[Value], b: &[Value]) -> Ordering {
+    for (a, b) in a.iter().zip(b.iter()) {
+        let ordering = a.compare(b);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn compare_integer_float(a: &BigInt, b: f64) -> Ordering {
+    if b.is_nan() {
+        return Ordering::Less;
+    }
+    match a.to_f64() {
+        Some(x) if x != b => x.total_cmp(&b),
+        _ => {
+            // Compare exactly to avoid rounding errors of large integers.
+            let Some(c) = BigInt::from_f64(b.trunc()) else {
+                // `b` is infinite.
+                return 0.0f64.total_cmp(&b);
+            };
+            match a.cmp(&c) {
+                Ordering::Equal if b.fract() > 0.0 => Ordering::Less,
+                Ordering::Equal if b.fract() < 0.0 => Ordering::Greater,
+                ordering => ordering,
+            }
+        }
+    }
+}
+
+fn read_bytes<R: Read>(reader: &mut R, n: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut buf = Vec::new();
+    reader.take(n as u64).read_to_end(&mut buf)?;
+    if buf.len() != n {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+fn decode_terms<R: Read>(
+    reader: &mut R,
+    n: usize,
+    depth: usize,
+) -> Result<Vec<Value>, DecodeError> {
+    let mut terms = Vec::new();
+    for _ in 0..n {
+        terms.push(Value::decode_term(reader, depth)?);
+    }
+    Ok(terms)
+}
+
+fn decode_big<R: Read>(reader: &mut R, n: usize) -> Result<Value, DecodeError> {
+    let sign = reader.read_u8()?;
+    let bytes = read_bytes(reader, n)?;
+    let value = BigInt::from_bytes_le(Sign::Plus, &bytes);
+    if sign == 0 || value.is_zero() {
+        Ok(Value::Integer(value))
+    } else {
+        Ok(Value::Integer(-value))
+    }
+}
+
+fn decode_latin1<R: Read>(reader: &mut R, n: usize) -> Result<String, DecodeError> {
+    Ok(read_bytes(reader, n)?.into_iter().map(char::from).collect())
+}
+
+fn decode_utf8<R: Read>(reader: &mut R, n: usize) -> Result<String, DecodeError> {
+    Ok(String::from_utf8(read_bytes(reader, n)?)?)
+}
+
+fn encode_atom<W: Write>(name: &str, writer: &mut W) -> Result<(), EncodeError> {
+    if name.len() <= 255 {
+        writer.write_u8(SMALL_ATOM_UTF8_EXT)?;
+        writer.write_u8(name.len() as u8)?;
+    } else {
+        writer.write_u8(ATOM_UTF8_EXT)?;
+        writer.write_u16::<BigEndian>(u16::try_from(name.len()).map_err(|_| {
+            EncodeError::TooLongAtom {
+                name: name.to_owned(),
+            }
+        })?)?;
+    }
+    writer.write_all(name.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_encode_works() {
+        let values = [
+            Value::integer(1),
+            Value::integer(-300),
+            Value::integer(BigInt::from(1) << 100u32),
+            Value::integer(-(BigInt::from(1) << 100u32)),
+            Value::Float(1.5),
+            Value::atom("foo"),
+            Value::Tuple(vec![Value::atom("ok"), Value::Nil]),
+            Value::list([Value::integer(104), Value::integer(105)]),
+            Value::cons(Value::atom("a"), Value::atom("b")),
+            Value::Binary(b"abc".to_vec()),
+            Value::BitString {
+                bytes: vec![1, 0b1010_0000],
+                bits: 3,
+            },
+            Value::Map(vec![(Value::atom("k"), Value::integer(1000))]),
+            Value::Export {
+                module: "lists".to_owned(),
+                function: "map".to_owned(),
+                arity: 2,
+            },
+        ];
+        for value in values {
+            let bytes = value.to_bytes().unwrap();
+            assert_eq!(Value::from_bytes(&bytes).unwrap(), value);
+        }
+
+        // term_to_binary({ok, "hi"})
+        let bytes = [131, 104, 2, 100, 0, 2, 111, 107, 107, 0, 2, 104, 105];
+        let value = Value::from_bytes(&bytes).unwrap();
+        assert_eq!(value.to_string(), "{ok,[104,105]}");
+        assert_eq!(Value::atom("a\nb'\u{7f}").to_string(), "'a\\nb\\'\\x{7f}'");
+    }
+
+    #[test]
+    fn compare_works() {
+        assert_eq!(
+            Value::integer(1).compare(&Value::Float(1.0)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Value::integer(2).compare(&Value::Float(1.5)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::integer(100).compare(&Value::atom("a")),
+            Ordering::Less
+        );
+        assert_eq!(Value::Tuple(vec![]).compare(&Value::Nil), Ordering::Less);
+        assert_eq!(
+            Value::list([Value::integer(1)]).compare(&Value::list([Value::integer(1), Value::Nil])),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::improper_list(vec![Value::integer(1)], Value::atom("a"))
+                .compare(&Value::list([Value::integer(1), Value::integer(2)])),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::improper_list(vec![Value::integer(1)], Value::Binary(Vec::new()))
+                .compare(&Value::list([Value::integer(1), Value::integer(2)])),
+            Ordering::Greater
+        );
+
+        // Floats beyond the range of `i128`.
+        let big = BigInt::from(10).pow(40);
+        assert_eq!(
+            Value::Integer(big.clone()).compare(&Value::Float(1.0e40)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::Integer(big.clone() * 2).compare(&Value::Float(1.0e40)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::Integer(-big).compare(&Value::Float(-1.0e40)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::Integer(BigInt::from_f64(1.0e40).unwrap()).compare(&Value::Float(1.0e40)),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn improper_list_works() {
+        let value = Value::cons(
+            Value::integer(1),
+            Value::improper_list(vec![Value::integer(2)], Value::atom("a")),
+        );
+        assert_eq!(
+            value,
+            Value::improper_list(vec![Value::integer(1), Value::integer(2)], Value::atom("a"))
+        );
+        assert_eq!(value.to_string(), "[1,2|a]");
+        assert_eq!(value.to_vec(), None);
+        assert_eq!(Value::improper_list(Vec::new(), Value::Nil), Value::Nil);
+    }
+
+    #[test]
+    fn decode_long_list_works() {
+        let n = 1_000_000;
+        let mut bytes = vec![VERSION, LIST_EXT];
+        bytes.extend_from_slice(&(n as u32).to_be_bytes());
+        bytes.extend(std::iter::repeat_n([SMALL_INTEGER_EXT, 1], n).flatten());
+        bytes.push(NIL_EXT);
+        let value = Value::from_bytes(&bytes).unwrap();
+        assert_eq!(value.clone(), value);
+        assert_eq!(value.to_vec().map(|x| x.len()), Some(n));
+        assert_eq!(value.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn decode_deeply_nested_fails() {
+        let n = 1_000_000;
+        let mut bytes = vec![VERSION];
+        bytes.extend(std::iter::repeat_n([SMALL_TUPLE_EXT, 1], n).flatten());
+        bytes.push(NIL_EXT);
+        assert!(matches!(
+            Value::from_bytes(&bytes),
+            Err(DecodeError::TooDeepNesting { max: MAX_NESTING })
+        ));
+
+        let mut bytes = vec![VERSION];
+        bytes.extend(std::iter::repeat_n([SMALL_TUPLE_EXT, 1], MAX_NESTING).flatten());
+        bytes.push(NIL_EXT);
+        assert!(Value::from_bytes(&bytes).is_ok());
+    }
+}
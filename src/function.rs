@@ -0,0 +1,121 @@
+//! Functions in a code chunk.
+use crate::instruction::Instruction;
+use crate::term::{Atom, Label};
+use std::ops::Range;
+
+/// A function in a sequence of instructions.
+///
+/// The compiler emits the following header at the beginning of every function:
+///
+/// ```text
+/// {label, FuncInfoLabel}.
+/// {line, ...}.  % optional
+/// {func_info, Module, Name, Arity}.
+/// {label, EntryLabel}.
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: Atom,
+    pub arity: usize,
+
+    /// The label just before `func_info` (a jump to this label raises a `function_clause` error).
+    pub func_info_label: Option<Label>,
+
+    /// The label just after `func_info` (the entry point of the function).
+    pub entry_label: Option<Label>,
+
+    /// The indices of the instructions (including the header) of this function.
+    pub range: Range<usize>,
+}
+
+impl Function {
+    pub fn instructions<'a>(&self, instructions: &'a [Instruction]) -> &'a [Instruction] {
+        &instructions[self.range.clone()]
+    }
+}
+
+/// Splits `instructions` into functions.
+///
+/// Instructions before the first function are not included in any function.
+/// The trailing `int_code_end` instruction is also excluded.
+pub fn split_functions(instructions: &[Instruction]) -> Vec<Function> {
+    let mut functions: Vec<Function> = Vec::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::FuncInfo(x) => {
+                let mut start = i;
+                let mut func_info_label = None;
+                for (j, prev) in instructions[..i].iter().enumerate().rev() {
+                    match prev {
+                        Instruction::Line(_) => {}
+                        Instruction::Label(x) => {
+                            start = j;
+                            func_info_label = Some(Label { value: x.literal });
+                            break;
+                        }
+                        _ => break,
+                    }
+                }
+                let entry_label = match instructions.get(i + 1) {
+                    Some(Instruction::Label(x)) => Some(Label { value: x.literal }),
+                    _ => None,
+                };
+                if let Some(prev) = functions.last_mut() {
+                    prev.range.end = start.max(prev.range.start);
+                }
+                functions.push(Function {
+                    name: x.function,
+                    arity: x.arity,
+                    func_info_label,
+                    entry_label,
+                    range: start..instructions.len(),
+                });
+            }
+            Instruction::IntCodeEnd(_) => {
+                if let Some(prev) = functions.last_mut() {
+                    prev.range.end = i;
+                }
+            }
+            _ => {}
+        }
+    }
+    functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, FuncInfo, IntCodeEnd, Line, Return};
+
+    #[test]
+    fn split_functions_works() {
+        let label = |literal| Instruction::Label(instruction::Label { literal });
+        let func_info = |function, arity| {
+            Instruction::FuncInfo(FuncInfo {
+                module: Atom { value: 1 },
+                function: Atom { value: function },
+                arity,
+            })
+        };
+        let instructions = vec![
+            label(1),
+            Instruction::Line(Line { literal: 1 }),
+            func_info(2, 0),
+            label(2),
+            Instruction::Return(Return {}),
+            label(3),
+            func_info(3, 1),
+            label(4),
+            Instruction::Return(Return {}),
+            Instruction::IntCodeEnd(IntCodeEnd {}),
+        ];
+        let functions = split_functions(&instructions);
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name, Atom { value: 2 });
+        assert_eq!(functions[0].range, 0..5);
+        assert_eq!(functions[0].func_info_label, Some(Label { value: 1 }));
+        assert_eq!(functions[0].entry_label, Some(Label { value: 2 }));
+        assert_eq!(functions[1].arity, 1);
+        assert_eq!(functions[1].range, 5..9);
+    }
+}
@@ -0,0 +1,369 @@
+//! Semantic hashes of functions.
+//!
+//! [`function_hash()`] computes a fingerprint of the code of a function which does not depend on
+//! where the function and the entries it refers to are placed in the module:
+//!
+//! - labels defined in the function are numbered in order of their definitions,
+//!   and labels of other functions are resolved to their names and arities,
+//! - atoms, literals, imports, lambdas and `StrT` strings are resolved to their contents
+//!   (instead of their table indices),
+//! - `line` instructions are ignored, and
+//! - type information of typed registers is ignored.
+//!
+//! Thus, two functions have the same hash if their code is the same modulo the above,
+//! even if they come from different builds of a module.
+//!
+//! The hash is computed by 64-bit FNV-1a, so it is stable across platforms and Rust versions.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::hash::function_hashes;
+//! use beamcode::module::Module;
+//!
+//! let old = function_hashes(&Module::from_file("old/foo.beam")?);
+//! let new = function_hashes(&Module::from_file("new/foo.beam")?);
+//! for (function, hash) in &new {
+//!     if old.get(function) != Some(hash) {
+//!         println!("{function} needs redeployment");
+//!     }
+//! }
+//! # Ok::<(), beamcode::DecodeError>(())
+//! ```
+use crate::function::{split_functions, Function};
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::term::{Atom, Label, Literal, Register, Term};
+use crate::{Encode, Visit, VisitorMut};
+use std::collections::{BTreeMap, HashMap};
+
+/// Computes the semantic hash of `function` in `module`.
+///
+/// Use [`function_hashes()`] to hash all functions of a module.
+pub fn function_hash(module: &Module, function: &Function) -> u64 {
+    let functions = split_functions(&module.code.instructions);
+    hash(module, &headers(module, &functions), function)
+}
+
+/// Computes the semantic hashes of all functions in `module`.
+///
+/// The keys of the returned map are the names and arities of the functions (e.g., `"foo/2"`).
+pub fn function_hashes(module: &Module) -> BTreeMap<String, u64> {
+    let functions = split_functions(&module.code.instructions);
+    let headers = headers(module, &functions);
+    functions
+        .iter()
+        .map(|f| (function_name(module, f), hash(module, &headers, f)))
+        .collect()
+}
+
+fn function_name(module: &Module, function: &Function) -> String {
+    format!(
+        "{}/{}",
+        module.atom_name(function.name).unwrap_or_default(),
+        function.arity
+    )
+}
+
+// The header labels of `functions`, mapped to the names of the functions.
+fn headers(module: &Module, functions: &[Function]) -> HashMap<Label, String> {
+    functions
+        .iter()
+        .flat_map(|f| {
+            let name = function_name(module, f);
+            [
+                f.func_info_label.map(|x| (x, format!("{name}:func_info"))),
+                f.entry_label.map(|x| (x, format!("{name}:entry"))),
+            ]
+        })
+        .flatten()
+        .collect()
+}
+
+fn hash(module: &Module, headers: &HashMap<Label, String>, function: &Function) -> u64 {
+    let mut normalizer = Normalizer {
+        module,
+        headers,
+        local_labels: HashMap::new(),
+        symbols: BTreeMap::new(),
+        order: Vec::new(),
+    };
+    for instruction in function.instructions(&module.code.instructions) {
+        if let Instruction::Label(x) = instruction {
+            let next = normalizer.local_labels.len();
+            normalizer
+                .local_labels
+                .entry(Label { value: x.literal })
+                .or_insert(next);
+        }
+    }
+
+    let mut hasher = Fnv1a::new();
+    let mut buf = Vec::new();
+    for instruction in function.instructions(&module.code.instructions) {
+        if matches!(instruction, Instruction::Line(_)) {
+            continue;
+        }
+        let mut instruction = instruction.clone();
+        normalizer.normalize(&mut instruction);
+        buf.clear();
+        instruction
+            .encode(&mut buf)
+            .expect("encoding an instruction into a `Vec` never fails");
+        hasher.write(&buf);
+    }
+    for symbol in &normalizer.order {
+        hasher.write(&(symbol.len() as u64).to_be_bytes());
+        hasher.write(symbol);
+    }
+    hasher.finish()
+}
+
+// Replaces table indices with the indices of the corresponding symbols in order of appearance.
+struct Normalizer<'a> {
+    module: &'a Module,
+    headers: &'a HashMap<Label, String>,
+    local_labels: HashMap<Label, usize>,
+    symbols: BTreeMap<Vec<u8>, usize>,
+    order: Vec<Vec<u8>>,
+}
+
+impl<'a> Normalizer<'a> {
+    fn normalize(&mut self, instruction: &mut Instruction) {
+        match instruction {
+            Instruction::Label(x) => {
+                x.literal = self.label(Label { value: x.literal }) + 1;
+                return;
+            }
+            Instruction::CallExt(x) => x.destination = self.import(x.destination),
+            Instruction::CallExtLast(x) => x.destination = self.import(x.destination),
+            Instruction::CallExtOnly(x) => x.destination = self.import(x.destination),
+            Instruction::Bif0(x) => self.import_operand(&mut x.arg1),
+            Instruction::Bif1(x) => self.import_operand(&mut x.arg2),
+            Instruction::Bif2(x) => self.import_operand(&mut x.arg2),
+            Instruction::GcBif1(x) => self.import_operand(&mut x.arg3),
+            Instruction::GcBif2(x) => self.import_operand(&mut x.arg3),
+            Instruction::GcBif3(x) => self.import_operand(&mut x.arg3),
+            Instruction::MakeFun2(x) => self.lambda_operand(&mut x.arg1),
+            Instruction::MakeFun3(x) => self.lambda_operand(&mut x.arg1),
+            Instruction::BsPutString(x) => {
+                if let (Some(length), Term::Usize(offset)) = (x.arg1.to_usize(), &mut x.arg2) {
+                    *offset = self.string(*offset, length);
+                }
+            }
+            Instruction::BsMatchString(x) => {
+                if let (Some(bits), Term::Usize(offset)) = (x.arg3.to_usize(), &mut x.arg4) {
+                    *offset = self.string(*offset, bits.div_ceil(8));
+                }
+            }
+            _ => {}
+        }
+        instruction.visit_mut(self);
+    }
+
+    fn symbol(&mut self, kind: &[u8], contents: &[u8]) -> usize {
+        let symbol = [kind, b":", contents].concat();
+        let next = self.symbols.len();
+        *self.symbols.entry(symbol.clone()).or_insert_with(|| {
+            self.order.push(symbol);
+            next
+        })
+    }
+
+    fn label(&mut self, label: Label) -> usize {
+        if let Some(i) = self.local_labels.get(&label) {
+            self.symbol(b"local", i.to_string().as_bytes())
+        } else if let Some(name) = self.headers.get(&label) {
+            self.symbol(b"function", name.as_bytes())
+        } else {
+            self.symbol(b"unknown", label.value.to_string().as_bytes())
+        }
+    }
+
+    fn import(&mut self, index: usize) -> usize {
+        let name = match self.module.imports.get(index) {
+            Some(x) => format!(
+                "{}:{}/{}",
+                self.module.atom_name(x.module).unwrap_or_default(),
+                self.module.atom_name(x.function).unwrap_or_default(),
+                x.arity
+            ),
+            None => format!("#{index}"),
+        };
+        self.symbol(b"import", name.as_bytes())
+    }
+
+    fn import_operand(&mut self, term: &mut Term) {
+        if let Term::Usize(index) = term {
+            *index = self.import(*index);
+        }
+    }
+
+    fn lambda_operand(&mut self, term: &mut Term) {
+        if let Term::Usize(index) = term {
+            *index = self.lambda(*index);
+        }
+    }
+
+    fn lambda(&mut self, index: usize) -> usize {
+        let name = match self.module.lambdas.get(index) {
+            Some(x) => format!(
+                "{}/{}/{}",
+                self.module.atom_name(x.function).unwrap_or_default(),
+                x.arity,
+                x.num_free
+            ),
+            None => format!("#{index}"),
+        };
+        self.symbol(b"lambda", name.as_bytes())
+    }
+
+    fn string(&mut self, offset: usize, len: usize) -> usize {
+        let bytes = self
+            .module
+            .other_chunk(b"StrT")
+            .and_then(|x| x.get(offset..offset.checked_add(len)?))
+            .unwrap_or_default();
+        self.symbol(b"string", bytes)
+    }
+}
+
+impl<'a> VisitorMut for Normalizer<'a> {
+    fn visit_label_mut(&mut self, label: &mut Label) {
+        if label.value != 0 {
+            label.value = self.label(*label) + 1;
+        }
+    }
+
+    fn visit_atom_mut(&mut self, atom: &mut Atom) {
+        if atom.value != 0 {
+            let name = self.module.atom_name(*atom).unwrap_or_default().to_owned();
+            atom.value = self.symbol(b"atom", name.as_bytes()) + 1;
+        }
+    }
+
+    fn visit_literal_mut(&mut self, literal: &mut Literal) {
+        let module = self.module;
+        let etf = module
+            .literals
+            .get(literal.value)
+            .map(|x| x.as_slice())
+            .unwrap_or_default();
+        literal.value = self.symbol(b"literal", etf);
+    }
+
+    fn visit_term_mut(&mut self, term: &mut Term) {
+        if let Term::TypedRegister(x) = term {
+            *term = Register::Typed(*x).untyped().into();
+        }
+    }
+
+    fn visit_register_mut(&mut self, register: &mut Register) {
+        *register = register.untyped();
+    }
+}
+
+// 64-bit FNV-1a.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{CallExtOnly, CallOnly, Line, Move, Return};
+    use crate::term::{Register, TypedRegister, XRegister};
+
+    #[test]
+    fn function_hash_works() {
+        let build = |padding: bool, atom: &str| {
+            let mut builder = ModuleBuilder::new("foo");
+            if padding {
+                let mut function = builder.function("padding", 0);
+                function.import("bar", "padding", 0);
+                let padding = function.atom("padding");
+                let label = function.new_label();
+                function
+                    .label(label)
+                    .push(Move {
+                        src: Term::Atom(padding),
+                        dst: Register::x(0),
+                    })
+                    .push(CallExtOnly {
+                        arity: 0,
+                        destination: 0,
+                    });
+            }
+            let mut function = builder.function("main", 0);
+            let bar = function.import("bar", "baz", 1);
+            let atom = function.atom(atom);
+            function
+                .push(Move {
+                    src: Term::Atom(atom),
+                    dst: Register::x(0),
+                })
+                .push(CallExtOnly {
+                    arity: 1,
+                    destination: bar,
+                });
+            function_hashes(&builder.build())["main/0"]
+        };
+
+        assert_eq!(build(false, "ok"), build(true, "ok"));
+        assert_ne!(build(false, "ok"), build(false, "error"));
+    }
+
+    #[test]
+    fn function_hash_of_local_calls_works() {
+        let build = |callee: &str, typed: bool| {
+            let mut builder = ModuleBuilder::new("foo");
+            let mut function = builder.function("a", 0);
+            let a = function.entry_label();
+            function.push(Return {});
+            let mut function = builder.function("b", 0);
+            let b = function.entry_label();
+            function.push(Return {});
+            let mut function = builder.function("main", 0);
+            let destination = if typed {
+                Register::Typed(TypedRegister::X {
+                    register: XRegister { value: 1, ty: None },
+                    ty: 0,
+                })
+            } else {
+                Register::x(1)
+            };
+            if typed {
+                function.push(Line { literal: 1 });
+            }
+            function
+                .push(Move {
+                    src: Term::x(0),
+                    dst: destination,
+                })
+                .push(CallOnly {
+                    arity: 0,
+                    label: if callee == "a" { a } else { b },
+                });
+            function_hashes(&builder.build())["main/0"]
+        };
+
+        assert_eq!(build("a", false), build("a", true));
+        assert_ne!(build("a", false), build("b", false));
+    }
+}
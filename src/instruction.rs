@@ -6,14 +6,18 @@
 //! - [erlang/otp/lib/compiler/src/genop.tab](https://github.com/erlang/otp/blob/master/lib/compiler/src/genop.tab)
 //! - erlang/otp/lib/compiler/src/beam_opcodes.erl (generated file)
 use crate::term::{self, Allocation, Atom, List, Register, Term, YRegister};
-use crate::{Decode, Encode};
+use crate::{Decode, Encode, Visit};
 use beamcode_derive::Opcode;
+#[cfg(feature = "proptest")]
+use beamcode_derive::ProptestArbitrary;
 
 pub trait Opcode {
     const CODE: u8;
 }
 
-#[derive(Debug, Clone, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Instruction {
     Allocate(Allocate),
     AllocateHeap(AllocateHeap),
@@ -224,13 +228,34 @@ pub enum Instruction {
     WaitTimeout(WaitTimeout),
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+impl Instruction {
+    /// Returns the operands of this instruction as generic terms (in the order of encoding).
+    pub fn operands(&self) -> Vec<Term> {
+        let mut bytes = Vec::new();
+        self.encode(&mut bytes)
+            .expect("encoding into a `Vec` never fails");
+        let mut reader = &bytes[1..];
+        let mut operands = Vec::new();
+        while !reader.is_empty() {
+            operands.push(
+                Term::decode(&mut reader).expect("re-decoding an encoded operand never fails"),
+            );
+        }
+        operands
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(1)]
 pub struct Label {
     pub literal: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(2)]
 pub struct FuncInfo {
     pub module: Atom,
@@ -238,40 +263,55 @@ pub struct FuncInfo {
     pub arity: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(3)]
 pub struct IntCodeEnd {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(4)]
 pub struct Call {
     pub arity: usize,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(5)]
 pub struct CallLast {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(6)]
 pub struct CallOnly {
     pub arity: usize,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(7)]
 pub struct CallExt {
     pub arity: usize,
     pub destination: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(8)]
 pub struct CallExtLast {
     pub arity: usize,
@@ -279,40 +319,58 @@ pub struct CallExtLast {
     pub deallocate: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(9)]
 pub struct Bif0 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(10)]
 pub struct Bif1 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(11)]
 pub struct Bif2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(12)]
 pub struct Allocate {
     pub stack_need: Allocation,
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(13)]
 pub struct AllocateHeap {
     pub stack_need: Allocation,
@@ -320,14 +378,20 @@ pub struct AllocateHeap {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(14)]
 pub struct AllocateZero {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::allocation_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(15)]
 pub struct AllocateHeapZero {
     pub stack_need: Allocation,
@@ -335,69 +399,99 @@ pub struct AllocateHeapZero {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(16)]
 pub struct TestHeap {
     pub heap_need: Allocation,
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(17)]
 pub struct Init {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(18)]
 pub struct Deallocate {
     pub n: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(19)]
 pub struct Return {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(20)]
 pub struct Send {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(21)]
 pub struct RemoveMessage {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(22)]
 pub struct Timeout {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(23)]
 pub struct LoopRec {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(24)]
 pub struct LoopRecEnd {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(25)]
 pub struct Wait {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(26)]
 pub struct WaitTimeout {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(27)]
 pub struct MPlus {
     pub arg1: Term,
@@ -407,7 +501,9 @@ pub struct MPlus {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(28)]
 pub struct MMinus {
     pub arg1: Term,
@@ -417,7 +513,9 @@ pub struct MMinus {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(29)]
 pub struct MTimes {
     pub arg1: Term,
@@ -427,7 +525,9 @@ pub struct MTimes {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(30)]
 pub struct MDiv {
     pub arg1: Term,
@@ -437,7 +537,9 @@ pub struct MDiv {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(31)]
 pub struct IntDiv {
     pub arg1: Term,
@@ -447,7 +549,9 @@ pub struct IntDiv {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(32)]
 pub struct IntRem {
     pub arg1: Term,
@@ -457,7 +561,9 @@ pub struct IntRem {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(33)]
 pub struct IntBand {
     pub arg1: Term,
@@ -467,7 +573,9 @@ pub struct IntBand {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(34)]
 pub struct IntBor {
     pub arg1: Term,
@@ -477,7 +585,9 @@ pub struct IntBor {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(35)]
 pub struct IntBxor {
     pub arg1: Term,
@@ -487,7 +597,9 @@ pub struct IntBxor {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(36)]
 pub struct IntBsl {
     pub arg1: Term,
@@ -497,7 +609,9 @@ pub struct IntBsl {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(37)]
 pub struct IntBsr {
     pub arg1: Term,
@@ -507,7 +621,9 @@ pub struct IntBsr {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(38)]
 pub struct IntBnot {
     pub arg1: Term,
@@ -515,39 +631,53 @@ pub struct IntBnot {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(39)]
 pub struct IsLt {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(40)]
 pub struct IsGe {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(41)]
 pub struct IsEq {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(42)]
 pub struct IsNe {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(43)]
 pub struct IsEqExact {
     pub label: term::Label,
@@ -555,7 +685,9 @@ pub struct IsEqExact {
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(44)]
 pub struct IsNeExact {
     pub label: term::Label,
@@ -563,63 +695,81 @@ pub struct IsNeExact {
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(45)]
 pub struct IsInteger {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(46)]
 pub struct IsFloat {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(47)]
 pub struct IsNumber {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(48)]
 pub struct IsAtom {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(49)]
 pub struct IsPid {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(50)]
 pub struct IsReference {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(51)]
 pub struct IsPort {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(52)]
 pub struct IsNil {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(53)]
 pub struct IsBinary {
     pub label: term::Label,
@@ -627,35 +777,45 @@ pub struct IsBinary {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(54)]
 pub struct IsConstant {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(55)]
 pub struct IsList {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(56)]
 pub struct IsNonemptyList {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(57)]
 pub struct IsTuple {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(58)]
 pub struct TestArity {
     pub label: term::Label,
@@ -663,49 +823,69 @@ pub struct TestArity {
     pub arity: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(59)]
 pub struct SelectVal {
     pub arg: Term,
     pub fail_label: term::Label,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::value_label_pairs))]
     pub destinations: List,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(60)]
 pub struct SelectTupleArity {
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::arity_label_pairs))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(61)]
 pub struct Jump {
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(62)]
 pub struct Catch {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(63)]
 pub struct CatchEnd {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(64)]
 pub struct Move {
     pub src: Term,
     pub dst: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(65)]
 pub struct GetList {
     pub source: Term,
@@ -713,7 +893,9 @@ pub struct GetList {
     pub tail: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(66)]
 pub struct GetTupleElement {
     pub source: Register,
@@ -721,16 +903,22 @@ pub struct GetTupleElement {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(67)]
 pub struct SetTupleElement {
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(68)]
 pub struct PutString {
     pub arg1: Term,
@@ -738,7 +926,9 @@ pub struct PutString {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(69)]
 pub struct PutList {
     pub head: Term,
@@ -746,43 +936,60 @@ pub struct PutList {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(70)]
 pub struct PutTuple {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(71)]
 pub struct Put {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(72)]
 pub struct Badmatch {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(73)]
 pub struct IfEnd {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(74)]
 pub struct CaseEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(75)]
 pub struct CallFun {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(76)]
 pub struct MakeFun {
     pub arg1: Term,
@@ -790,14 +997,19 @@ pub struct MakeFun {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(77)]
 pub struct IsFunction {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(78)]
 pub struct CallExtOnly {
     pub arity: usize,
@@ -805,7 +1017,9 @@ pub struct CallExtOnly {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(79)]
 pub struct BsStartMatch {
     pub arg1: Term,
@@ -813,7 +1027,9 @@ pub struct BsStartMatch {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(80)]
 pub struct BsGetInteger {
     pub arg1: Term,
@@ -824,7 +1040,9 @@ pub struct BsGetInteger {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(81)]
 pub struct BsGetFloat {
     pub arg1: Term,
@@ -835,7 +1053,9 @@ pub struct BsGetFloat {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(82)]
 pub struct BsGetBinary {
     pub arg1: Term,
@@ -845,7 +1065,9 @@ pub struct BsGetBinary {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(83)]
 pub struct BsSkipBits {
     pub arg1: Term,
@@ -855,28 +1077,36 @@ pub struct BsSkipBits {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(84)]
 pub struct BsTestTail {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(85)]
 pub struct BsSave {
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(86)]
 pub struct BsRestore {
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(87)]
 pub struct BsInit {
     pub arg1: Term,
@@ -884,175 +1114,261 @@ pub struct BsInit {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(88)]
 pub struct BsFinal {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(89)]
 pub struct BsPutInteger {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(90)]
 pub struct BsPutBinary {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(91)]
 pub struct BsPutFloat {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(92)]
 pub struct BsPutString {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(93)]
 pub struct BsNeedBuf {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(94)]
 pub struct Fclearerror {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(95)]
 pub struct Fcheckerror {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(96)]
 pub struct Fmove {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_source))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_destination))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(97)]
 pub struct Fconv {
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(98)]
 pub struct Fadd {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(99)]
 pub struct Fsub {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(100)]
 pub struct Fmul {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(101)]
 pub struct Fdiv {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(102)]
 pub struct Fnegate {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::float_register_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(103)]
 pub struct MakeFun2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(104)]
 pub struct Try {
     pub register: Register,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(105)]
 pub struct TryEnd {
     pub register: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(106)]
 pub struct TryCase {
     pub register: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(107)]
 pub struct TryCaseEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(108)]
 pub struct Raise {
     pub stacktrace: Term,
     pub exc_value: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(109)]
 pub struct BsInit2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg6: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(110)]
 pub struct BsBitsToBytes {
     pub arg1: Term,
@@ -1060,145 +1376,217 @@ pub struct BsBitsToBytes {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(111)]
 pub struct BsAdd {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(112)]
 pub struct Apply {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(113)]
 pub struct ApplyLast {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(114)]
 pub struct IsBoolean {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(115)]
 pub struct IsFunction2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(116)]
 pub struct BsStartMatch2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(117)]
 pub struct BsGetInteger2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg6: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(118)]
 pub struct BsGetFloat2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg6: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(119)]
 pub struct BsGetBinary2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg6: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(120)]
 pub struct BsSkipBits2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(121)]
 pub struct BsTestTail2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(122)]
 pub struct BsSave2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(123)]
 pub struct BsRestore2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(124)]
 pub struct GcBif1 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(125)]
 pub struct GcBif2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg6: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(126)]
 pub struct BsFinal2 {
     pub arg1: Term,
@@ -1206,7 +1594,9 @@ pub struct BsFinal2 {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(127)]
 pub struct BsBitsToBytes2 {
     pub arg1: Term,
@@ -1214,264 +1604,402 @@ pub struct BsBitsToBytes2 {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(128)]
 pub struct PutLiteral {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(129)]
 pub struct IsBitstr {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(130)]
 pub struct BsContextToBinary {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(131)]
 pub struct BsTestUnit {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(132)]
 pub struct BsMatchString {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(133)]
 pub struct BsInitWritable {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(134)]
 pub struct BsAppend {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
     pub arg6: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg7: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg8: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(135)]
 pub struct BsPrivateAppend {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(136)]
 pub struct Trim {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(137)]
 pub struct BsInitBits {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(138)]
 pub struct BsGetUtf8 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(139)]
 pub struct BsSkipUtf8 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(140)]
 pub struct BsGetUtf16 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(141)]
 pub struct BsSkipUtf16 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(142)]
 pub struct BsGetUtf32 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(143)]
 pub struct BsSkipUtf32 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(144)]
 pub struct BsUtf8Size {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(145)]
 pub struct BsPutUtf8 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(146)]
 pub struct BsUtf16Size {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(147)]
 pub struct BsPutUtf16 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(148)]
 pub struct BsPutUtf32 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(149)]
 pub struct OnLoad {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(150)]
 pub struct RecvMark {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(151)]
 pub struct RecvSet {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(152)]
 pub struct GcBif3 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
     pub arg4: Term,
     pub arg5: Term,
     pub arg6: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(153)]
 pub struct Line {
     pub literal: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(154)]
 pub struct PutMapAssoc {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::source_pairs))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(155)]
 pub struct PutMapExact {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::source_pairs))]
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(156)]
 pub struct IsMap {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(157)]
 pub struct HasMapFields {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::source_list))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(158)]
 pub struct GetMapElement {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::key_destination_pairs))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(159)]
 pub struct IsTaggedTuple {
     pub label: term::Label,
@@ -1480,36 +2008,50 @@ pub struct IsTaggedTuple {
     pub atom: Atom,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(160)]
 pub struct BuildStacktrace {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(161)]
 pub struct RawRaise {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(162)]
 pub struct GetHd {
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(163)]
 pub struct GetTl {
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(164)]
 pub struct PutTuple2 {
     pub destination: Register,
     pub elements: List,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(165)]
 pub struct BsGetTail {
     pub context: Term,
@@ -1517,7 +2059,9 @@ pub struct BsGetTail {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(166)]
 pub struct BsStartMatch3 {
     pub fail: term::Label,
@@ -1526,7 +2070,9 @@ pub struct BsStartMatch3 {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(167)]
 pub struct BsGetPosition {
     pub context: Term,
@@ -1534,92 +2080,136 @@ pub struct BsGetPosition {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(168)]
 pub struct BsSetPosition {
     pub context: Term,
     pub position: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(169)]
 pub struct Swap {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(170)]
 pub struct BsStartMatch4 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_or_atom))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(171)]
 pub struct MakeFun3 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::source_list))]
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(172)]
 pub struct InitYregs {
     pub registers: Vec<YRegister>,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(173)]
 pub struct RecvMarkerBind {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(174)]
 pub struct RecvMarkerClear {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(175)]
 pub struct RecvMarkerReserve {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(176)]
 pub struct RecvMarkerUse {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(177)]
 pub struct BsCreateBin {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::label_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::allocation_operand))]
     pub arg2: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg3: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg4: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::register_operand))]
     pub arg5: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::bs_create_bin_segments))]
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(178)]
 pub struct CallFun2 {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::atom_operand))]
     pub arg1: Term,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary_impls::usize_operand))]
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(179)]
 pub struct NifStart {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, PartialEq, Opcode, Decode, Encode, Visit)]
+#[cfg_attr(feature = "proptest", derive(ProptestArbitrary))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[opcode(180)]
 pub struct Badrecord {
     pub arg1: Term,
@@ -0,0 +1,1154 @@
+//! Reference interpreter for a subset of BEAM instructions.
+//!
+//! [`Interpreter`] executes the decoded instructions of a [`Module`] directly.
+//! It is intended for testing code generators without an Erlang runtime, thus it is simple
+//! rather than fast, and it only supports sequential code:
+//!
+//! - x, y and floating point registers,
+//! - `move`, `swap`, `init`, `init_yregs`,
+//! - `call`, `call_last`, `call_only`, `return`, and `call_ext*` to [supported BIFs](Interpreter::call_bif),
+//! - `allocate*`, `deallocate`, `trim`, `test_heap`,
+//! - comparisons (`is_lt`, `is_ge`, `is_eq*`, `is_ne*`), type tests, `test_arity`, `is_tagged_tuple`,
+//! - `select_val`, `select_tuple_arity`, `jump`,
+//! - `get_list`, `get_hd`, `get_tl`, `put_list`, `put_tuple`, `put`, `put_tuple2`, `get_tuple_element`,
+//! - `bif*` and `gc_bif*` calling supported BIFs,
+//! - floating point instructions, and
+//! - `badmatch`, `case_end`, `if_end`, `try_case_end`, `badrecord`.
+//!
+//! Other instructions (e.g., message passing, exception handling, binaries and maps) make the
+//! execution fail with [`InterpretError::UnsupportedInstruction`].
+use crate::etf::Value;
+use crate::function::split_functions;
+use crate::instruction::{Instruction, IntCodeEnd};
+use crate::module::Module;
+use crate::term::{Atom, FloatingPointRegister, Label, Register, Term};
+use crate::DecodeError;
+use num::{BigInt, FromPrimitive as _, Integer as _, Signed as _, ToPrimitive as _, Zero as _};
+use std::collections::HashMap;
+
+/// The default value of [`Interpreter::max_steps`].
+pub const DEFAULT_MAX_STEPS: usize = 1_000_000;
+
+/// Maximum number of bits an integer can be shifted to the left (by `bsl`, or by `bsr` with a
+/// negative shift).
+///
+/// Larger shifts raise a `system_limit` exception instead of allocating huge integers.
+pub const MAX_SHIFT: i64 = 1 << 24;
+
+/// Interpretation errors.
+#[derive(Debug, thiserror::Error)]
+pub enum InterpretError {
+    #[error("exception error: {reason}")]
+    Exception { reason: Value },
+
+    #[error("unsupported instruction at {index}: {instruction:?}")]
+    UnsupportedInstruction {
+        index: usize,
+        instruction: Box<Instruction>,
+    },
+
+    #[error("undefined function: {module}:{function}/{arity}")]
+    UndefinedFunction {
+        module: String,
+        function: String,
+        arity: usize,
+    },
+
+    #[error("undefined label: {label:?}")]
+    UndefinedLabel { label: Label },
+
+    #[error("uninitialized register: {register:?}")]
+    UninitializedRegister { register: Register },
+
+    #[error("invalid operand at {index}: {operand:?}")]
+    InvalidOperand { index: usize, operand: Term },
+
+    #[error("the number of steps exceeded the limit {max_steps}")]
+    StepLimitExceeded { max_steps: usize },
+
+    #[error(transparent)]
+    DecodeError(#[from] DecodeError),
+}
+
+/// Reference interpreter.
+#[derive(Debug)]
+pub struct Interpreter<'a> {
+    module: &'a Module,
+    literals: Vec<Value>,
+    labels: HashMap<Label, usize>,
+
+    /// The maximum number of instructions executed by a call (to detect infinite loops).
+    pub max_steps: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Makes a new interpreter for `module`.
+    ///
+    /// This fails if the literal table of `module` cannot be decoded.
+    pub fn new(module: &'a Module) -> Result<Self, InterpretError> {
+        let literals = module
+            .literals
+            .iter()
+            .map(|x| Value::from_bytes(x))
+            .collect::<Result<_, _>>()?;
+        let labels = module
+            .code
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| match x {
+                Instruction::Label(x) => Some((Label { value: x.literal }, i)),
+                _ => None,
+            })
+            .collect();
+        Ok(Self {
+            module,
+            literals,
+            labels,
+            max_steps: DEFAULT_MAX_STEPS,
+        })
+    }
+
+    /// Calls the function `name/args.len()` (either exported or local) and returns its result.
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, InterpretError> {
+        let function = split_functions(&self.module.code.instructions)
+            .into_iter()
+            .find(|x| x.arity == args.len() && self.module.atom_name(x.name) == Some(name))
+            .and_then(|x| x.entry_label)
+            .ok_or_else(|| InterpretError::UndefinedFunction {
+                module: self.module.name().unwrap_or("?").to_owned(),
+                function: name.to_owned(),
+                arity: args.len(),
+            })?;
+        self.call_label(function, args)
+    }
+
+    /// Calls the function whose entry label is `label` and returns its result.
+    pub fn call_label(&self, label: Label, args: Vec<Value>) -> Result<Value, InterpretError> {
+        let mut process = Process {
+            interpreter: self,
+            x: args.into_iter().map(Some).collect(),
+            y: Vec::new(),
+            f: Vec::new(),
+            continuations: Vec::new(),
+            pending_tuple: None,
+            ip: self.label_index(label)?,
+        };
+        process.run()
+    }
+
+    fn label_index(&self, label: Label) -> Result<usize, InterpretError> {
+        self.labels
+            .get(&label)
+            .copied()
+            .ok_or(InterpretError::UndefinedLabel { label })
+    }
+
+    /// Calls a BIF.
+    ///
+    /// The following functions of the `erlang` module are supported:
+    ///
+    /// - arithmetic: `+`, `-`, `*`, `/`, `div`, `rem`, `band`, `bor`, `bxor`, `bsl`, `bsr`, `bnot`,
+    ///   `abs`, `float`, `trunc`
+    /// - comparisons: `==`, `/=`, `=:=`, `=/=`, `<`, `>`, `=<`, `>=`
+    /// - boolean operators: `and`, `or`, `xor`, `not`
+    /// - type tests: `is_atom`, `is_boolean`, `is_float`, `is_integer`, `is_list`, `is_number`,
+    ///   `is_tuple`, `is_binary`, `is_map`
+    /// - others: `hd`, `tl`, `length`, `element`, `setelement`, `tuple_size`, `self`
+    ///   (returns `[]` as there are no processes)
+    ///
+    /// Returns `Ok(None)` if the BIF is not supported.
+    pub fn call_bif(
+        &self,
+        module: &str,
+        function: &str,
+        args: &[Value],
+    ) -> Result<Option<Value>, InterpretError> {
+        if module != "erlang" {
+            return Ok(None);
+        }
+        let badarg = || InterpretError::Exception {
+            reason: Value::atom("badarg"),
+        };
+        let badarith = || InterpretError::Exception {
+            reason: Value::atom("badarith"),
+        };
+        let value = match (function, args) {
+            ("+", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a + b),
+            ("-", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a - b),
+            ("*", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a * b),
+            ("+" | "-" | "*" | "/", [a, b]) => {
+                let (Some(a), Some(b)) = (to_f64(a), to_f64(b)) else {
+                    return Err(badarith());
+                };
+                let x = match function {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    _ => a / b,
+                };
+                if !x.is_finite() {
+                    return Err(badarith());
+                }
+                Value::Float(x)
+            }
+            ("-", [Value::Integer(a)]) => Value::Integer(-a),
+            ("-", [Value::Float(a)]) => Value::Float(-a),
+            ("+", [a]) if a.is_number() => a.clone(),
+            ("div" | "rem", [Value::Integer(_), Value::Integer(b)]) if b.is_zero() => {
+                return Err(badarith())
+            }
+            ("div", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a / b),
+            ("rem", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a % b),
+            ("band", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a & b),
+            ("bor", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a | b),
+            ("bxor", [Value::Integer(a), Value::Integer(b)]) => Value::Integer(a ^ b),
+            ("bsl" | "bsr", [Value::Integer(a), Value::Integer(b)]) => {
+                let shift = b.to_i64().ok_or_else(badarith)?;
+                let shift = if function == "bsl" {
+                    shift
+                } else {
+                    shift.checked_neg().ok_or_else(badarith)?
+                };
+                if shift > MAX_SHIFT {
+                    return Err(exception(Value::atom("system_limit")));
+                }
+                if shift >= 0 {
+                    Value::Integer(a << shift as u64)
+                } else {
+                    // Arithmetic shift (rounding towards negative infinity). Shifting by more
+                    // than the bit length leaves only the sign.
+                    let shift = shift.unsigned_abs().min(a.bits() + 1);
+                    let divisor = BigInt::from(1) << shift;
+                    Value::Integer(a.div_floor(&divisor))
+                }
+            }
+            ("bnot", [Value::Integer(a)]) => Value::Integer(!a),
+            ("abs", [Value::Integer(a)]) => Value::Integer(a.abs()),
+            ("abs", [Value::Float(a)]) => Value::Float(a.abs()),
+            ("float", [a]) => Value::Float(to_f64(a).ok_or_else(badarg)?),
+            ("trunc", [Value::Integer(a)]) => Value::Integer(a.clone()),
+            ("trunc", [Value::Float(a)]) => {
+                Value::Integer(BigInt::from_f64(a.trunc()).ok_or_else(badarg)?)
+            }
+            ("==", [a, b]) => boolean(a.compare(b).is_eq()),
+            ("/=", [a, b]) => boolean(!a.compare(b).is_eq()),
+            ("=:=", [a, b]) => boolean(a == b),
+            ("=/=", [a, b]) => boolean(a != b),
+            ("<", [a, b]) => boolean(a.compare(b).is_lt()),
+            (">", [a, b]) => boolean(a.compare(b).is_gt()),
+            ("=<", [a, b]) => boolean(a.compare(b).is_le()),
+            (">=", [a, b]) => boolean(a.compare(b).is_ge()),
+            ("and" | "or" | "xor", [a, b]) if a.is_boolean() && b.is_boolean() => {
+                let a = *a == Value::atom("true");
+                let b = *b == Value::atom("true");
+                boolean(match function {
+                    "and" => a && b,
+                    "or" => a || b,
+                    _ => a ^ b,
+                })
+            }
+            ("not", [a]) if a.is_boolean() => boolean(*a == Value::atom("false")),
+            ("is_atom", [a]) => boolean(matches!(a, Value::Atom(_))),
+            ("is_boolean", [a]) => boolean(a.is_boolean()),
+            ("is_float", [a]) => boolean(matches!(a, Value::Float(_))),
+            ("is_integer", [a]) => boolean(matches!(a, Value::Integer(_))),
+            ("is_list", [a]) => boolean(matches!(a, Value::Nil | Value::List(..))),
+            ("is_number", [a]) => boolean(a.is_number()),
+            ("is_tuple", [a]) => boolean(matches!(a, Value::Tuple(_))),
+            ("is_binary", [a]) => boolean(matches!(a, Value::Binary(_))),
+            ("is_map", [a]) => boolean(matches!(a, Value::Map(_))),
+            ("hd", [Value::List(items, _)]) => items[0].clone(),
+            ("tl", [Value::List(items, tail)]) => {
+                Value::improper_list(items[1..].to_vec(), (**tail).clone())
+            }
+            ("length", [a]) => Value::integer(a.to_vec().ok_or_else(badarg)?.len()),
+            ("element", [Value::Integer(i), Value::Tuple(x)]) => i
+                .to_usize()
+                .and_then(|i| i.checked_sub(1))
+                .and_then(|i| x.get(i))
+                .cloned()
+                .ok_or_else(badarg)?,
+            ("setelement", [Value::Integer(i), Value::Tuple(x), v]) => {
+                let mut x = x.clone();
+                let i = i
+                    .to_usize()
+                    .and_then(|i| i.checked_sub(1))
+                    .filter(|&i| i < x.len())
+                    .ok_or_else(badarg)?;
+                x[i] = v.clone();
+                Value::Tuple(x)
+            }
+            ("tuple_size", [Value::Tuple(x)]) => Value::integer(x.len()),
+            ("self", []) => Value::Nil,
+            (
+                "+" | "-" | "*" | "div" | "rem" | "band" | "bor" | "bxor" | "bsl" | "bsr" | "bnot"
+                | "abs" | "trunc",
+                _,
+            ) => return Err(badarith()),
+            (
+                "and" | "or" | "xor" | "not" | "hd" | "tl" | "element" | "setelement"
+                | "tuple_size",
+                _,
+            ) => return Err(badarg()),
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+}
+
+#[derive(Debug)]
+struct Process<'a, 'b> {
+    interpreter: &'b Interpreter<'a>,
+    x: Vec<Option<Value>>,
+    y: Vec<Vec<Option<Value>>>,
+    f: Vec<f64>,
+    continuations: Vec<usize>,
+    pending_tuple: Option<(Register, usize, Vec<Value>)>,
+    ip: usize,
+}
+
+impl<'a, 'b> Process<'a, 'b> {
+    fn run(&mut self) -> Result<Value, InterpretError> {
+        let instructions = &self.interpreter.module.code.instructions;
+        for _ in 0..self.interpreter.max_steps {
+            let index = self.ip;
+            let instruction =
+                instructions
+                    .get(index)
+                    .ok_or_else(|| InterpretError::UnsupportedInstruction {
+                        index,
+                        instruction: Box::new(Instruction::IntCodeEnd(IntCodeEnd {})),
+                    })?;
+            self.ip += 1;
+            if let Some(result) = self.step(index, instruction)? {
+                return Ok(result);
+            }
+        }
+        Err(InterpretError::StepLimitExceeded {
+            max_steps: self.interpreter.max_steps,
+        })
+    }
+
+    // Executes an instruction. Returns `Some(_)` if the outermost function returned.
+    fn step(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+    ) -> Result<Option<Value>, InterpretError> {
+        if self.pending_tuple.is_some() {
+            // `put_tuple` must be followed by `Arity` `put` instructions.
+            let Instruction::Put(x) = instruction else {
+                return Err(unsupported(index, instruction));
+            };
+            let value = self.value(index, &x.arg1)?;
+            let (_, arity, elements) = self.pending_tuple.as_mut().expect("unreachable");
+            elements.push(value);
+            if elements.len() == *arity {
+                let (destination, _, elements) = self.pending_tuple.take().expect("unreachable");
+                self.set(&destination, Value::Tuple(elements))?;
+            }
+            return Ok(None);
+        }
+
+        let malformed = || unsupported(index, instruction);
+        let label = |term: &Term| term.to_label().ok_or_else(malformed);
+        let number = |term: &Term| term.to_usize().ok_or_else(malformed);
+        let register = |term: &Term| term.to_register().ok_or_else(malformed);
+        let float_register = |term: &Term| match term {
+            Term::FloatingPointRegister(x) => Ok(*x),
+            _ => Err(malformed()),
+        };
+        match instruction {
+            Instruction::Label(_)
+            | Instruction::Line(_)
+            | Instruction::TestHeap(_)
+            | Instruction::Fclearerror(_)
+            | Instruction::Fcheckerror(_) => {}
+            Instruction::FuncInfo(x) => {
+                let args = (0..x.arity)
+                    .map(|i| self.value(index, &Term::x(i)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let name =
+                    |atom| Value::atom(self.interpreter.module.atom_name(atom).unwrap_or("[]"));
+                return Err(exception(Value::Tuple(vec![
+                    Value::atom("function_clause"),
+                    name(x.module),
+                    name(x.function),
+                    Value::list(args),
+                ])));
+            }
+            Instruction::Move(x) => {
+                let value = self.value(index, &x.src)?;
+                self.set(&x.dst, value)?;
+            }
+            Instruction::Swap(x) => {
+                let (register1, register2) = (register(&x.arg1)?, register(&x.arg2)?);
+                let a = self.get(&register1)?;
+                let b = self.get(&register2)?;
+                self.set(&register1, b)?;
+                self.set(&register2, a)?;
+            }
+            Instruction::Init(x) => self.set(&register(&x.arg1)?, Value::Nil)?,
+            Instruction::InitYregs(x) => {
+                for y in &x.registers {
+                    self.set(&Register::Y(*y), Value::Nil)?;
+                }
+            }
+            Instruction::Call(x) => {
+                self.continuations.push(self.ip);
+                self.jump(x.label)?;
+            }
+            Instruction::CallLast(x) => {
+                self.deallocate(index, instruction, number(&x.arg3)?)?;
+                self.jump(label(&x.arg2)?)?;
+            }
+            Instruction::CallOnly(x) => self.jump(x.label)?,
+            Instruction::CallExt(x) => {
+                let value = self.call_ext(x.destination, x.arity)?;
+                self.clear_x();
+                self.x[0] = Some(value);
+            }
+            Instruction::CallExtLast(x) => {
+                self.deallocate(index, instruction, x.deallocate)?;
+                let value = self.call_ext(x.destination, x.arity)?;
+                self.clear_x();
+                self.x[0] = Some(value);
+                return self.r#return();
+            }
+            Instruction::CallExtOnly(x) => {
+                let value = self.call_ext(x.destination, x.arity)?;
+                self.clear_x();
+                self.x[0] = Some(value);
+                return self.r#return();
+            }
+            Instruction::Return(_) => return self.r#return(),
+            Instruction::Allocate(x) => self.allocate(x.stack_need.words(), false),
+            Instruction::AllocateHeap(x) => self.allocate(x.stack_need.words(), false),
+            Instruction::AllocateZero(x) => {
+                let stack_need = x.arg1.to_allocation().ok_or_else(malformed)?;
+                self.allocate(stack_need.words(), true)
+            }
+            Instruction::AllocateHeapZero(x) => self.allocate(x.stack_need.words(), true),
+            Instruction::Deallocate(x) => self.deallocate(index, instruction, x.n)?,
+            Instruction::Trim(x) => {
+                let n = number(&x.arg1)?;
+                let frame = self
+                    .y
+                    .last_mut()
+                    .filter(|frame| n <= frame.len())
+                    .ok_or_else(malformed)?;
+                frame.drain(..n);
+            }
+            Instruction::Jump(x) => self.jump(x.label)?,
+            Instruction::IsLt(x) => {
+                let ok = self.compare(index, &x.arg2, &x.arg3)?.is_lt();
+                self.test(ok, label(&x.arg1)?)?;
+            }
+            Instruction::IsGe(x) => {
+                let ok = self.compare(index, &x.arg2, &x.arg3)?.is_ge();
+                self.test(ok, label(&x.arg1)?)?;
+            }
+            Instruction::IsEq(x) => {
+                let ok = self.compare(index, &x.arg2, &x.arg3)?.is_eq();
+                self.test(ok, label(&x.arg1)?)?;
+            }
+            Instruction::IsNe(x) => {
+                let ok = self.compare(index, &x.arg2, &x.arg3)?.is_ne();
+                self.test(ok, label(&x.arg1)?)?;
+            }
+            Instruction::IsEqExact(x) => {
+                let ok = self.value(index, &x.arg1)? == self.value(index, &x.arg2)?;
+                self.test(ok, x.label)?;
+            }
+            Instruction::IsNeExact(x) => {
+                let ok = self.value(index, &x.arg1)? != self.value(index, &x.arg2)?;
+                self.test(ok, x.label)?;
+            }
+            Instruction::IsInteger(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Integer(_)))?
+            }
+            Instruction::IsFloat(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Float(_)))?
+            }
+            Instruction::IsNumber(x) => {
+                self.type_test(index, &x.arg1, x.label, Value::is_number)?
+            }
+            Instruction::IsAtom(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Atom(_)))?
+            }
+            Instruction::IsBoolean(x) => {
+                self.type_test(index, &x.arg2, label(&x.arg1)?, Value::is_boolean)?
+            }
+            Instruction::IsNil(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Nil))?
+            }
+            Instruction::IsList(x) => self.type_test(index, &x.arg1, x.label, |x| {
+                matches!(x, Value::Nil | Value::List(..))
+            })?,
+            Instruction::IsNonemptyList(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::List(..)))?
+            }
+            Instruction::IsTuple(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Tuple(_)))?
+            }
+            Instruction::IsBinary(x) => {
+                self.type_test(index, &x.arg1, x.label, |x| matches!(x, Value::Binary(_)))?
+            }
+            Instruction::IsBitstr(x) => self.type_test(index, &x.arg2, label(&x.arg1)?, |x| {
+                matches!(x, Value::Binary(_) | Value::BitString { .. })
+            })?,
+            Instruction::IsMap(x) => self.type_test(index, &x.arg2, label(&x.arg1)?, |x| {
+                matches!(x, Value::Map(_))
+            })?,
+            Instruction::IsFunction(x) => self.type_test(index, &x.arg2, label(&x.arg1)?, |x| {
+                matches!(x, Value::Export { .. })
+            })?,
+            Instruction::TestArity(x) => self.type_test(
+                index,
+                &x.arg1,
+                x.label,
+                |t| matches!(t, Value::Tuple(t) if t.len() == x.arity),
+            )?,
+            Instruction::IsTaggedTuple(x) => {
+                let atom = self.atom(x.atom);
+                let value = self.get(&x.register)?;
+                let ok = matches!(&value, Value::Tuple(t) if t.len() == x.arity && t.first() == Some(&atom));
+                self.test(ok, x.label)?;
+            }
+            Instruction::SelectVal(x) => {
+                let value = self.value(index, &x.arg)?;
+                let mut target = x.fail_label;
+                for pair in x.destinations.items.chunks(2) {
+                    let [candidate, Term::Label(label)] = pair else {
+                        return Err(unsupported(index, instruction));
+                    };
+                    if self.value(index, candidate)? == value {
+                        target = *label;
+                        break;
+                    }
+                }
+                self.jump(target)?;
+            }
+            Instruction::SelectTupleArity(x) => {
+                let Value::Tuple(tuple) = self.value(index, &x.arg1)? else {
+                    return Err(unsupported(index, instruction));
+                };
+                let mut target = label(&x.arg2)?;
+                let Term::List(destinations) = &x.arg3 else {
+                    return Err(unsupported(index, instruction));
+                };
+                for pair in destinations.items.chunks(2) {
+                    let [Term::Usize(arity), Term::Label(label)] = pair else {
+                        return Err(unsupported(index, instruction));
+                    };
+                    if *arity == tuple.len() {
+                        target = *label;
+                        break;
+                    }
+                }
+                self.jump(target)?;
+            }
+            Instruction::GetList(x) => {
+                let (head, tail) = self.cons(index, &x.source)?;
+                self.set(&x.head, head)?;
+                self.set(&x.tail, tail)?;
+            }
+            Instruction::GetHd(x) => {
+                let (head, _) = self.cons(index, &x.arg1)?;
+                self.set(&register(&x.arg2)?, head)?;
+            }
+            Instruction::GetTl(x) => {
+                let (_, tail) = self.cons(index, &x.arg1)?;
+                self.set(&register(&x.arg2)?, tail)?;
+            }
+            Instruction::PutList(x) => {
+                let head = self.value(index, &x.head)?;
+                let tail = self.value(index, &x.tail)?;
+                self.set(&x.destination, Value::cons(head, tail))?;
+            }
+            Instruction::PutTuple(x) => {
+                let (arity, destination) = (number(&x.arg1)?, register(&x.arg2)?);
+                if arity == 0 {
+                    self.set(&destination, Value::Tuple(Vec::new()))?;
+                } else {
+                    self.pending_tuple = Some((destination, arity, Vec::new()));
+                }
+            }
+            Instruction::PutTuple2(x) => {
+                let elements = x
+                    .elements
+                    .items
+                    .iter()
+                    .map(|x| self.value(index, x))
+                    .collect::<Result<_, _>>()?;
+                self.set(&x.destination, Value::Tuple(elements))?;
+            }
+            Instruction::GetTupleElement(x) => {
+                let Value::Tuple(tuple) = self.get(&x.source)? else {
+                    return Err(unsupported(index, instruction));
+                };
+                let element = tuple
+                    .get(x.element)
+                    .cloned()
+                    .ok_or_else(|| unsupported(index, instruction))?;
+                self.set(&x.destination, element)?;
+            }
+            Instruction::Bif0(x) => {
+                let (bif, destination) = (number(&x.arg1)?, register(&x.arg2)?);
+                if let Some(value) = self.bif(index, instruction, bif, &[], Label { value: 0 })? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::Bif1(x) => {
+                let (fail, bif, destination) =
+                    (label(&x.arg1)?, number(&x.arg2)?, register(&x.arg4)?);
+                let args = [x.arg3.clone()];
+                if let Some(value) = self.bif(index, instruction, bif, &args, fail)? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::Bif2(x) => {
+                let (fail, bif, destination) =
+                    (label(&x.arg1)?, number(&x.arg2)?, register(&x.arg5)?);
+                let args = [x.arg3.clone(), x.arg4.clone()];
+                if let Some(value) = self.bif(index, instruction, bif, &args, fail)? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::GcBif1(x) => {
+                let (fail, bif, destination) =
+                    (label(&x.arg1)?, number(&x.arg3)?, register(&x.arg5)?);
+                let args = [x.arg4.clone()];
+                if let Some(value) = self.bif(index, instruction, bif, &args, fail)? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::GcBif2(x) => {
+                let (fail, bif, destination) =
+                    (label(&x.arg1)?, number(&x.arg3)?, register(&x.arg6)?);
+                let args = [x.arg4.clone(), x.arg5.clone()];
+                if let Some(value) = self.bif(index, instruction, bif, &args, fail)? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::GcBif3(x) => {
+                let (fail, bif, destination) =
+                    (label(&x.arg1)?, number(&x.arg3)?, register(&x.arg7)?);
+                let args = [x.arg4.clone(), x.arg5.clone(), x.arg6.clone()];
+                if let Some(value) = self.bif(index, instruction, bif, &args, fail)? {
+                    self.set(&destination, value)?;
+                }
+            }
+            Instruction::Fmove(x) => match (&x.arg1, &x.arg2) {
+                (source, Term::FloatingPointRegister(destination)) => {
+                    let Value::Float(value) = self.value(index, source)? else {
+                        return Err(unsupported(index, instruction));
+                    };
+                    self.set_float(*destination, value);
+                }
+                (Term::FloatingPointRegister(source), destination) => {
+                    let value = Value::Float(self.float(*source));
+                    self.set(&register(destination)?, value)?;
+                }
+                _ => return Err(unsupported(index, instruction)),
+            },
+            Instruction::Fconv(x) => {
+                let destination = float_register(&x.arg2)?;
+                let value = to_f64(&self.value(index, &x.arg1)?)
+                    .ok_or_else(|| exception(Value::atom("badarith")))?;
+                self.set_float(destination, value);
+            }
+            Instruction::Fadd(x) => {
+                let [a, b, destination] = [&x.arg2, &x.arg3, &x.arg4].map(float_register);
+                self.float_op(a?, b?, destination?, label(&x.arg1)?, |a, b| a + b)?
+            }
+            Instruction::Fsub(x) => {
+                let [a, b, destination] = [&x.arg2, &x.arg3, &x.arg4].map(float_register);
+                self.float_op(a?, b?, destination?, label(&x.arg1)?, |a, b| a - b)?
+            }
+            Instruction::Fmul(x) => {
+                let [a, b, destination] = [&x.arg2, &x.arg3, &x.arg4].map(float_register);
+                self.float_op(a?, b?, destination?, label(&x.arg1)?, |a, b| a * b)?
+            }
+            Instruction::Fdiv(x) => {
+                let [a, b, destination] = [&x.arg2, &x.arg3, &x.arg4].map(float_register);
+                self.float_op(a?, b?, destination?, label(&x.arg1)?, |a, b| a / b)?
+            }
+            Instruction::Fnegate(x) => {
+                let [a, destination] = [&x.arg2, &x.arg3].map(float_register);
+                let a = a?;
+                self.float_op(a, a, destination?, label(&x.arg1)?, |a, _| -a)?
+            }
+            Instruction::Badmatch(x) => {
+                let value = self.value(index, &x.arg1)?;
+                return Err(exception(Value::Tuple(vec![
+                    Value::atom("badmatch"),
+                    value,
+                ])));
+            }
+            Instruction::CaseEnd(x) => {
+                let value = self.value(index, &x.arg1)?;
+                return Err(exception(Value::Tuple(vec![
+                    Value::atom("case_clause"),
+                    value,
+                ])));
+            }
+            Instruction::TryCaseEnd(x) => {
+                let value = self.value(index, &x.arg1)?;
+                return Err(exception(Value::Tuple(vec![
+                    Value::atom("try_clause"),
+                    value,
+                ])));
+            }
+            Instruction::Badrecord(x) => {
+                let value = self.value(index, &x.arg1)?;
+                return Err(exception(Value::Tuple(vec![
+                    Value::atom("badrecord"),
+                    value,
+                ])));
+            }
+            Instruction::IfEnd(_) => return Err(exception(Value::atom("if_clause"))),
+            _ => return Err(unsupported(index, instruction)),
+        }
+        Ok(None)
+    }
+
+    fn jump(&mut self, label: Label) -> Result<(), InterpretError> {
+        self.ip = self.interpreter.label_index(label)?;
+        Ok(())
+    }
+
+    // Jumps to `label` unless `ok`.
+    fn test(&mut self, ok: bool, label: Label) -> Result<(), InterpretError> {
+        if !ok {
+            self.jump(label)?;
+        }
+        Ok(())
+    }
+
+    fn type_test<F>(
+        &mut self,
+        index: usize,
+        term: &Term,
+        label: Label,
+        f: F,
+    ) -> Result<(), InterpretError>
+    where
+        F: FnOnce(&Value) -> bool,
+    {
+        let ok = f(&self.value(index, term)?);
+        self.test(ok, label)
+    }
+
+    fn r#return(&mut self) -> Result<Option<Value>, InterpretError> {
+        match self.continuations.pop() {
+            Some(ip) => {
+                self.ip = ip;
+                Ok(None)
+            }
+            None => self.get(&Register::x(0)).map(Some),
+        }
+    }
+
+    fn allocate(&mut self, n: usize, zero: bool) {
+        let init = if zero { Some(Value::Nil) } else { None };
+        self.y.push(vec![init; n]);
+    }
+
+    fn deallocate(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        n: usize,
+    ) -> Result<(), InterpretError> {
+        match self.y.pop() {
+            Some(frame) if frame.len() == n => Ok(()),
+            _ => Err(unsupported(index, instruction)),
+        }
+    }
+
+    fn clear_x(&mut self) {
+        self.x.clear();
+        self.x.push(None);
+    }
+
+    fn call_ext(&mut self, import: usize, arity: usize) -> Result<Value, InterpretError> {
+        let module = self.interpreter.module;
+        let args = (0..arity)
+            .map(|i| self.get(&Register::x(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let import = module.imports.get(import).copied();
+        let name = |atom| module.atom_name(atom).unwrap_or("[]").to_owned();
+        let (m, f) = import
+            .map(|x| (name(x.module), name(x.function)))
+            .unwrap_or_default();
+        if import.is_some_and(|x| x.arity == arity) {
+            if let Some(value) = self.interpreter.call_bif(&m, &f, &args)? {
+                return Ok(value);
+            }
+        }
+        Err(InterpretError::UndefinedFunction {
+            module: m,
+            function: f,
+            arity,
+        })
+    }
+
+    fn bif(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        bif: usize,
+        args: &[Term],
+        label: Label,
+    ) -> Result<Option<Value>, InterpretError> {
+        let module = self.interpreter.module;
+        let import = module
+            .imports
+            .get(bif)
+            .ok_or_else(|| unsupported(index, instruction))?;
+        let name = |atom| module.atom_name(atom).unwrap_or("[]");
+        let args = args
+            .iter()
+            .map(|x| self.value(index, x))
+            .collect::<Result<Vec<_>, _>>()?;
+        match self
+            .interpreter
+            .call_bif(name(import.module), name(import.function), &args)
+        {
+            Ok(Some(value)) => Ok(Some(value)),
+            Ok(None) => Err(InterpretError::UndefinedFunction {
+                module: name(import.module).to_owned(),
+                function: name(import.function).to_owned(),
+                arity: import.arity,
+            }),
+            Err(InterpretError::Exception { .. }) if label.value != 0 => {
+                self.jump(label)?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn float_op<F>(
+        &mut self,
+        arg1: FloatingPointRegister,
+        arg2: FloatingPointRegister,
+        destination: FloatingPointRegister,
+        label: Label,
+        f: F,
+    ) -> Result<(), InterpretError>
+    where
+        F: FnOnce(f64, f64) -> f64,
+    {
+        let value = f(self.float(arg1), self.float(arg2));
+        if value.is_finite() {
+            self.set_float(destination, value);
+        } else if label.value != 0 {
+            self.jump(label)?;
+        } else {
+            return Err(exception(Value::atom("badarith")));
+        }
+        Ok(())
+    }
+
+    fn compare(
+        &self,
+        index: usize,
+        a: &Term,
+        b: &Term,
+    ) -> Result<std::cmp::Ordering, InterpretError> {
+        Ok(self.value(index, a)?.compare(&self.value(index, b)?))
+    }
+
+    fn cons(&self, index: usize, term: &Term) -> Result<(Value, Value), InterpretError> {
+        match self.value(index, term)? {
+            Value::List(mut items, tail) => {
+                let head = items.remove(0);
+                Ok((head, Value::improper_list(items, *tail)))
+            }
+            _ => Err(InterpretError::InvalidOperand {
+                index,
+                operand: term.clone(),
+            }),
+        }
+    }
+
+    fn atom(&self, atom: Atom) -> Value {
+        match self.interpreter.module.atom_name(atom) {
+            None => Value::Nil,
+            Some(name) => Value::atom(name),
+        }
+    }
+
+    fn value(&self, index: usize, term: &Term) -> Result<Value, InterpretError> {
+        match term {
+            Term::Usize(x) => Ok(Value::integer(*x)),
+            Term::Integer(x) => Ok(Value::Integer(x.clone())),
+            Term::Atom(x) => Ok(self.atom(*x)),
+            Term::Character(x) => Ok(Value::integer(u32::from(*x))),
+            Term::Float(x) => Ok(Value::Float(x.value)),
+            Term::Literal(x) => self
+                .interpreter
+                .literals
+                .get(x.value)
+                .cloned()
+                .ok_or_else(|| InterpretError::InvalidOperand {
+                    index,
+                    operand: term.clone(),
+                }),
+            Term::FloatingPointRegister(x) => Ok(Value::Float(self.float(*x))),
+            _ => match term.to_register() {
+                Some(register) => self.get(&register),
+                None => Err(InterpretError::InvalidOperand {
+                    index,
+                    operand: term.clone(),
+                }),
+            },
+        }
+    }
+
+    fn get(&self, register: &Register) -> Result<Value, InterpretError> {
+        let value = match register.untyped() {
+            Register::X(x) => self.x.get(x.value).cloned().flatten(),
+            Register::Y(y) => self
+                .y
+                .last()
+                .and_then(|frame| frame.get(y.value))
+                .cloned()
+                .flatten(),
+            Register::Typed(_) => unreachable!(),
+        };
+        value.ok_or_else(|| InterpretError::UninitializedRegister {
+            register: register.untyped(),
+        })
+    }
+
+    fn set(&mut self, register: &Register, value: Value) -> Result<(), InterpretError> {
+        match register.untyped() {
+            Register::X(x) => {
+                if self.x.len() <= x.value {
+                    self.x.resize(x.value + 1, None);
+                }
+                self.x[x.value] = Some(value);
+            }
+            Register::Y(y) => {
+                let slot = self
+                    .y
+                    .last_mut()
+                    .and_then(|frame| frame.get_mut(y.value))
+                    .ok_or_else(|| InterpretError::UninitializedRegister {
+                        register: register.untyped(),
+                    })?;
+                *slot = Some(value);
+            }
+            Register::Typed(_) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn float(&self, register: FloatingPointRegister) -> f64 {
+        self.f.get(register.value).copied().unwrap_or(0.0)
+    }
+
+    fn set_float(&mut self, register: FloatingPointRegister, value: f64) {
+        if self.f.len() <= register.value {
+            self.f.resize(register.value + 1, 0.0);
+        }
+        self.f[register.value] = value;
+    }
+}
+
+fn unsupported(index: usize, instruction: &Instruction) -> InterpretError {
+    InterpretError::UnsupportedInstruction {
+        index,
+        instruction: Box::new(instruction.clone()),
+    }
+}
+
+fn exception(reason: Value) -> InterpretError {
+    InterpretError::Exception { reason }
+}
+
+fn boolean(b: bool) -> Value {
+    Value::atom(if b { "true" } else { "false" })
+}
+
+fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(x) => x.to_f64(),
+        Value::Float(x) => Some(*x),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{
+        Allocate, Call, Deallocate, GcBif2, GetList, IsEqExact, IsNil, Move, PutTuple2, Return,
+        TestHeap,
+    };
+    use crate::term::{Allocation, List};
+
+    #[test]
+    fn interpret_works() {
+        let mut builder = ModuleBuilder::new("test");
+
+        // fact(0) -> 1; fact(N) -> N * fact(N - 1).
+        let mut function = builder.function("fact", 1);
+        let fact = function.entry_label();
+        let times = function.import("erlang", "*", 2);
+        let minus = function.import("erlang", "-", 2);
+        let recurse = function.new_label();
+        function
+            .push(IsEqExact {
+                label: recurse,
+                arg1: Term::x(0),
+                arg2: Term::Integer(0.into()),
+            })
+            .push(Move {
+                src: Term::Integer(1.into()),
+                dst: Register::x(0),
+            })
+            .push(Return {})
+            .label(recurse)
+            .push(Allocate {
+                stack_need: Allocation::Words(1),
+                live: 1,
+            })
+            .push(Move {
+                src: Term::x(0),
+                dst: Register::y(0),
+            })
+            .push(GcBif2 {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(1),
+                arg3: Term::Usize(minus),
+                arg4: Term::x(0),
+                arg5: Term::Integer(1.into()),
+                arg6: Term::x(0),
+            })
+            .push(Call {
+                arity: 1,
+                label: fact,
+            })
+            .push(GcBif2 {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(1),
+                arg3: Term::Usize(times),
+                arg4: Term::y(0),
+                arg5: Term::x(0),
+                arg6: Term::x(0),
+            })
+            .push(Deallocate { n: 1 })
+            .push(Return {});
+
+        // head_pair([H | _]) -> {H, Literal}.
+        let literal = Value::Binary(b"abc".to_vec()).to_bytes().unwrap();
+        let mut function = builder.function("head_pair", 1);
+        let literal = function.literal(&literal);
+        let fail = function.func_info_label();
+        function
+            .push(IsNil {
+                label: fail,
+                arg1: Term::x(0),
+            })
+            .push(Move {
+                src: Term::Atom(Atom { value: 0 }),
+                dst: Register::x(0),
+            })
+            .push(Return {});
+        let mut function = builder.function("head_pair", 2);
+        function
+            .push(GetList {
+                source: Term::x(0),
+                head: Register::x(0),
+                tail: Register::x(1),
+            })
+            .push(TestHeap {
+                heap_need: Allocation::Words(3),
+                live: 1,
+            })
+            .push(PutTuple2 {
+                destination: Register::x(0),
+                elements: List {
+                    items: vec![Term::x(0), Term::Literal(literal)],
+                },
+            })
+            .push(Return {});
+        let module = builder.build();
+        assert_eq!(crate::validate::validate(&module), Ok(()));
+
+        let interpreter = Interpreter::new(&module).unwrap();
+        let result = interpreter.call("fact", vec![Value::integer(20)]).unwrap();
+        assert_eq!(result, Value::integer(2432902008176640000u64));
+
+        let result = interpreter.call("head_pair", vec![Value::Nil]).unwrap();
+        assert_eq!(result, Value::Nil);
+        let err = interpreter
+            .call("head_pair", vec![Value::integer(1)])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "exception error: {function_clause,test,head_pair,[1]}"
+        );
+        let result = interpreter
+            .call(
+                "head_pair",
+                vec![Value::list([Value::atom("a")]), Value::Nil],
+            )
+            .unwrap();
+        assert_eq!(result.to_string(), "{a,<<97,98,99>>}");
+
+        let err = interpreter
+            .call("fact", vec![Value::atom("a")])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "exception error: badarith");
+    }
+
+    #[test]
+    fn call_bif_works() {
+        let module = ModuleBuilder::new("test").build();
+        let interpreter = Interpreter::new(&module).unwrap();
+        let call = |function, args: &[Value]| interpreter.call_bif("erlang", function, args);
+        let error = |function, args: &[Value]| call(function, args).unwrap_err().to_string();
+
+        let result = call("bsl", &[Value::integer(3), Value::integer(2)]).unwrap();
+        assert_eq!(result, Some(Value::integer(12)));
+        let result = call("bsr", &[Value::integer(-5), Value::integer(1)]).unwrap();
+        assert_eq!(result, Some(Value::integer(-3)));
+        let result = call("bsr", &[Value::integer(-5), Value::integer(i64::MAX)]).unwrap();
+        assert_eq!(result, Some(Value::integer(-1)));
+        let result = call("bsl", &[Value::integer(5), Value::integer(i64::MIN)]).unwrap();
+        assert_eq!(result, Some(Value::integer(0)));
+        assert_eq!(
+            error("bsr", &[Value::integer(1), Value::integer(i64::MIN)]),
+            "exception error: badarith"
+        );
+        assert_eq!(
+            error("bsl", &[Value::integer(1), Value::integer(MAX_SHIFT + 1)]),
+            "exception error: system_limit"
+        );
+        assert_eq!(
+            error("bsr", &[Value::integer(1), Value::integer(-MAX_SHIFT - 1)]),
+            "exception error: system_limit"
+        );
+
+        // Floats beyond the range of `i128`.
+        let result = call("trunc", &[Value::Float(1.0e40)]).unwrap();
+        assert_eq!(
+            result,
+            Some(Value::Integer(BigInt::from_f64(1.0e40).unwrap()))
+        );
+        let result = call("trunc", &[Value::Float(-1.5e300)]).unwrap();
+        assert_eq!(
+            result,
+            Some(Value::Integer(BigInt::from_f64(-1.5e300).unwrap()))
+        );
+        let result = call("trunc", &[Value::Float(-2.5)]).unwrap();
+        assert_eq!(result, Some(Value::integer(-2)));
+    }
+}
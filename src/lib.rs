@@ -4,23 +4,148 @@
 //!
 //! - [The BEAM Book - Generic BEAM Instructions](https://blog.stenmans.org/theBeamBook/#CH-Instructions)
 use crate::instruction::Instruction;
-use crate::term::TermKind;
-use beamcode_derive::{Decode, Encode};
+use crate::term::{Atom, FloatingPointRegister, Label, Literal, Register, Term, TermKind};
+use beamcode_derive::{Decode, Encode, Visit};
 use byteorder::ReadBytesExt as _;
 use num::BigInt;
 use std::io::{Read, Write};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+pub mod asm;
+pub mod builder;
+pub mod call_graph;
+pub mod canonical;
+pub mod cfg;
+pub mod dead_code;
+pub mod def_use;
+pub mod diff;
+pub mod downgrade;
+pub mod etf;
+pub mod function;
+pub mod hash;
 pub mod instruction;
+pub mod interpret;
+mod json;
+pub mod liveness;
+pub mod module;
+pub mod normalize;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod operand_generators;
+pub mod optimize;
+pub mod preserve;
+#[cfg(feature = "proptest")]
+mod proptest_impls;
+pub mod relabel;
+pub mod roundtrip;
+pub mod stats;
 pub mod term;
+pub mod upgrade;
+pub mod validate;
+pub mod xref;
 
 /// This trait allows decoding an object from a byte sequence.
+///
+/// The `*_limited` methods enforce [`DecodeLimits`] and should be used for untrusted input.
+/// The other methods use [`DecodeLimits::default()`] (i.e., no limits).
 pub trait Decode: Sized {
     fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        Self::decode_limited(reader, &DecodeLimits::default())
+    }
+
+    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+        Self::decode_with_tag_limited(reader, tag, &DecodeLimits::default())
+    }
+
+    fn decode_limited<R: Read>(reader: &mut R, limits: &DecodeLimits) -> Result<Self, DecodeError> {
         let tag = reader.read_u8()?;
-        Self::decode_with_tag(reader, tag)
+        Self::decode_with_tag_limited(reader, tag, limits)
+    }
+
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError>;
+}
+
+/// Limits on the resources consumed by decoding.
+///
+/// The default value imposes no limits.
+///
+/// # Examples
+///
+/// ```
+/// use beamcode::{decode_instructions_limited, DecodeError, DecodeLimits};
+///
+/// let limits = DecodeLimits {
+///     max_instructions: 1,
+///     ..Default::default()
+/// };
+/// // return, return
+/// assert!(matches!(
+///     decode_instructions_limited(&[19, 19], &limits),
+///     Err(DecodeError::TooManyInstructions { max: 1 })
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// The maximum number of bytes of an integer (the 4-bit and 11-bit forms are always allowed).
+    pub max_integer_bytes: usize,
+
+    /// The maximum number of items of a list (or an allocation list).
+    pub max_list_length: usize,
+
+    /// The maximum number of instructions.
+    pub max_instructions: usize,
+
+    /// The maximum depth of nested lists.
+    pub max_nesting: usize,
+
+    /// The maximum number of bytes of the decompressed literal table (`LitT` chunk).
+    pub max_literal_table_bytes: usize,
+}
+
+impl DecodeLimits {
+    fn check_integer_size(&self, size: usize) -> Result<(), DecodeError> {
+        if size > self.max_integer_bytes {
+            return Err(DecodeError::TooLargeInteger { size });
+        }
+        Ok(())
+    }
+
+    fn check_list_length(&self, length: usize) -> Result<(), DecodeError> {
+        if length > self.max_list_length {
+            return Err(DecodeError::TooLongList { length });
+        }
+        Ok(())
+    }
+
+    // Returns the limits for the items of a list.
+    fn nested(&self) -> Result<Self, DecodeError> {
+        let max_nesting = self
+            .max_nesting
+            .checked_sub(1)
+            .ok_or(DecodeError::TooDeepNesting {
+                max: self.max_nesting,
+            })?;
+        Ok(Self {
+            max_nesting,
+            ..*self
+        })
     }
+}
 
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError>;
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_integer_bytes: usize::MAX,
+            max_list_length: usize::MAX,
+            max_instructions: usize::MAX,
+            max_nesting: usize::MAX,
+            max_literal_table_bytes: usize::MAX,
+        }
+    }
 }
 
 /// This trait allows encoding an object into a byte sequence.
@@ -28,6 +153,47 @@ pub trait Encode {
     fn encode<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError>;
 }
 
+/// This trait allows traversing the operands contained in an object.
+pub trait Visit {
+    fn visit<V: Visitor>(&self, visitor: &mut V);
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V);
+}
+
+/// Visitor for [`Visit::visit()`].
+pub trait Visitor {
+    fn visit_label(&mut self, _label: &Label) {}
+
+    fn visit_float_register(&mut self, _register: &FloatingPointRegister) {}
+
+    /// Called for every [`Term`] operand before visiting its contents.
+    fn visit_term(&mut self, _term: &Term) {}
+
+    /// Called for every [`Register`] operand before visiting its contents.
+    fn visit_register(&mut self, _register: &Register) {}
+
+    fn visit_atom(&mut self, _atom: &Atom) {}
+
+    fn visit_literal(&mut self, _literal: &Literal) {}
+}
+
+/// Visitor for [`Visit::visit_mut()`].
+pub trait VisitorMut {
+    fn visit_label_mut(&mut self, _label: &mut Label) {}
+
+    fn visit_float_register_mut(&mut self, _register: &mut FloatingPointRegister) {}
+
+    /// Called for every [`Term`] operand before visiting its contents.
+    fn visit_term_mut(&mut self, _term: &mut Term) {}
+
+    /// Called for every [`Register`] operand before visiting its contents.
+    fn visit_register_mut(&mut self, _register: &mut Register) {}
+
+    fn visit_atom_mut(&mut self, _atom: &mut Atom) {}
+
+    fn visit_literal_mut(&mut self, _literal: &mut Literal) {}
+}
+
 /// Decoding errors.
 #[derive(Debug, thiserror::Error)]
 pub enum DecodeError {
@@ -49,9 +215,51 @@ pub enum DecodeError {
     #[error("invalid Unicode codepoint: {value}")]
     InvalidUnicodeCodepoint { value: u32 },
 
+    #[error("too large integer: {size} bytes")]
+    TooLargeInteger { size: usize },
+
+    #[error("too long list: {length} items")]
+    TooLongList { length: usize },
+
+    #[error("length prefix of a large integer in the long form")]
+    NestedIntegerLength,
+
+    #[error("too many instructions (max: {max})")]
+    TooManyInstructions { max: usize },
+
+    #[error("too deeply nested terms (max: {max})")]
+    TooDeepNesting { max: usize },
+
+    #[error("negative index: kind={kind:?}, value={value}")]
+    NegativeIndex { kind: TermKind, value: BigInt },
+
+    #[error("too large index: kind={kind:?}, value={value}")]
+    IndexTooLarge { kind: TermKind, value: BigInt },
+
+    #[error("too large literal table (max: {max} bytes)")]
+    TooLargeLiteralTable { max: usize },
+
+    #[error("malformed .beam file: {reason}")]
+    MalformedBeamFile { reason: String },
+
+    #[error("missing mandatory chunk: {id:?}")]
+    MissingChunk { id: String },
+
+    #[error("unknown external term format version: {version}")]
+    UnknownEtfVersion { version: u8 },
+
+    #[error("unknown (or unsupported) external term format tag: {tag}")]
+    UnknownEtfTag { tag: u8 },
+
+    #[error(transparent)]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+
     #[error(transparent)]
     NumError(#[from] num::bigint::TryFromBigIntError<BigInt>),
 
+    #[error(transparent)]
+    BeamFileError(#[from] beam_file::Error),
+
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
@@ -59,16 +267,38 @@ pub enum DecodeError {
 /// Encoding errors.
 #[derive(Debug, thiserror::Error)]
 pub enum EncodeError {
+    #[error("too long atom: {name:?}")]
+    TooLongAtom { name: String },
+
+    #[error("integer too short for the multi-byte encoding: {size} bytes")]
+    TooShortIntegerBytes { size: usize },
+
+    #[error(transparent)]
+    BeamFileError(#[from] beam_file::Error),
+
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
 
 /// Decodes BEAM instructions.
 pub fn decode_instructions(bytecode: &[u8]) -> Result<Vec<Instruction>, DecodeError> {
+    decode_instructions_limited(bytecode, &DecodeLimits::default())
+}
+
+/// Decodes BEAM instructions within `limits`.
+pub fn decode_instructions_limited(
+    bytecode: &[u8],
+    limits: &DecodeLimits,
+) -> Result<Vec<Instruction>, DecodeError> {
     let mut reader = bytecode;
     let mut instructions = Vec::new();
     while !reader.is_empty() {
-        let instruction = Instruction::decode(&mut reader)?;
+        if instructions.len() == limits.max_instructions {
+            return Err(DecodeError::TooManyInstructions {
+                max: limits.max_instructions,
+            });
+        }
+        let instruction = Instruction::decode_limited(&mut reader, limits)?;
         instructions.push(instruction);
     }
     Ok(instructions)
@@ -0,0 +1,233 @@
+//! Register liveness analysis.
+//!
+//! This module provides a backward liveness analysis of x and y registers
+//! over the [control flow graph](crate::cfg::Cfg) of a function.
+//!
+//! The `live` operands of instructions are regarded as neither uses nor definitions during the analysis
+//! (see [`Instruction::explicit_uses()`] and [`Instruction::explicit_defs()`]).
+//! Therefore, the result can be used to check whether the encoded `live` operands are correct
+//! (see [`Liveness::check_live_operands()`]).
+use crate::cfg::Cfg;
+use crate::instruction::Instruction;
+use crate::term::{Register, XRegister, YRegister};
+use std::collections::BTreeSet;
+
+/// A set of live registers.
+pub type LiveRegisters = BTreeSet<Register>;
+
+/// Live-in and live-out registers of a basic block or an instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveSets {
+    pub live_in: LiveRegisters,
+    pub live_out: LiveRegisters,
+}
+
+/// The result of a liveness analysis.
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    pub cfg: Cfg,
+
+    /// Live sets of each basic block (indexed in the same way as [`Cfg::blocks`]).
+    pub blocks: Vec<LiveSets>,
+
+    /// Live sets of each instruction.
+    pub instructions: Vec<LiveSets>,
+}
+
+impl Liveness {
+    /// Analyzes the liveness of registers in `instructions` (typically a function).
+    pub fn analyze(instructions: &[Instruction]) -> Self {
+        let cfg = Cfg::new(instructions);
+        let mut blocks = vec![LiveSets::default(); cfg.blocks.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, block) in cfg.blocks.iter().enumerate().rev() {
+                let mut live_out = LiveRegisters::new();
+                for &j in &block.successors {
+                    live_out.extend(blocks[j].live_in.iter().cloned());
+                }
+                let mut live = live_out.clone();
+                for instruction in instructions[block.range.clone()].iter().rev() {
+                    live = transfer(instruction, &live);
+                }
+                if blocks[i].live_in != live || blocks[i].live_out != live_out {
+                    blocks[i] = LiveSets {
+                        live_in: live,
+                        live_out,
+                    };
+                    changed = true;
+                }
+            }
+        }
+
+        let mut sets = vec![LiveSets::default(); instructions.len()];
+        for (block, block_sets) in cfg.blocks.iter().zip(blocks.iter()) {
+            let mut live = block_sets.live_out.clone();
+            for i in block.range.clone().rev() {
+                let live_in = transfer(&instructions[i], &live);
+                sets[i] = LiveSets {
+                    live_in: live_in.clone(),
+                    live_out: live,
+                };
+                live = live_in;
+            }
+        }
+        Self {
+            cfg,
+            blocks,
+            instructions: sets,
+        }
+    }
+
+    /// Checks whether the `live` operands of `instructions` (the same ones given to
+    /// [`Liveness::analyze()`]) are consistent with the result of this analysis.
+    ///
+    /// An instruction having a `live` operand `N` must preserve every x register which is live
+    /// after the instruction and is not written by the instruction itself.
+    /// That is, `N` is expected to be the number of such registers (`x0..x{N-1}`).
+    pub fn check_live_operands(&self, instructions: &[Instruction]) -> Vec<LiveOperandMismatch> {
+        let mut mismatches = Vec::new();
+        for (index, instruction) in instructions.iter().enumerate() {
+            let Some(encoded) = instruction.live() else {
+                continue;
+            };
+            let defs = instruction.explicit_defs();
+            let required = self.instructions[index]
+                .live_out
+                .iter()
+                .filter_map(|r| match r {
+                    Register::X(x) if !defs.registers.contains(r) => Some(x.value + 1),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+            if encoded != required {
+                mismatches.push(LiveOperandMismatch {
+                    index,
+                    encoded,
+                    required,
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+/// A `live` operand which does not agree with the liveness analysis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveOperandMismatch {
+    /// The index of the instruction.
+    pub index: usize,
+
+    /// The value of the `live` operand.
+    pub encoded: usize,
+
+    /// The value computed by the analysis.
+    pub required: usize,
+}
+
+impl LiveOperandMismatch {
+    /// Returns `true` if the encoded value is too small (i.e., some live registers would be lost).
+    pub fn is_unsafe(&self) -> bool {
+        self.encoded < self.required
+    }
+}
+
+fn transfer(instruction: &Instruction, live_out: &LiveRegisters) -> LiveRegisters {
+    if let Instruction::Trim(x) = instruction {
+        // `trim N _` drops `y0..y{N-1}` and renames `y{K+N}` to `yK`.
+        let n = x.arg1.to_usize().unwrap_or(0);
+        return live_out
+            .iter()
+            .map(|r| match r {
+                Register::Y(y) => Register::Y(YRegister {
+                    value: y.value + n,
+                    ty: None,
+                }),
+                r => r.clone(),
+            })
+            .collect();
+    }
+
+    let defs = instruction.explicit_defs();
+    let mut live_in = live_out
+        .iter()
+        .filter(|r| !defs.contains(r))
+        .cloned()
+        .collect::<LiveRegisters>();
+    live_in.extend(instruction.explicit_uses().registers);
+    live_in
+}
+
+/// Returns the x registers in `live` in ascending order.
+pub fn x_registers(live: &LiveRegisters) -> impl '_ + Iterator<Item = XRegister> {
+    live.iter().filter_map(|r| match r {
+        Register::X(x) => Some(*x),
+        _ => None,
+    })
+}
+
+/// Returns the y registers in `live` in ascending order.
+pub fn y_registers(live: &LiveRegisters) -> impl '_ + Iterator<Item = YRegister> {
+    live.iter().filter_map(|r| match r {
+        Register::Y(x) => Some(*x),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, GcBif2, Move, Return, TestHeap};
+    use crate::term::{Allocation, Label, Term};
+
+    fn x(value: usize) -> Register {
+        Register::X(XRegister { value, ty: None })
+    }
+
+    #[test]
+    fn liveness_works() {
+        let instructions = vec![
+            Instruction::Label(instruction::Label { literal: 1 }),
+            Instruction::TestHeap(TestHeap {
+                heap_need: Allocation::Words(2),
+                live: 1,
+            }),
+            Instruction::GcBif2(GcBif2 {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(2),
+                arg3: Term::Usize(0),
+                arg4: Term::XRegister(XRegister { value: 0, ty: None }),
+                arg5: Term::XRegister(XRegister { value: 1, ty: None }),
+                arg6: x(0).into(),
+            }),
+            Instruction::Move(Move {
+                src: Term::XRegister(XRegister { value: 1, ty: None }),
+                dst: x(0),
+            }),
+            Instruction::Return(Return {}),
+        ];
+        let liveness = Liveness::analyze(&instructions);
+        assert_eq!(liveness.blocks.len(), 1);
+        assert_eq!(
+            liveness.blocks[0].live_in,
+            [x(0), x(1)].into_iter().collect()
+        );
+        assert_eq!(
+            liveness.instructions[2].live_out,
+            [x(1)].into_iter().collect()
+        );
+
+        let mismatches = liveness.check_live_operands(&instructions);
+        assert_eq!(
+            mismatches,
+            vec![LiveOperandMismatch {
+                index: 1,
+                encoded: 1,
+                required: 2
+            }]
+        );
+        assert!(mismatches[0].is_unsafe());
+    }
+}
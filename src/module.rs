@@ -0,0 +1,567 @@
+//! BEAM modules.
+//!
+//! # References
+//!
+//! - [The BEAM Book - The BEAM File Format](https://blog.stenmans.org/theBeamBook/#CH-beam_modules)
+//! - [erlang/otp/lib/compiler/src/beam_asm.erl](https://github.com/erlang/otp/blob/master/lib/compiler/src/beam_asm.erl)
+use crate::instruction::Instruction;
+use crate::term::{Atom, Label, Literal};
+use crate::{DecodeError, DecodeLimits, EncodeError};
+use beam_file::chunk::{self, Chunk as _, StandardChunk};
+use beam_file::{parts, BeamFile, RawBeamFile, StandardBeamFile};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// A decoded BEAM module.
+///
+/// The chunks which are not interpreted by this crate are kept as-is in [`Module::other_chunks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// Atom table.
+    ///
+    /// Note that [`Atom::value`] is a one-based index of this table (`0` means `[]`).
+    pub atoms: Vec<String>,
+    pub imports: Vec<Import>,
+    pub exports: Vec<Export>,
+    pub locals: Vec<Local>,
+    pub lambdas: Vec<Lambda>,
+
+    /// Literal table (each literal is encoded in the External Term Format).
+    pub literals: Vec<Vec<u8>>,
+    pub code: Code,
+    pub other_chunks: Vec<OtherChunk>,
+}
+
+impl Module {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DecodeError> {
+        let beam = StandardBeamFile::from_file(path)?;
+        Self::from_beam_file(beam)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, DecodeError> {
+        let beam = StandardBeamFile::from_reader(reader)?;
+        Self::from_beam_file(beam)
+    }
+
+    /// Parses an untrusted `.beam` file within `limits`.
+    ///
+    /// The whole input is read first, and the sizes of the chunks and the entry counts of the
+    /// tables are checked against it before any memory is allocated for them.
+    pub fn from_reader_limited<R: Read>(
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        check_beam_file(&bytes, limits)?;
+        let beam = StandardBeamFile::from_reader(&bytes[..])?;
+        Self::from_beam_file_limited(beam, limits)
+    }
+
+    pub fn from_beam_file(beam: StandardBeamFile) -> Result<Self, DecodeError> {
+        Self::from_beam_file_limited(beam, &DecodeLimits::default())
+    }
+
+    /// Makes a module from `beam`, decoding its code within `limits`.
+    ///
+    /// Note that `beam` has been already parsed, so only the decoding of the `Code` chunk is
+    /// limited (see [`Module::from_reader_limited()`] for untrusted input).
+    pub fn from_beam_file_limited(
+        beam: StandardBeamFile,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        let mut atoms = None;
+        let mut code = None;
+        let mut module = Self {
+            atoms: Vec::new(),
+            imports: Vec::new(),
+            exports: Vec::new(),
+            locals: Vec::new(),
+            lambdas: Vec::new(),
+            literals: Vec::new(),
+            code: Code::default(),
+            other_chunks: Vec::new(),
+        };
+        for chunk in beam.chunks {
+            match chunk {
+                StandardChunk::Atom(chunk) => {
+                    atoms = Some(chunk.atoms.into_iter().map(|x| x.name).collect());
+                }
+                StandardChunk::Code(chunk) => {
+                    code = Some(Code {
+                        info_size: chunk.info_size,
+                        version: chunk.version,
+                        opcode_max: chunk.opcode_max,
+                        label_count: chunk.label_count,
+                        function_count: chunk.function_count,
+                        instructions: crate::decode_instructions_limited(&chunk.bytecode, limits)?,
+                    });
+                }
+                StandardChunk::ImpT(chunk) => {
+                    module.imports = chunk.imports.into_iter().map(Import::from).collect();
+                }
+                StandardChunk::ExpT(chunk) => {
+                    module.exports = chunk.exports.into_iter().map(Export::from).collect();
+                }
+                StandardChunk::LocT(chunk) => {
+                    module.locals = chunk.locals.into_iter().map(Local::from).collect();
+                }
+                StandardChunk::FunT(chunk) => {
+                    module.lambdas = chunk.functions.into_iter().map(Lambda::from).collect();
+                }
+                StandardChunk::LitT(chunk) => {
+                    module.literals = chunk.literals;
+                }
+                chunk => {
+                    let mut data = Vec::new();
+                    chunk.encode_data(&mut data)?;
+                    module.other_chunks.push(OtherChunk {
+                        id: *chunk.id(),
+                        data,
+                    });
+                }
+            }
+        }
+        module.atoms = atoms.ok_or_else(|| DecodeError::MissingChunk {
+            id: "AtU8".to_owned(),
+        })?;
+        module.code = code.ok_or_else(|| DecodeError::MissingChunk {
+            id: "Code".to_owned(),
+        })?;
+        Ok(module)
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), EncodeError> {
+        self.to_beam_file()?.to_file(path)?;
+        Ok(())
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), EncodeError> {
+        self.to_beam_file()?.to_writer(writer)?;
+        Ok(())
+    }
+
+    pub fn to_beam_file(&self) -> Result<RawBeamFile, EncodeError> {
+        let mut chunks = vec![
+            StandardChunk::Atom(chunk::AtomChunk {
+                is_unicode: true,
+                atoms: self
+                    .atoms
+                    .iter()
+                    .map(|name| parts::Atom { name: name.clone() })
+                    .collect(),
+            }),
+            StandardChunk::Code(chunk::CodeChunk {
+                info_size: self.code.info_size,
+                version: self.code.version,
+                opcode_max: self.code.opcode_max,
+                label_count: self.code.label_count,
+                function_count: self.code.function_count,
+                bytecode: crate::encode_instructions(&self.code.instructions)?,
+            }),
+            StandardChunk::ImpT(chunk::ImpTChunk {
+                imports: self.imports.iter().map(|x| x.to_part()).collect(),
+            }),
+            StandardChunk::ExpT(chunk::ExpTChunk {
+                exports: self.exports.iter().map(|x| x.to_part()).collect(),
+            }),
+        ];
+        if !self.lambdas.is_empty() {
+            chunks.push(StandardChunk::FunT(chunk::FunTChunk {
+                functions: self.lambdas.iter().map(|x| x.to_part()).collect(),
+            }));
+        }
+        if !self.literals.is_empty() {
+            chunks.push(StandardChunk::LitT(chunk::LitTChunk {
+                literals: self.literals.clone(),
+            }));
+        }
+        chunks.push(StandardChunk::LocT(chunk::LocTChunk {
+            locals: self.locals.iter().map(|x| x.to_part()).collect(),
+        }));
+
+        let mut raw_chunks = Vec::new();
+        for chunk in chunks {
+            let mut data = Vec::new();
+            chunk.encode_data(&mut data)?;
+            raw_chunks.push(chunk::RawChunk {
+                id: *chunk.id(),
+                data,
+            });
+        }
+        for chunk in &self.other_chunks {
+            raw_chunks.push(chunk::RawChunk {
+                id: chunk.id,
+                data: chunk.data.clone(),
+            });
+        }
+        Ok(BeamFile { chunks: raw_chunks })
+    }
+
+    /// Returns the name of this module.
+    pub fn name(&self) -> Option<&str> {
+        self.atoms.first().map(|x| x.as_str())
+    }
+
+    /// Returns the name of the given atom (`None` if the atom is `[]` or out of range).
+    pub fn atom_name(&self, atom: Atom) -> Option<&str> {
+        atom.value
+            .checked_sub(1)
+            .and_then(|i| self.atoms.get(i))
+            .map(|x| x.as_str())
+    }
+
+    /// Returns the atom named `name`, adding it to the atom table if needed.
+    pub fn intern_atom(&mut self, name: &str) -> Atom {
+        let i = match self.atoms.iter().position(|x| x == name) {
+            Some(i) => i,
+            None => {
+                self.atoms.push(name.to_owned());
+                self.atoms.len() - 1
+            }
+        };
+        Atom { value: i + 1 }
+    }
+
+    /// Returns the index of the import table entry for `module:function/arity`,
+    /// adding the entry if needed.
+    pub fn intern_import(&mut self, module: &str, function: &str, arity: usize) -> usize {
+        let import = Import {
+            module: self.intern_atom(module),
+            function: self.intern_atom(function),
+            arity,
+        };
+        match self.imports.iter().position(|x| *x == import) {
+            Some(i) => i,
+            None => {
+                self.imports.push(import);
+                self.imports.len() - 1
+            }
+        }
+    }
+
+    /// Returns the literal encoded in the External Term Format as `etf`
+    /// (including the leading version byte `131`), adding it to the literal table if needed.
+    pub fn intern_literal(&mut self, etf: &[u8]) -> Literal {
+        let i = match self.literals.iter().position(|x| x == etf) {
+            Some(i) => i,
+            None => {
+                self.literals.push(etf.to_owned());
+                self.literals.len() - 1
+            }
+        };
+        Literal { value: i }
+    }
+
+    /// Returns the data of the chunk `id` in [`Module::other_chunks`].
+    pub fn other_chunk(&self, id: &[u8; 4]) -> Option<&[u8]> {
+        self.other_chunks
+            .iter()
+            .find(|x| &x.id == id)
+            .map(|x| x.data.as_slice())
+    }
+}
+
+/// `Code` chunk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Code {
+    pub info_size: u32,
+    pub version: u32,
+    pub opcode_max: u32,
+    pub label_count: u32,
+    pub function_count: u32,
+    pub instructions: Vec<Instruction>,
+}
+
+/// An entry of the import table (`ImpT` chunk).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Import {
+    pub module: Atom,
+    pub function: Atom,
+    pub arity: usize,
+}
+
+impl Import {
+    fn to_part(self) -> parts::Import {
+        parts::Import {
+            module: self.module.value as u32,
+            function: self.function.value as u32,
+            arity: self.arity as u32,
+        }
+    }
+}
+
+impl From<parts::Import> for Import {
+    fn from(x: parts::Import) -> Self {
+        Self {
+            module: Atom {
+                value: x.module as usize,
+            },
+            function: Atom {
+                value: x.function as usize,
+            },
+            arity: x.arity as usize,
+        }
+    }
+}
+
+/// An entry of the export table (`ExpT` chunk).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Export {
+    pub function: Atom,
+    pub arity: usize,
+    pub label: Label,
+}
+
+impl Export {
+    fn to_part(self) -> parts::Export {
+        parts::Export {
+            function: self.function.value as u32,
+            arity: self.arity as u32,
+            label: self.label.value as u32,
+        }
+    }
+}
+
+impl From<parts::Export> for Export {
+    fn from(x: parts::Export) -> Self {
+        Self {
+            function: Atom {
+                value: x.function as usize,
+            },
+            arity: x.arity as usize,
+            label: Label {
+                value: x.label as usize,
+            },
+        }
+    }
+}
+
+/// An entry of the local function table (`LocT` chunk).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Local {
+    pub function: Atom,
+    pub arity: usize,
+    pub label: Label,
+}
+
+impl Local {
+    fn to_part(self) -> parts::Local {
+        parts::Local {
+            function: self.function.value as u32,
+            arity: self.arity as u32,
+            label: self.label.value as u32,
+        }
+    }
+}
+
+impl From<parts::Local> for Local {
+    fn from(x: parts::Local) -> Self {
+        Self {
+            function: Atom {
+                value: x.function as usize,
+            },
+            arity: x.arity as usize,
+            label: Label {
+                value: x.label as usize,
+            },
+        }
+    }
+}
+
+/// An entry of the lambda table (`FunT` chunk).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lambda {
+    pub function: Atom,
+    pub arity: usize,
+    pub label: Label,
+    pub index: usize,
+    pub num_free: usize,
+    pub old_uniq: u32,
+}
+
+impl Lambda {
+    fn to_part(self) -> parts::Function {
+        parts::Function {
+            function: self.function.value as u32,
+            arity: self.arity as u32,
+            label: self.label.value as u32,
+            index: self.index as u32,
+            num_free: self.num_free as u32,
+            old_uniq: self.old_uniq,
+        }
+    }
+}
+
+impl From<parts::Function> for Lambda {
+    fn from(x: parts::Function) -> Self {
+        Self {
+            function: Atom {
+                value: x.function as usize,
+            },
+            arity: x.arity as usize,
+            label: Label {
+                value: x.label as usize,
+            },
+            index: x.index as usize,
+            num_free: x.num_free as usize,
+            old_uniq: x.old_uniq,
+        }
+    }
+}
+
+/// A chunk which is not interpreted by this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtherChunk {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+// Checks the sizes in the container and the table headers of a `.beam` file, so that parsing it
+// never allocates more memory than the input justifies.
+fn check_beam_file(bytes: &[u8], limits: &DecodeLimits) -> Result<(), DecodeError> {
+    let payload_size = read_u32(bytes, 4).ok_or_else(|| malformed("truncated header"))?;
+    if payload_size < 4 || payload_size > bytes.len() - 8 {
+        return Err(malformed("payload size out of range"));
+    }
+    let mut chunks = &bytes[12..8 + payload_size];
+    while !chunks.is_empty() {
+        let size = read_u32(chunks, 4).ok_or_else(|| malformed("truncated chunk header"))?;
+        let data = chunks[8..]
+            .get(..size)
+            .ok_or_else(|| malformed("chunk size out of range"))?;
+        let id = &chunks[..4];
+        chunks = chunks
+            .get(8 + size.next_multiple_of(4)..)
+            .unwrap_or_default();
+
+        // The minimum number of bytes of a table entry.
+        let entry_size = match id {
+            b"Atom" | b"AtU8" => 1,
+            b"ImpT" | b"ExpT" | b"LocT" => 12,
+            b"FunT" => 24,
+            b"LitT" => {
+                check_literal_table(data, limits)?;
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(count) = read_u32(data, 0) {
+            if count.saturating_mul(entry_size) > data.len() - 4 {
+                return Err(malformed("too many table entries"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_literal_table(data: &[u8], limits: &DecodeLimits) -> Result<(), DecodeError> {
+    let mut table = Vec::new();
+    libflate::zlib::Decoder::new(data.get(4..).unwrap_or_default())?
+        .take((limits.max_literal_table_bytes as u64).saturating_add(1))
+        .read_to_end(&mut table)?;
+    if table.len() > limits.max_literal_table_bytes {
+        return Err(DecodeError::TooLargeLiteralTable {
+            max: limits.max_literal_table_bytes,
+        });
+    }
+
+    let count = read_u32(&table, 0).ok_or_else(|| malformed("truncated literal table"))?;
+    let mut literals = &table[4..];
+    for _ in 0..count {
+        let size = read_u32(literals, 0).ok_or_else(|| malformed("truncated literal table"))?;
+        literals = literals[4..]
+            .get(size..)
+            .ok_or_else(|| malformed("literal size out of range"))?;
+    }
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<usize> {
+    let bytes = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?) as usize)
+}
+
+fn malformed(reason: &str) -> DecodeError {
+    DecodeError::MalformedBeamFile {
+        reason: reason.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::etf::Value;
+    use crate::instruction::Return;
+
+    // Returns the offset of the data of the chunk `id` in `bytes` (a `.beam` file).
+    fn chunk_offset(bytes: &[u8], id: &[u8; 4]) -> usize {
+        let mut offset = 12;
+        while &bytes[offset..offset + 4] != id {
+            offset += 8 + read_u32(bytes, offset + 4).unwrap().next_multiple_of(4);
+        }
+        offset + 8
+    }
+
+    #[test]
+    fn from_reader_limited_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        builder.function("bar", 0).export().push(Return {});
+        let mut module = builder.build();
+        module.literals.push(Value::atom("ok").to_bytes().unwrap());
+        let mut bytes = Vec::new();
+        module.to_writer(&mut bytes).unwrap();
+        let limits = DecodeLimits::default();
+        assert_eq!(
+            Module::from_reader_limited(&bytes[..], &limits).unwrap(),
+            module
+        );
+
+        // A payload larger than the input.
+        let mut input = bytes.clone();
+        input[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Module::from_reader_limited(&input[..], &limits),
+            Err(DecodeError::MalformedBeamFile { .. })
+        ));
+
+        // An import table claiming to have 2^32 - 1 entries.
+        let mut input = bytes.clone();
+        let offset = chunk_offset(&input, b"ImpT");
+        input[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Module::from_reader_limited(&input[..], &limits),
+            Err(DecodeError::MalformedBeamFile { .. })
+        ));
+
+        // A literal table claiming to have 2^32 - 1 entries.
+        let mut table = u32::MAX.to_be_bytes().to_vec();
+        table.extend_from_slice(&[0, 0, 0, 1, 0]);
+        let mut encoder = libflate::zlib::Encoder::new(Vec::new()).unwrap();
+        std::io::Write::write_all(&mut encoder, &table).unwrap();
+        let compressed = encoder.finish().into_result().unwrap();
+        let mut literal_chunk = (table.len() as u32).to_be_bytes().to_vec();
+        literal_chunk.extend_from_slice(&compressed);
+        let mut input = bytes[..12].to_vec();
+        input.extend_from_slice(b"LitT");
+        input.extend_from_slice(&(literal_chunk.len() as u32).to_be_bytes());
+        input.extend_from_slice(&literal_chunk);
+        input.resize(12 + 8 + literal_chunk.len().next_multiple_of(4), 0);
+        let payload_size = input.len() as u32 - 8;
+        input[4..8].copy_from_slice(&payload_size.to_be_bytes());
+        assert!(matches!(
+            Module::from_reader_limited(&input[..], &limits),
+            Err(DecodeError::MalformedBeamFile { .. })
+        ));
+
+        let limits = DecodeLimits {
+            max_literal_table_bytes: 4,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Module::from_reader_limited(&bytes[..], &limits),
+            Err(DecodeError::TooLargeLiteralTable { max: 4 })
+        ));
+    }
+}
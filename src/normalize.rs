@@ -0,0 +1,232 @@
+//! Normalization of instruction sequences.
+//!
+//! Before OTP 22, the compiler built a tuple by `put_tuple` followed by one `put` instruction
+//! for each element:
+//!
+//! ```text
+//! {put_tuple, 2, {x,0}}.
+//! {put, {atom,ok}}.
+//! {put, {x,1}}.
+//! ```
+//!
+//! [`fold_put_tuples()`] replaces such a sequence with the equivalent single `put_tuple2`
+//! instruction, so that analyses see one instruction per tuple construction, and returns the
+//! indices of the folded instructions.
+//! [`expand_put_tuples()`] restores the original sequences of only those instructions, so
+//! `put_tuple2` instructions which were in the code from the beginning are kept as they are.
+//!
+//! [`decode_instructions_folded()`] and [`encode_instructions_expanded()`] combine these with
+//! decoding and encoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::instruction::{Instruction, Put, PutTuple};
+//! use beamcode::normalize::{expand_put_tuples, fold_put_tuples};
+//! use beamcode::term::Term;
+//!
+//! let original = vec![
+//!     Instruction::PutTuple(PutTuple {
+//!         arg1: Term::Usize(1),
+//!         arg2: Term::x(0),
+//!     }),
+//!     Instruction::Put(Put { arg1: Term::x(1) }),
+//! ];
+//! let mut instructions = original.clone();
+//! let folded = fold_put_tuples(&mut instructions);
+//! assert_eq!(folded, [0]);
+//! assert!(matches!(instructions[..], [Instruction::PutTuple2(_)]));
+//!
+//! expand_put_tuples(&mut instructions, &folded);
+//! assert_eq!(instructions, original);
+//! ```
+use crate::instruction::{Instruction, Put, PutTuple, PutTuple2};
+use crate::term::{List, Term};
+use crate::{DecodeError, EncodeError};
+
+/// Folds the `put_tuple` instruction at the beginning of `instructions` and the following
+/// `put` instructions into a `put_tuple2` instruction.
+///
+/// Returns `None` if `instructions` does not start with a well-formed `put_tuple` or the `put`
+/// instructions are fewer than the arity.
+pub fn fold_put_tuple(instructions: &[Instruction]) -> Option<PutTuple2> {
+    let Some(Instruction::PutTuple(x)) = instructions.first() else {
+        return None;
+    };
+    let arity = x.arg1.to_usize()?;
+    let destination = x.arg2.to_register()?;
+    let elements = instructions[1..]
+        .iter()
+        .take(arity)
+        .map_while(|x| match x {
+            Instruction::Put(x) => Some(x.arg1.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    (elements.len() == arity).then_some(PutTuple2 {
+        destination,
+        elements: List { items: elements },
+    })
+}
+
+/// Replaces every `put_tuple` sequence in `instructions` with `put_tuple2`.
+///
+/// Incomplete sequences are left as-is.
+/// Returns the indices of the folded instructions (in ascending order).
+/// If instructions are inserted into or removed from `instructions` afterwards,
+/// the indices must be updated accordingly before passing them to [`expand_put_tuples()`].
+pub fn fold_put_tuples(instructions: &mut Vec<Instruction>) -> Vec<usize> {
+    let old = std::mem::take(instructions);
+    let mut folded = Vec::new();
+    let mut i = 0;
+    while i < old.len() {
+        if let Some(x) = fold_put_tuple(&old[i..]) {
+            i += x.elements.items.len() + 1;
+            folded.push(instructions.len());
+            instructions.push(Instruction::PutTuple2(x));
+        } else {
+            instructions.push(old[i].clone());
+            i += 1;
+        }
+    }
+    folded
+}
+
+/// Replaces the `put_tuple2` instructions at `folded` (the indices returned by
+/// [`fold_put_tuples()`], in ascending order) with `put_tuple` followed by `put`s.
+///
+/// The other instructions (including `put_tuple2` ones) are left as-is.
+/// Returns the number of the expanded instructions.
+pub fn expand_put_tuples(instructions: &mut Vec<Instruction>, folded: &[usize]) -> usize {
+    let old = std::mem::take(instructions);
+    let mut count = 0;
+    for (i, instruction) in old.into_iter().enumerate() {
+        let Instruction::PutTuple2(x) = instruction else {
+            instructions.push(instruction);
+            continue;
+        };
+        if folded.binary_search(&i).is_err() {
+            instructions.push(Instruction::PutTuple2(x));
+            continue;
+        }
+        instructions.push(Instruction::PutTuple(PutTuple {
+            arg1: Term::Usize(x.elements.items.len()),
+            arg2: x.destination.into(),
+        }));
+        instructions.extend(
+            x.elements
+                .items
+                .into_iter()
+                .map(|arg1| Instruction::Put(Put { arg1 })),
+        );
+        count += 1;
+    }
+    count
+}
+
+/// Decodes BEAM instructions and folds their `put_tuple` sequences.
+///
+/// Returns the folded instructions and the indices of the folded ones
+/// (see [`fold_put_tuples()`]).
+pub fn decode_instructions_folded(
+    bytecode: &[u8],
+) -> Result<(Vec<Instruction>, Vec<usize>), DecodeError> {
+    let mut instructions = crate::decode_instructions(bytecode)?;
+    let folded = fold_put_tuples(&mut instructions);
+    Ok((instructions, folded))
+}
+
+/// Expands the folded instructions at `folded` (see [`expand_put_tuples()`]) and encodes them.
+pub fn encode_instructions_expanded(
+    instructions: &[Instruction],
+    folded: &[usize],
+) -> Result<Vec<u8>, EncodeError> {
+    let mut instructions = instructions.to_vec();
+    expand_put_tuples(&mut instructions, folded);
+    crate::encode_instructions(&instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Return;
+    use crate::term::{Atom, Register, Term};
+
+    #[test]
+    fn fold_and_expand_works() {
+        let put_tuple = |arity| {
+            Instruction::PutTuple(PutTuple {
+                arg1: Term::Usize(arity),
+                arg2: Term::x(0),
+            })
+        };
+        let put = |value| Instruction::Put(Put { arg1: value });
+        let original = vec![
+            put_tuple(2),
+            put(Term::Atom(Atom { value: 1 })),
+            put(Term::x(1)),
+            put_tuple(0),
+            put_tuple(2),
+            put(Term::x(1)),
+            Instruction::Return(Return {}),
+        ];
+
+        let mut instructions = original.clone();
+        let folded = fold_put_tuples(&mut instructions);
+        assert_eq!(folded, [0, 1]);
+        assert_eq!(
+            instructions,
+            [
+                Instruction::PutTuple2(PutTuple2 {
+                    destination: Register::x(0),
+                    elements: List {
+                        items: vec![Term::Atom(Atom { value: 1 }), Term::x(1)],
+                    },
+                }),
+                Instruction::PutTuple2(PutTuple2 {
+                    destination: Register::x(0),
+                    elements: List { items: Vec::new() },
+                }),
+                put_tuple(2),
+                put(Term::x(1)),
+                Instruction::Return(Return {}),
+            ]
+        );
+
+        assert_eq!(expand_put_tuples(&mut instructions, &folded), 2);
+        assert_eq!(instructions, original);
+    }
+
+    #[test]
+    fn expand_keeps_original_put_tuple2_works() {
+        let put_tuple2 = |register| {
+            Instruction::PutTuple2(PutTuple2 {
+                destination: Register::x(register),
+                elements: List {
+                    items: vec![Term::x(1)],
+                },
+            })
+        };
+        let original = vec![
+            put_tuple2(0),
+            Instruction::PutTuple(PutTuple {
+                arg1: Term::Usize(1),
+                arg2: Term::x(2),
+            }),
+            Instruction::Put(Put { arg1: Term::x(1) }),
+            Instruction::Return(Return {}),
+        ];
+        let bytecode = crate::encode_instructions(&original).unwrap();
+
+        let (instructions, folded) = decode_instructions_folded(&bytecode).unwrap();
+        assert_eq!(folded, [1]);
+        assert_eq!(
+            instructions,
+            [put_tuple2(0), put_tuple2(2), Instruction::Return(Return {})]
+        );
+        assert_eq!(
+            encode_instructions_expanded(&instructions, &folded).unwrap(),
+            bytecode
+        );
+    }
+}
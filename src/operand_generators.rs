@@ -0,0 +1,106 @@
+//! Generators of operands shared by the `arbitrary` and `proptest` integrations.
+//!
+//! [`impl_operand_arbitrary!`] implements both `arbitrary::Arbitrary` and
+//! `proptest::arbitrary::Arbitrary` (for the enabled features) from a single description of
+//! the alternatives of a type.
+use crate::term::{
+    Allocation, AllocationList, AllocationListItem, Atom, Float, FloatingPointRegister, Label,
+    Literal, Register, TypedRegister, XRegister, YRegister,
+};
+
+// The maximum number of the items (or pairs of items) of a generated list.
+pub(crate) const MAX_LIST_ITEMS: usize = 4;
+
+// Each `(field: Type, ...) => value` is an alternative; one of them is chosen uniformly and its
+// fields are generated by the `Arbitrary` implementations of their types.
+macro_rules! impl_operand_arbitrary {
+    ($($ty:ty { $(($($field:ident: $field_ty:ty),+) => $value:expr),+ $(,)? })*) => {
+        $(
+            #[cfg(feature = "proptest")]
+            impl proptest::arbitrary::Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+                fn arbitrary_with((): ()) -> Self::Strategy {
+                    use proptest::strategy::Strategy as _;
+                    proptest::prop_oneof![
+                        $(($(proptest::arbitrary::any::<$field_ty>(),)+)
+                            .prop_map(|($($field,)+)| $value)),+
+                    ]
+                    .boxed()
+                }
+            }
+
+            #[cfg(feature = "arbitrary")]
+            impl<'a> arbitrary::Arbitrary<'a> for $ty {
+                fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+                    type Alternative<'a> =
+                        fn(&mut arbitrary::Unstructured<'a>) -> arbitrary::Result<$ty>;
+                    let alternatives: &[Alternative<'a>] = &[$(|u| {
+                        $(let $field: $field_ty = u.arbitrary()?;)+
+                        Ok($value)
+                    }),+];
+                    u.choose(alternatives)?(u)
+                }
+            }
+        )*
+    };
+}
+
+// A list of at most `MAX_LIST_ITEMS` items.
+#[derive(Debug)]
+pub(crate) struct Items<T>(pub Vec<T>);
+
+#[cfg(feature = "proptest")]
+impl<T: proptest::arbitrary::Arbitrary + 'static> proptest::arbitrary::Arbitrary for Items<T> {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        use proptest::strategy::Strategy as _;
+        proptest::collection::vec(proptest::arbitrary::any::<T>(), 0..=MAX_LIST_ITEMS)
+            .prop_map(Self)
+            .boxed()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for Items<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let items = (0..u.int_in_range(0..=MAX_LIST_ITEMS)?)
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?;
+        Ok(Self(items))
+    }
+}
+
+// Type information is not encoded in the operands, so `ty` of `XRegister` and `YRegister`
+// is always `None`.
+impl_operand_arbitrary! {
+    Atom { (value: usize) => Atom { value } }
+    Label { (value: usize) => Label { value } }
+    Literal { (value: usize) => Literal { value } }
+    FloatingPointRegister { (value: usize) => FloatingPointRegister { value } }
+    Float { (value: f64) => Float { value } }
+    XRegister { (value: usize) => XRegister { value, ty: None } }
+    YRegister { (value: usize) => YRegister { value, ty: None } }
+    TypedRegister {
+        (register: XRegister, ty: usize) => TypedRegister::X { register, ty },
+        (register: YRegister, ty: usize) => TypedRegister::Y { register, ty },
+    }
+    Register {
+        (x: XRegister) => Register::X(x),
+        (x: YRegister) => Register::Y(x),
+        (x: TypedRegister) => Register::Typed(x),
+    }
+    AllocationListItem {
+        (n: usize) => AllocationListItem::Words(n),
+        (n: usize) => AllocationListItem::Floats(n),
+        (n: usize) => AllocationListItem::Funs(n),
+    }
+    AllocationList { (items: Items<AllocationListItem>) => AllocationList { items: items.0 } }
+    Allocation {
+        (n: usize) => Allocation::Words(n),
+        (x: AllocationList) => Allocation::List(x),
+    }
+}
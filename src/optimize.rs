@@ -0,0 +1,371 @@
+//! Peephole optimizations.
+//!
+//! An optimization is implemented as a [`Pass`], which rewrites a sequence of instructions
+//! (typically the whole code chunk or a function) and reports what it changed.
+//! The passes keep the function headers (`label`, `line`, `func_info`, `label`) and
+//! `int_code_end` intact, thus their results can be encoded by
+//! [`encode_instructions()`](crate::encode_instructions) as-is.
+//!
+//! Labels referred to only by the export, local or lambda tables are always function entry
+//! labels, which are never removed.
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::term::Label;
+use crate::{Visit, Visitor};
+use std::collections::BTreeSet;
+
+/// An optimization pass.
+pub trait Pass {
+    /// Returns the name of this pass.
+    fn name(&self) -> &'static str;
+
+    /// Rewrites `instructions` and returns the changes made.
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change>;
+}
+
+/// A change made by a [`Pass`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The instruction at `index` (in the sequence given to [`Pass::run()`]) was removed.
+    Removed {
+        index: usize,
+        instruction: Instruction,
+    },
+}
+
+/// The changes made by a run of a [`Pass`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassReport {
+    pub pass: &'static str,
+    pub changes: Vec<Change>,
+}
+
+/// A sequence of passes.
+pub struct Optimizer {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl Optimizer {
+    /// Makes an optimizer which runs nothing.
+    pub fn new() -> Self {
+        Self { passes: Vec::new() }
+    }
+
+    /// Appends a pass.
+    pub fn pass<P: 'static + Pass>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Runs the passes in order repeatedly until none of them changes `instructions`.
+    ///
+    /// The reports of the runs which changed something are returned.
+    pub fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<PassReport> {
+        let mut reports = Vec::new();
+        loop {
+            let mut changed = false;
+            for pass in &mut self.passes {
+                let changes = pass.run(instructions);
+                if !changes.is_empty() {
+                    changed = true;
+                    reports.push(PassReport {
+                        pass: pass.name(),
+                        changes,
+                    });
+                }
+            }
+            if !changed {
+                return reports;
+            }
+        }
+    }
+
+    /// Runs the passes over the code of `module` (see [`Optimizer::run()`]).
+    pub fn run_module(&mut self, module: &mut Module) -> Vec<PassReport> {
+        self.run(&mut module.code.instructions)
+    }
+}
+
+impl Default for Optimizer {
+    /// Makes an optimizer which runs all the passes defined in this module.
+    fn default() -> Self {
+        Self::new()
+            .pass(RemoveSelfMoves)
+            .pass(RemoveJumpsToNextLabel)
+            .pass(RemoveUnreachableCode)
+            .pass(RemoveUnusedLabels)
+            .pass(MergeLines)
+    }
+}
+
+impl std::fmt::Debug for Optimizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Optimizer")
+            .field(
+                "passes",
+                &self.passes.iter().map(|x| x.name()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Removes `move` instructions whose source and destination are the same register.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveSelfMoves;
+
+impl Pass for RemoveSelfMoves {
+    fn name(&self) -> &'static str {
+        "remove_self_moves"
+    }
+
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change> {
+        remove_if(instructions, |instructions, i| {
+            matches!(&instructions[i], Instruction::Move(x)
+                if x.src.to_register().is_some_and(|src| src.untyped() == x.dst.untyped()))
+        })
+    }
+}
+
+/// Removes `jump` instructions to the label which immediately follows them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveJumpsToNextLabel;
+
+impl Pass for RemoveJumpsToNextLabel {
+    fn name(&self) -> &'static str {
+        "remove_jumps_to_next_label"
+    }
+
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change> {
+        remove_if(instructions, |instructions, i| {
+            let Instruction::Jump(x) = &instructions[i] else {
+                return false;
+            };
+            instructions[i + 1..]
+                .iter()
+                .map_while(|x| match x {
+                    Instruction::Label(x) => Some(x.literal),
+                    _ => None,
+                })
+                .any(|literal| literal == x.label.value)
+        })
+    }
+}
+
+/// Removes instructions which follow a terminator (e.g., `jump` or `return`) and
+/// are not preceded by any label.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveUnreachableCode;
+
+impl Pass for RemoveUnreachableCode {
+    fn name(&self) -> &'static str {
+        "remove_unreachable_code"
+    }
+
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change> {
+        let mut unreachable = BTreeSet::new();
+        let mut reachable = true;
+        for (i, instruction) in instructions.iter().enumerate() {
+            match instruction {
+                Instruction::Label(_) | Instruction::IntCodeEnd(_) => reachable = true,
+                _ if !reachable => {
+                    unreachable.insert(i);
+                    continue;
+                }
+                _ => {}
+            }
+            if instruction.is_terminator() {
+                reachable = false;
+            }
+        }
+        remove_if(instructions, |_, i| unreachable.contains(&i))
+    }
+}
+
+/// Removes labels which are never referred to.
+///
+/// The labels of function headers are kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveUnusedLabels;
+
+impl Pass for RemoveUnusedLabels {
+    fn name(&self) -> &'static str {
+        "remove_unused_labels"
+    }
+
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change> {
+        let used = referred_labels(instructions);
+        remove_if(instructions, |instructions, i| {
+            let Instruction::Label(x) = &instructions[i] else {
+                return false;
+            };
+            !used.contains(&Label { value: x.literal }) && !is_function_header(instructions, i)
+        })
+    }
+}
+
+/// Removes `line` instructions which are identical to the immediately preceding one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeLines;
+
+impl Pass for MergeLines {
+    fn name(&self) -> &'static str {
+        "merge_lines"
+    }
+
+    fn run(&mut self, instructions: &mut Vec<Instruction>) -> Vec<Change> {
+        remove_if(instructions, |instructions, i| {
+            matches!((i.checked_sub(1).map(|j| &instructions[j]), &instructions[i]),
+                (Some(Instruction::Line(a)), Instruction::Line(b)) if a == b)
+        })
+    }
+}
+
+/// Returns the labels referred to by `instructions`.
+pub fn referred_labels(instructions: &[Instruction]) -> BTreeSet<Label> {
+    #[derive(Default)]
+    struct Collector(BTreeSet<Label>);
+    impl Visitor for Collector {
+        fn visit_label(&mut self, label: &Label) {
+            if label.value != 0 {
+                self.0.insert(*label);
+            }
+        }
+    }
+
+    let mut collector = Collector::default();
+    for instruction in instructions {
+        if !matches!(instruction, Instruction::Label(_)) {
+            instruction.visit(&mut collector);
+        }
+    }
+    collector.0
+}
+
+// Returns `true` if the `i`-th instruction is a label just before (ignoring `line`) or
+// after a `func_info` instruction.
+fn is_function_header(instructions: &[Instruction], i: usize) -> bool {
+    let after = instructions[i + 1..]
+        .iter()
+        .find(|x| !matches!(x, Instruction::Line(_)));
+    let before = i.checked_sub(1).map(|j| &instructions[j]);
+    matches!(after, Some(Instruction::FuncInfo(_)))
+        || matches!(before, Some(Instruction::FuncInfo(_)))
+}
+
+// Removes the instructions satisfying `f` (evaluated against the original sequence).
+fn remove_if<F>(instructions: &mut Vec<Instruction>, mut f: F) -> Vec<Change>
+where
+    F: FnMut(&[Instruction], usize) -> bool,
+{
+    let targets = (0..instructions.len())
+        .filter(|&i| f(instructions, i))
+        .collect::<BTreeSet<_>>();
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let mut changes = Vec::new();
+    let old = std::mem::take(instructions);
+    for (index, instruction) in old.into_iter().enumerate() {
+        if targets.contains(&index) {
+            changes.push(Change::Removed { index, instruction });
+        } else {
+            instructions.push(instruction);
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, FuncInfo, IntCodeEnd, IsNil, Jump, Line, Move, Return};
+    use crate::term::{Atom, Register, Term};
+
+    fn label(literal: usize) -> Instruction {
+        Instruction::Label(instruction::Label { literal })
+    }
+
+    fn line(literal: usize) -> Instruction {
+        Instruction::Line(Line { literal })
+    }
+
+    fn jump(value: usize) -> Instruction {
+        Instruction::Jump(Jump {
+            label: Label { value },
+        })
+    }
+
+    #[test]
+    fn optimize_works() {
+        let mut instructions = vec![
+            label(1),
+            line(1),
+            func_info(),
+            label(2),
+            Instruction::IsNil(IsNil {
+                label: Label { value: 4 },
+                arg1: Term::x(0),
+            }),
+            line(2),
+            line(2),
+            Instruction::Move(Move {
+                src: Term::x(0),
+                dst: Register::x(0),
+            }),
+            jump(3),
+            Instruction::Return(Return {}),
+            label(3),
+            Instruction::Return(Return {}),
+            label(4),
+            label(5),
+            jump(5),
+            Instruction::IntCodeEnd(IntCodeEnd {}),
+        ];
+        let reports = Optimizer::default().run(&mut instructions);
+        assert_eq!(
+            instructions,
+            vec![
+                label(1),
+                line(1),
+                func_info(),
+                label(2),
+                Instruction::IsNil(IsNil {
+                    label: Label { value: 4 },
+                    arg1: Term::x(0),
+                }),
+                line(2),
+                Instruction::Return(Return {}),
+                label(4),
+                label(5),
+                jump(5),
+                Instruction::IntCodeEnd(IntCodeEnd {}),
+            ]
+        );
+        assert_eq!(
+            reports.iter().map(|x| x.pass).collect::<Vec<_>>(),
+            [
+                "remove_self_moves",
+                "remove_unreachable_code",
+                "merge_lines",
+                "remove_jumps_to_next_label",
+                "remove_unused_labels",
+            ]
+        );
+        assert_eq!(
+            reports[1].changes,
+            vec![Change::Removed {
+                index: 8,
+                instruction: Instruction::Return(Return {})
+            }]
+        );
+        crate::encode_instructions(&instructions).unwrap();
+    }
+
+    fn func_info() -> Instruction {
+        Instruction::FuncInfo(FuncInfo {
+            module: Atom { value: 1 },
+            function: Atom { value: 2 },
+            arity: 1,
+        })
+    }
+}
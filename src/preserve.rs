@@ -0,0 +1,168 @@
+//! Byte-exact round-trips of non-canonically encoded instructions.
+//!
+//! [`encode_instructions()`](crate::encode_instructions) always encodes integers in the shortest
+//! (canonical) form, so bytecode produced by other tools may not be reproduced exactly.
+//! [`decode_instructions_preserving()`] additionally records the original bytes of every
+//! instruction whose canonical encoding differs from them, and
+//! [`encode_instructions_preserving()`] reuses those bytes for the instructions equal to the
+//! decoded ones. Modified or added instructions are encoded canonically.
+//!
+//! The original bytes are associated with the contents of instructions (not their indices),
+//! so inserting or removing instructions does not affect the other ones.
+//! If the same instruction appears more than once, its `n`-th occurrence gets the original bytes
+//! of the `n`-th occurrence in the decoded bytecode.
+//!
+//! # Limitations
+//!
+//! - The original bytes are recorded per instruction, not per operand.
+//!   Modifying any operand of an instruction (e.g., renumbering its labels) makes the whole
+//!   instruction encoded canonically, including its unmodified operands.
+//! - Occurrences of the same instruction are matched by their order only.
+//!   Removing (or inserting) an occurrence of an instruction which appears more than once shifts
+//!   the original bytes of the following occurrences, so a non-canonical encoding may move
+//!   to another occurrence. The decoded instructions are the same either way.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::preserve::{decode_instructions_preserving, encode_instructions_preserving};
+//!
+//! // {move,{x,0},{x,1}} whose destination is encoded in two bytes.
+//! let bytecode = [64, 3, 0b0000_1011, 1];
+//! let (instructions, originals) = decode_instructions_preserving(&bytecode)?;
+//! assert_eq!(beamcode::encode_instructions(&instructions)?, [64, 3, 19]);
+//! assert_eq!(encode_instructions_preserving(&instructions, &originals)?, bytecode);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::instruction::Instruction;
+use crate::{Decode, DecodeError, Encode, EncodeError};
+use std::collections::HashMap;
+
+/// The original bytes of non-canonically encoded instructions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OriginalEncodings {
+    // Canonical bytes => the original bytes of each occurrence (in order of appearance).
+    // Only the instructions which have at least one non-canonical occurrence are recorded.
+    entries: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+}
+
+impl OriginalEncodings {
+    /// Returns the number of the non-canonically encoded instructions.
+    pub fn len(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(canonical, originals)| originals.iter().filter(|x| *x != canonical).count())
+            .sum()
+    }
+
+    /// Returns `true` if all the instructions were canonically encoded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Decodes BEAM instructions, recording the original bytes of non-canonically encoded ones.
+pub fn decode_instructions_preserving(
+    bytecode: &[u8],
+) -> Result<(Vec<Instruction>, OriginalEncodings), DecodeError> {
+    let mut reader = bytecode;
+    let mut instructions = Vec::new();
+    let mut entries = HashMap::<_, Vec<_>>::new();
+    while !reader.is_empty() {
+        let start = bytecode.len() - reader.len();
+        let instruction = Instruction::decode(&mut reader)?;
+        let original = &bytecode[start..bytecode.len() - reader.len()];
+
+        // Decoded instructions can always be encoded.
+        let mut canonical = Vec::new();
+        if instruction.encode(&mut canonical).is_ok() {
+            entries
+                .entry(canonical)
+                .or_default()
+                .push(original.to_owned());
+        }
+        instructions.push(instruction);
+    }
+    entries.retain(|canonical, originals| originals.iter().any(|x| x != canonical));
+    Ok((instructions, OriginalEncodings { entries }))
+}
+
+/// Encodes BEAM instructions, reusing the original bytes recorded in `originals`.
+pub fn encode_instructions_preserving(
+    instructions: &[Instruction],
+    originals: &OriginalEncodings,
+) -> Result<Vec<u8>, EncodeError> {
+    let mut buf = Vec::new();
+    let mut occurrences = HashMap::<Vec<u8>, usize>::new();
+    let mut canonical = Vec::new();
+    for instruction in instructions {
+        canonical.clear();
+        instruction.encode(&mut canonical)?;
+        let original = originals.entries.get(&canonical).and_then(|x| {
+            let n = occurrences.entry(canonical.clone()).or_default();
+            *n += 1;
+            x.get(*n - 1)
+        });
+        buf.extend_from_slice(original.unwrap_or(&canonical));
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Return;
+    use crate::term::{Register, Term};
+
+    #[test]
+    fn preserving_roundtrip_works() {
+        // return, {move,{x,0},{x,1}} with a non-canonical x register, return
+        let bytecode = [19, 64, 3, 0b0000_1011, 1, 19];
+        let (mut instructions, originals) = decode_instructions_preserving(&bytecode).unwrap();
+        assert_eq!(originals.len(), 1);
+        assert_eq!(
+            encode_instructions_preserving(&instructions, &originals).unwrap(),
+            bytecode
+        );
+
+        // Modified instructions are encoded canonically.
+        let Instruction::Move(x) = &mut instructions[1] else {
+            panic!()
+        };
+        x.src = Term::x(2);
+        x.dst = Register::x(1);
+        assert_eq!(
+            encode_instructions_preserving(&instructions, &originals).unwrap(),
+            [19, 64, 35, 19, 19]
+        );
+    }
+
+    #[test]
+    fn preserving_roundtrip_after_edits_works() {
+        // {move,{x,0},{x,1}} (non-canonical), return, {move,{x,0},{x,1}} (canonical),
+        // {move,{x,0},{x,1}} (non-canonical in another way)
+        let bytecode = [64, 3, 0b0000_1011, 1, 19, 64, 3, 19, 64, 0b0000_1011, 0, 19];
+        let (mut instructions, originals) = decode_instructions_preserving(&bytecode).unwrap();
+        assert_eq!(originals.len(), 2);
+        assert_eq!(
+            encode_instructions_preserving(&instructions, &originals).unwrap(),
+            bytecode
+        );
+
+        // Unmodified instructions keep their original bytes.
+        instructions.insert(0, Instruction::Return(Return {}));
+        instructions.remove(2);
+        assert_eq!(
+            encode_instructions_preserving(&instructions, &originals).unwrap(),
+            [19, 64, 3, 0b0000_1011, 1, 64, 3, 19, 64, 0b0000_1011, 0, 19]
+        );
+
+        // Occurrences are matched by order, so removing the first `move` shifts the original
+        // bytes of the following ones.
+        instructions.remove(1);
+        assert_eq!(
+            encode_instructions_preserving(&instructions, &originals).unwrap(),
+            [19, 64, 3, 0b0000_1011, 1, 64, 3, 19]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! [`proptest`] integration (enabled by the `proptest` feature).
+//!
+//! [`Arbitrary`] is implemented for [`Term`], [`Instruction`](crate::instruction::Instruction)
+//! and the operand types, so `any::<Instruction>()` can be used in downstream property tests.
+//! Every generated value satisfies `decode(encode(x)) == x`.
+//!
+//! Note that the generated instructions are only well-typed at the Rust level
+//! (e.g., a `Term` operand may be any term).
+use crate::operand_generators::MAX_LIST_ITEMS;
+use crate::term::{
+    AllocationList, Atom, Float, FloatingPointRegister, Label, List, Literal, Term, TypedRegister,
+    XRegister, YRegister,
+};
+use num::BigInt;
+use proptest::arbitrary::{any, Arbitrary};
+use proptest::collection::vec;
+use proptest::prop_oneof;
+use proptest::strategy::{BoxedStrategy, Strategy};
+
+impl<T: Arbitrary + 'static> Arbitrary for List<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        vec(any::<T>(), 0..=MAX_LIST_ITEMS)
+            .prop_map(|items| Self { items })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Term {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        let leaf = prop_oneof![
+            any::<usize>().prop_map(Self::Usize),
+            vec(any::<u8>(), 0..16).prop_map(|x| Self::Integer(BigInt::from_signed_bytes_be(&x))),
+            any::<Atom>().prop_map(Self::Atom),
+            any::<XRegister>().prop_map(Self::XRegister),
+            any::<YRegister>().prop_map(Self::YRegister),
+            any::<Label>().prop_map(Self::Label),
+            any::<char>().prop_map(Self::Character),
+            any::<Float>().prop_map(Self::Float),
+            any::<FloatingPointRegister>().prop_map(Self::FloatingPointRegister),
+            any::<AllocationList>().prop_map(Self::AllocationList),
+            any::<Literal>().prop_map(Self::Literal),
+            any::<TypedRegister>().prop_map(Self::TypedRegister),
+        ];
+        leaf.prop_recursive(3, 16, 4, |inner| {
+            vec(inner, 0..=MAX_LIST_ITEMS).prop_map(|items| Self::List(List { items }))
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+    use crate::{Decode, Encode};
+    use proptest::proptest;
+
+    proptest! {
+        #[test]
+        fn term_roundtrip_works(term in any::<Term>()) {
+            let mut buf = Vec::new();
+            term.encode(&mut buf).unwrap();
+            assert_eq!(Term::decode(&mut &buf[..]).unwrap(), term);
+        }
+
+        #[test]
+        fn instruction_roundtrip_works(instruction in any::<Instruction>()) {
+            let mut buf = Vec::new();
+            instruction.encode(&mut buf).unwrap();
+            let mut reader = &buf[..];
+            assert_eq!(Instruction::decode(&mut reader).unwrap(), instruction);
+            assert!(reader.is_empty());
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Label renumbering.
+//!
+//! This is useful, for example, before splicing instructions taken from another function
+//! (or module) so that the label numbers do not collide.
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::term::Label;
+use crate::{Visit, VisitorMut};
+use std::collections::BTreeMap;
+
+/// Renumbers the labels defined in `instructions` densely starting from `first_label`.
+///
+/// Labels are numbered in order of their definitions.
+/// All references to the defined labels (including those in jump tables) are updated accordingly.
+/// References to labels not defined in `instructions`, and the special label `0`
+/// (which means "no fail label"), are left untouched.
+///
+/// If a label is defined more than once, every definition is mapped to the number
+/// assigned to the first one.
+///
+/// The returned map is from the old labels to the new ones.
+pub fn relabel(instructions: &mut [Instruction], first_label: usize) -> BTreeMap<Label, Label> {
+    let mut map = BTreeMap::new();
+    let mut next = first_label;
+    for instruction in instructions.iter() {
+        if let Instruction::Label(x) = instruction {
+            map.entry(Label { value: x.literal }).or_insert_with(|| {
+                next += 1;
+                Label { value: next - 1 }
+            });
+        }
+    }
+
+    let mut relabeler = Relabeler { map: &map };
+    for instruction in instructions.iter_mut() {
+        if let Instruction::Label(x) = instruction {
+            x.literal = map[&Label { value: x.literal }].value;
+        } else {
+            instruction.visit_mut(&mut relabeler);
+        }
+    }
+    map
+}
+
+/// Renumbers the labels of `module` densely starting from `1`.
+///
+/// In addition to [`relabel()`], this function updates the labels of the export, local and lambda
+/// tables, and recomputes the label count of the `Code` chunk.
+pub fn relabel_module(module: &mut Module) -> BTreeMap<Label, Label> {
+    let map = relabel(&mut module.code.instructions, 1);
+    let lookup = |label: Label| map.get(&label).copied().unwrap_or(label);
+    for x in &mut module.exports {
+        x.label = lookup(x.label);
+    }
+    for x in &mut module.locals {
+        x.label = lookup(x.label);
+    }
+    for x in &mut module.lambdas {
+        x.label = lookup(x.label);
+    }
+    module.code.label_count = map.len() as u32 + 1;
+    map
+}
+
+#[derive(Debug)]
+struct Relabeler<'a> {
+    map: &'a BTreeMap<Label, Label>,
+}
+
+impl<'a> VisitorMut for Relabeler<'a> {
+    fn visit_label_mut(&mut self, label: &mut Label) {
+        if label.value != 0 {
+            if let Some(x) = self.map.get(label) {
+                *label = *x;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, FuncInfo, IsNil, Jump, Return, SelectVal};
+    use crate::term::{Atom, List, Term, XRegister};
+
+    fn label(value: usize) -> Instruction {
+        Instruction::Label(instruction::Label { literal: value })
+    }
+
+    fn x0() -> Term {
+        Term::XRegister(XRegister { value: 0, ty: None })
+    }
+
+    #[test]
+    fn relabel_works() {
+        let mut instructions = vec![
+            label(10),
+            Instruction::FuncInfo(FuncInfo {
+                module: Atom { value: 1 },
+                function: Atom { value: 2 },
+                arity: 1,
+            }),
+            label(11),
+            Instruction::SelectVal(SelectVal {
+                arg: x0(),
+                fail_label: Label { value: 10 },
+                destinations: List {
+                    items: vec![
+                        Term::Atom(Atom { value: 3 }),
+                        Term::Label(Label { value: 20 }),
+                    ],
+                },
+            }),
+            label(20),
+            Instruction::IsNil(IsNil {
+                label: Label { value: 0 },
+                arg1: x0(),
+            }),
+            Instruction::Jump(Jump {
+                label: Label { value: 99 },
+            }),
+            Instruction::Return(Return {}),
+        ];
+        let map = relabel(&mut instructions, 1);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[&Label { value: 20 }], Label { value: 3 });
+
+        assert_eq!(instructions[0], label(1));
+        assert_eq!(instructions[2], label(2));
+        assert_eq!(instructions[4], label(3));
+        let Instruction::SelectVal(x) = &instructions[3] else {
+            panic!()
+        };
+        assert_eq!(x.fail_label, Label { value: 1 });
+        assert_eq!(x.destinations.items[1], Term::Label(Label { value: 3 }));
+        let Instruction::IsNil(x) = &instructions[5] else {
+            panic!()
+        };
+        assert_eq!(x.label, Label { value: 0 });
+        let Instruction::Jump(x) = &instructions[6] else {
+            panic!()
+        };
+        assert_eq!(x.label, Label { value: 99 });
+    }
+}
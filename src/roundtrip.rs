@@ -0,0 +1,163 @@
+//! Round-trip verification.
+//!
+//! [`verify_roundtrip()`] decodes bytecode instruction by instruction, re-encodes each
+//! instruction, and reports the instructions whose re-encoded bytes differ from the original ones.
+//! If an instruction cannot be decoded, the verification stops there and the report records
+//! where it failed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::roundtrip::verify_roundtrip;
+//!
+//! # let bytecode = Vec::new();
+//! let report = verify_roundtrip(&bytecode);
+//! for x in &report.mismatches {
+//!     println!("[{}] {:?}: expected={:?}, actual={:?}", x.index, x.instruction, x.expected, x.actual);
+//! }
+//! if let Some((index, offset, e)) = &report.decode_error {
+//!     println!("[{index}] failed to decode at byte {offset}: {e}");
+//! }
+//! ```
+use crate::instruction::Instruction;
+use crate::{Decode, DecodeError, Encode};
+use std::ops::Range;
+
+/// The result of [`verify_roundtrip()`].
+#[derive(Debug, Default)]
+pub struct RoundtripReport {
+    /// The number of the decoded instructions.
+    pub instructions: usize,
+
+    /// The mismatched instructions in order of appearance.
+    pub mismatches: Vec<Mismatch>,
+
+    /// The index and the byte offset of the instruction that could not be decoded, and the error.
+    ///
+    /// The instructions after it are not verified.
+    pub decode_error: Option<(usize, usize, DecodeError)>,
+}
+
+impl RoundtripReport {
+    /// Returns `true` if every instruction was decoded and re-encoded identically.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty() && self.decode_error.is_none()
+    }
+
+    /// Returns the first mismatch, if any.
+    pub fn first_mismatch(&self) -> Option<&Mismatch> {
+        self.mismatches.first()
+    }
+}
+
+/// An instruction whose re-encoded bytes differ from the original ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The index of the instruction.
+    pub index: usize,
+
+    /// The byte range of the instruction in the original bytecode.
+    pub range: Range<usize>,
+
+    /// The original bytes.
+    pub expected: Vec<u8>,
+
+    /// The re-encoded bytes (the bytes written before the failure, if encoding failed).
+    pub actual: Vec<u8>,
+
+    pub instruction: Instruction,
+}
+
+/// Decodes and re-encodes every instruction in `bytecode` (e.g., the bytecode of a `Code` chunk),
+/// and reports the mismatches.
+///
+/// If an instruction cannot be decoded, the report covers the instructions before it
+/// (see [`RoundtripReport::decode_error`]).
+pub fn verify_roundtrip(bytecode: &[u8]) -> RoundtripReport {
+    let mut report = RoundtripReport::default();
+    let mut reader = bytecode;
+    while !reader.is_empty() {
+        let start = bytecode.len() - reader.len();
+        let instruction = match Instruction::decode(&mut reader) {
+            Ok(instruction) => instruction,
+            Err(e) => {
+                report.decode_error = Some((report.instructions, start, e));
+                break;
+            }
+        };
+        let end = bytecode.len() - reader.len();
+
+        let mut actual = Vec::new();
+        let encoded = instruction.encode(&mut actual);
+        let expected = &bytecode[start..end];
+        if encoded.is_err() || actual != expected {
+            report.mismatches.push(Mismatch {
+                index: report.instructions,
+                range: start..end,
+                expected: expected.to_owned(),
+                actual,
+                instruction,
+            });
+        }
+        report.instructions += 1;
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Move;
+    use crate::term::{Register, Term};
+
+    #[test]
+    fn verify_roundtrip_works() {
+        // return, {move,{x,0},{x,1}} with a non-canonical x register, return
+        let bytecode = [19, 64, 3, 0b0000_1011, 1, 19];
+        let report = verify_roundtrip(&bytecode);
+        assert_eq!(report.instructions, 3);
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.first_mismatch(),
+            Some(&Mismatch {
+                index: 1,
+                range: 1..5,
+                expected: vec![64, 3, 0b0000_1011, 1],
+                actual: vec![64, 3, 19],
+                instruction: Instruction::Move(Move {
+                    src: Term::x(0),
+                    dst: Register::x(1),
+                }),
+            })
+        );
+        assert!(verify_roundtrip(&[19, 64, 3, 19]).is_ok());
+    }
+
+    #[test]
+    fn verify_roundtrip_reports_every_mismatch_works() {
+        let report = verify_roundtrip(&[]);
+        assert_eq!(report.instructions, 0);
+        assert_eq!(report.first_mismatch(), None);
+
+        let bytecode = [64, 3, 0b0000_1011, 1, 19, 64, 0b0000_1011, 0, 19];
+        let report = verify_roundtrip(&bytecode);
+        assert_eq!(
+            report
+                .mismatches
+                .iter()
+                .map(|x| (x.index, x.range.clone()))
+                .collect::<Vec<_>>(),
+            [(0, 0..4), (2, 5..9)]
+        );
+    }
+
+    #[test]
+    fn verify_truncated_roundtrip_fails() {
+        // return, {move,{x,0},{x,1}} with a non-canonical x register, return, truncated move
+        let report = verify_roundtrip(&[19, 64, 3, 0b0000_1011, 1, 19, 64, 3]);
+        assert!(!report.is_ok());
+        assert_eq!(report.instructions, 3);
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(matches!(report.decode_error, Some((3, 6, _))));
+    }
+}
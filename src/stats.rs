@@ -0,0 +1,268 @@
+//! Instruction and operand statistics.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::module::Module;
+//! use beamcode::stats::Stats;
+//!
+//! let mut stats = Stats::new();
+//! for path in ["foo.beam", "bar.beam"] {
+//!     stats.add_module(&Module::from_file(path)?)?;
+//! }
+//! println!("{}", stats.to_json());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::call_graph::Mfa;
+use crate::function::split_functions;
+use crate::module::Module;
+use crate::term::{Term, TermKind, TypedRegister};
+use crate::{Encode, EncodeError};
+use num::BigInt;
+use std::collections::BTreeMap;
+
+/// Statistics of modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Statistics of each instruction type (keyed by the name, e.g., `"move"`).
+    pub instructions: BTreeMap<&'static str, InstructionStats>,
+
+    /// The number of operands of each kind (the elements of lists are also counted).
+    pub term_kinds: BTreeMap<TermKind, usize>,
+
+    /// Register usage of each function.
+    pub functions: Vec<FunctionStats>,
+
+    /// The number of integer operands which do not fit in a small integer
+    /// (i.e., a signed 60-bit integer on 64-bit emulators).
+    pub bignums: usize,
+}
+
+/// Statistics of an instruction type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionStats {
+    pub opcode: u8,
+    pub count: usize,
+
+    /// The total number of bytes of the encoded instructions.
+    pub encoded_bytes: usize,
+}
+
+/// Register usage of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionStats {
+    pub function: Mfa,
+
+    /// The number of x registers used (i.e., the maximum index plus one).
+    pub x_registers: usize,
+
+    /// The number of y registers used (i.e., the maximum index plus one).
+    pub y_registers: usize,
+}
+
+impl Stats {
+    /// Makes an empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the statistics of `module`.
+    pub fn from_module(module: &Module) -> Result<Self, EncodeError> {
+        let mut this = Self::new();
+        this.add_module(module)?;
+        Ok(this)
+    }
+
+    /// Adds the instructions of `module` to this statistics.
+    pub fn add_module(&mut self, module: &Module) -> Result<(), EncodeError> {
+        let instructions = &module.code.instructions;
+        let mut buf = Vec::new();
+        for instruction in instructions {
+            buf.clear();
+            instruction.encode(&mut buf)?;
+            let entry = self
+                .instructions
+                .entry(instruction.name())
+                .or_insert_with(|| InstructionStats {
+                    opcode: instruction.opcode(),
+                    ..Default::default()
+                });
+            entry.count += 1;
+            entry.encoded_bytes += buf.len();
+        }
+
+        let module_name = module.name().unwrap_or_default();
+        let mut function_stats = split_functions(instructions)
+            .into_iter()
+            .map(|f| {
+                let name = module.atom_name(f.name).unwrap_or_default();
+                (
+                    f.range.clone(),
+                    FunctionStats {
+                        function: Mfa::new(module_name, name, f.arity),
+                        x_registers: 0,
+                        y_registers: 0,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut owners = vec![None; instructions.len()];
+        for (j, (range, _)) in function_stats.iter().enumerate() {
+            owners[range.clone()].fill(Some(j));
+        }
+        for (instruction, owner) in instructions.iter().zip(owners) {
+            let mut function = owner.map(|j| &mut function_stats[j].1);
+            for operand in instruction.operands() {
+                self.add_term(&operand, &mut function);
+            }
+        }
+        self.functions
+            .extend(function_stats.into_iter().map(|(_, x)| x));
+        Ok(())
+    }
+
+    fn add_term(&mut self, term: &Term, function: &mut Option<&mut FunctionStats>) {
+        *self.term_kinds.entry(term.kind()).or_default() += 1;
+        match term {
+            Term::Integer(x) if !is_small_integer(x) => self.bignums += 1,
+            Term::List(x) => {
+                for item in &x.items {
+                    self.add_term(item, function);
+                }
+            }
+            _ => {}
+        }
+        if let Some(function) = function {
+            match term {
+                Term::XRegister(x) | Term::TypedRegister(TypedRegister::X { register: x, .. }) => {
+                    function.x_registers = function.x_registers.max(x.value + 1);
+                }
+                Term::YRegister(x) | Term::TypedRegister(TypedRegister::Y { register: x, .. }) => {
+                    function.y_registers = function.y_registers.max(x.value + 1);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns this statistics as a JSON object.
+    pub fn to_json(&self) -> String {
+        let instructions = self
+            .instructions
+            .iter()
+            .map(|(name, x)| {
+                format!(
+                    r#"{}:{{"opcode":{},"count":{},"encoded_bytes":{}}}"#,
+                    crate::json::string(name),
+                    x.opcode,
+                    x.count,
+                    x.encoded_bytes
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let term_kinds = self
+            .term_kinds
+            .iter()
+            .map(|(kind, count)| format!(r#""{kind:?}":{count}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        let functions = self
+            .functions
+            .iter()
+            .map(|x| {
+                format!(
+                    r#"{{"function":{},"x_registers":{},"y_registers":{}}}"#,
+                    x.function.to_json(),
+                    x.x_registers,
+                    x.y_registers
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"instructions":{{{instructions}}},"term_kinds":{{{term_kinds}}},"functions":[{functions}],"bignums":{}}}"#,
+            self.bignums
+        )
+    }
+}
+
+fn is_small_integer(x: &BigInt) -> bool {
+    let max = BigInt::from(1) << 59;
+    -&max <= *x && *x < max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Move, PutTuple2, Return};
+    use crate::term::{List, Register, XRegister};
+
+    #[test]
+    fn stats_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 0);
+        function
+            .push(Move {
+                src: Term::Integer(BigInt::from(1) << 64),
+                dst: Register::x(3),
+            })
+            .push(Move {
+                src: Term::x(3),
+                dst: Register::y(0),
+            })
+            .push(Return {});
+        let module = builder.build();
+
+        let stats = Stats::from_module(&module).unwrap();
+        assert_eq!(stats.instructions["move"].count, 2);
+        assert_eq!(stats.instructions["move"].encoded_bytes, 16);
+        assert_eq!(stats.term_kinds[&TermKind::XRegister], 2);
+        assert_eq!(stats.term_kinds[&TermKind::Integer], 1);
+        assert_eq!(stats.functions[0].x_registers, 4);
+        assert_eq!(stats.functions[0].y_registers, 1);
+        assert_eq!(stats.bignums, 1);
+        assert!(stats
+            .to_json()
+            .contains(r#""move":{"opcode":64,"count":2,"encoded_bytes":16}"#));
+    }
+
+    #[test]
+    fn stats_of_list_operands_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let max = BigInt::from(1) << 59u32;
+        let mut function = builder.function("bar", 0);
+        function
+            .push(PutTuple2 {
+                destination: Register::x(0),
+                elements: List {
+                    items: vec![
+                        Term::TypedRegister(TypedRegister::X {
+                            register: XRegister { value: 5, ty: None },
+                            ty: 0,
+                        }),
+                        Term::Integer(&max - 1),
+                        Term::Integer(-&max),
+                        Term::Integer(max.clone()),
+                        Term::Integer(-&max - 1),
+                    ],
+                },
+            })
+            .push(Return {});
+        let module = builder.build();
+
+        let mut stats = Stats::from_module(&module).unwrap();
+        assert_eq!(stats.term_kinds[&TermKind::List], 1);
+        assert_eq!(stats.term_kinds[&TermKind::TypedRegister], 1);
+        assert_eq!(stats.term_kinds[&TermKind::Integer], 4);
+        assert_eq!(stats.functions[0].x_registers, 6);
+        assert_eq!(stats.functions[0].y_registers, 0);
+        assert_eq!(stats.bignums, 2);
+
+        stats.add_module(&module).unwrap();
+        assert_eq!(stats.instructions["put_tuple2"].count, 2);
+        assert_eq!(stats.functions.len(), 2);
+        assert_eq!(stats.bignums, 4);
+    }
+}
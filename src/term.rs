@@ -5,12 +5,12 @@
 //! - [The BEAM Book - Compact Term Encoding](https://blog.stenmans.org/theBeamBook/#SEC-BeamModulesCTE)
 //! - [erlang/otp/lib/compiler/src/beam_asm.erl](https://github.com/erlang/otp/blob/master/lib/compiler/src/beam_asm.erl)
 //! - [erlang/otp/lib/compiler/src/beam_disasm.erl](https://github.com/erlang/otp/blob/master/lib/compiler/src/beam_disasm.erl)
-use crate::{Decode, DecodeError, Encode, EncodeError};
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use crate::{Decode, DecodeError, DecodeLimits, Encode, EncodeError, Visit, Visitor, VisitorMut};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use num::BigInt;
 use std::io::{Read, Write};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum TermKind {
     Usize,
     Integer,
@@ -19,6 +19,7 @@ pub enum TermKind {
     YRegister,
     Label,
     Character,
+    Float,
     List,
     FloatingPointRegister,
     AllocationList,
@@ -28,7 +29,7 @@ pub enum TermKind {
 }
 
 impl TermKind {
-    fn from_tag(tag: u8) -> Self {
+    pub(crate) fn from_tag(tag: u8) -> Self {
         match tag & 0b111 {
             0 => Self::Usize,
             1 => Self::Integer,
@@ -38,6 +39,7 @@ impl TermKind {
             5 => Self::Label,
             6 => Self::Character,
             7 => match tag >> 4 {
+                0b0000 => Self::Float,
                 0b0001 => Self::List,
                 0b0010 => Self::FloatingPointRegister,
                 0b0011 => Self::AllocationList,
@@ -49,8 +51,8 @@ impl TermKind {
         }
     }
 
-    fn expect(self, expected: &[Self]) -> Result<(), DecodeError> {
-        if expected.iter().any(|&x| x == self) {
+    pub(crate) fn expect(self, expected: &[Self]) -> Result<(), DecodeError> {
+        if expected.contains(&self) {
             Ok(())
         } else {
             Err(DecodeError::UnexpectedTerm {
@@ -69,6 +71,7 @@ impl TermKind {
             Self::YRegister => 4,
             Self::Label => 5,
             Self::Character => 6,
+            Self::Float => 0b0000_0111,
             Self::List => 0b0001_0111,
             Self::FloatingPointRegister => 0b0010_0111,
             Self::AllocationList => 0b0011_0111,
@@ -88,6 +91,7 @@ pub enum Term {
     YRegister(YRegister),
     Label(Label),
     Character(char),
+    Float(Float),
     List(List),
     FloatingPointRegister(FloatingPointRegister),
     AllocationList(AllocationList),
@@ -95,46 +99,193 @@ pub enum Term {
     TypedRegister(TypedRegister),
 }
 
+impl Term {
+    /// Makes an x register term without type information.
+    pub fn x(value: usize) -> Self {
+        Self::XRegister(XRegister { value, ty: None })
+    }
+
+    /// Makes a y register term without type information.
+    pub fn y(value: usize) -> Self {
+        Self::YRegister(YRegister { value, ty: None })
+    }
+
+    /// Returns the kind of this term.
+    pub fn kind(&self) -> TermKind {
+        match self {
+            Self::Usize(_) => TermKind::Usize,
+            Self::Integer(_) => TermKind::Integer,
+            Self::Atom(_) => TermKind::Atom,
+            Self::XRegister(_) => TermKind::XRegister,
+            Self::YRegister(_) => TermKind::YRegister,
+            Self::Label(_) => TermKind::Label,
+            Self::Character(_) => TermKind::Character,
+            Self::Float(_) => TermKind::Float,
+            Self::List(_) => TermKind::List,
+            Self::FloatingPointRegister(_) => TermKind::FloatingPointRegister,
+            Self::AllocationList(_) => TermKind::AllocationList,
+            Self::Literal(_) => TermKind::Literal,
+            Self::TypedRegister(_) => TermKind::TypedRegister,
+        }
+    }
+
+    /// Returns the register denoted by this term, if any.
+    pub fn to_register(&self) -> Option<Register> {
+        match self {
+            Self::XRegister(x) => Some(Register::X(*x)),
+            Self::YRegister(x) => Some(Register::Y(*x)),
+            Self::TypedRegister(x) => Some(Register::Typed(*x)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this term if it is an unsigned integer (e.g., an arity).
+    pub fn to_usize(&self) -> Option<usize> {
+        match self {
+            Self::Usize(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Returns the allocation denoted by this term (a word count or an allocation list), if any.
+    pub fn to_allocation(&self) -> Option<Allocation> {
+        match self {
+            Self::Usize(x) => Some(Allocation::Words(*x)),
+            Self::AllocationList(x) => Some(Allocation::List(x.clone())),
+            _ => None,
+        }
+    }
+
+    /// Returns the label denoted by this term, if any.
+    pub fn to_label(&self) -> Option<Label> {
+        match self {
+            Self::Label(x) => Some(*x),
+            _ => None,
+        }
+    }
+}
+
 impl Decode for Term {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         match TermKind::from_tag(tag) {
-            TermKind::Usize => Decode::decode_with_tag(reader, tag).map(Self::Usize),
-            TermKind::Integer => Decode::decode_with_tag(reader, tag).map(Self::Integer),
-            TermKind::Atom => Decode::decode_with_tag(reader, tag).map(Self::Atom),
-            TermKind::XRegister => Decode::decode_with_tag(reader, tag).map(Self::XRegister),
-            TermKind::YRegister => Decode::decode_with_tag(reader, tag).map(Self::YRegister),
-            TermKind::Label => Decode::decode_with_tag(reader, tag).map(Self::Label),
-            TermKind::Character => Decode::decode_with_tag(reader, tag).map(Self::Character),
-            TermKind::List => Decode::decode_with_tag(reader, tag).map(Self::List),
-            TermKind::FloatingPointRegister => {
-                Decode::decode_with_tag(reader, tag).map(Self::FloatingPointRegister)
+            TermKind::Usize => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Usize)
+            }
+            TermKind::Integer => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Integer)
+            }
+            TermKind::Atom => Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Atom),
+            TermKind::XRegister => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::XRegister)
+            }
+            TermKind::YRegister => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::YRegister)
             }
+            TermKind::Label => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Label)
+            }
+            TermKind::Character => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Character)
+            }
+            TermKind::Float => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Float)
+            }
+            TermKind::List => Decode::decode_with_tag_limited(reader, tag, limits).map(Self::List),
+            TermKind::FloatingPointRegister => Decode::decode_with_tag_limited(reader, tag, limits)
+                .map(Self::FloatingPointRegister),
             TermKind::AllocationList => {
-                Decode::decode_with_tag(reader, tag).map(Self::AllocationList)
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::AllocationList)
+            }
+            TermKind::Literal => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Literal)
             }
-            TermKind::Literal => Decode::decode_with_tag(reader, tag).map(Self::Literal),
             TermKind::TypedRegister => {
-                Decode::decode_with_tag(reader, tag).map(Self::TypedRegister)
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::TypedRegister)
             }
             TermKind::Unknown(_) => Err(DecodeError::UnknownTermTag { tag }),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode)]
+impl Visit for Term {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_term(self);
+        match self {
+            Self::Usize(x) => x.visit(visitor),
+            Self::Integer(x) => x.visit(visitor),
+            Self::Atom(x) => x.visit(visitor),
+            Self::XRegister(x) => x.visit(visitor),
+            Self::YRegister(x) => x.visit(visitor),
+            Self::Label(x) => x.visit(visitor),
+            Self::Character(x) => x.visit(visitor),
+            Self::Float(x) => x.visit(visitor),
+            Self::List(x) => x.visit(visitor),
+            Self::FloatingPointRegister(x) => x.visit(visitor),
+            Self::AllocationList(x) => x.visit(visitor),
+            Self::Literal(x) => x.visit(visitor),
+            Self::TypedRegister(x) => x.visit(visitor),
+        }
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_term_mut(self);
+        match self {
+            Self::Usize(x) => x.visit_mut(visitor),
+            Self::Integer(x) => x.visit_mut(visitor),
+            Self::Atom(x) => x.visit_mut(visitor),
+            Self::XRegister(x) => x.visit_mut(visitor),
+            Self::YRegister(x) => x.visit_mut(visitor),
+            Self::Label(x) => x.visit_mut(visitor),
+            Self::Character(x) => x.visit_mut(visitor),
+            Self::Float(x) => x.visit_mut(visitor),
+            Self::List(x) => x.visit_mut(visitor),
+            Self::FloatingPointRegister(x) => x.visit_mut(visitor),
+            Self::AllocationList(x) => x.visit_mut(visitor),
+            Self::Literal(x) => x.visit_mut(visitor),
+            Self::TypedRegister(x) => x.visit_mut(visitor),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Visit)]
 pub enum Allocation {
     Words(usize),
     List(AllocationList),
 }
 
+impl Allocation {
+    /// Returns the number of the words (excluding floats and funs) to be allocated.
+    pub fn words(&self) -> usize {
+        match self {
+            Self::Words(n) => *n,
+            Self::List(x) => x
+                .items
+                .iter()
+                .map(|x| match x {
+                    AllocationListItem::Words(n) => *n,
+                    _ => 0,
+                })
+                .sum(),
+        }
+    }
+}
+
 impl Decode for Allocation {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         let kind = TermKind::from_tag(tag);
         kind.expect(&[TermKind::Usize, TermKind::AllocationList])?;
         if kind == TermKind::Usize {
-            Decode::decode_with_tag(reader, tag).map(Self::Words)
+            Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Words)
         } else {
-            Decode::decode_with_tag(reader, tag).map(Self::List)
+            Decode::decode_with_tag_limited(reader, tag, limits).map(Self::List)
         }
     }
 }
@@ -145,11 +296,16 @@ pub struct AllocationList {
 }
 
 impl Decode for AllocationList {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::AllocationList])?;
-        let size = usize::decode(reader)?;
+        let size = usize::decode_limited(reader, limits)?;
+        limits.check_list_length(size)?;
         let items = (0..size)
-            .map(|_| Decode::decode(reader))
+            .map(|_| Decode::decode_limited(reader, limits))
             .collect::<Result<_, _>>()?;
         Ok(Self { items })
     }
@@ -166,6 +322,12 @@ impl Encode for AllocationList {
     }
 }
 
+impl Visit for AllocationList {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AllocationListItem {
     Words(usize),
@@ -174,11 +336,15 @@ pub enum AllocationListItem {
 }
 
 impl Decode for AllocationListItem {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
-        match usize::decode_with_tag(reader, tag)? {
-            0 => usize::decode(reader).map(Self::Words),
-            1 => usize::decode(reader).map(Self::Floats),
-            2 => usize::decode(reader).map(Self::Funs),
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        match usize::decode_with_tag_limited(reader, tag, limits)? {
+            0 => usize::decode_limited(reader, limits).map(Self::Words),
+            1 => usize::decode_limited(reader, limits).map(Self::Floats),
+            2 => usize::decode_limited(reader, limits).map(Self::Funs),
             tag => Err(DecodeError::UnknownAllocationListItemTag { tag }),
         }
     }
@@ -204,16 +370,20 @@ impl Encode for AllocationListItem {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct FloatingPointRegister {
     pub value: usize,
 }
 
 impl Decode for FloatingPointRegister {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::FloatingPointRegister])?;
         Ok(Self {
-            value: usize::decode(reader)?,
+            value: usize::decode_limited(reader, limits)?,
         })
     }
 }
@@ -225,26 +395,40 @@ impl Encode for FloatingPointRegister {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl Visit for FloatingPointRegister {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_float_register(self);
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_float_register_mut(self);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum TypedRegister {
     X { register: XRegister, ty: usize },
     Y { register: YRegister, ty: usize },
 }
 
 impl Decode for TypedRegister {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::TypedRegister])?;
 
         let tag = reader.read_u8()?;
         let kind = TermKind::from_tag(tag);
         kind.expect(&[TermKind::XRegister, TermKind::YRegister])?;
         if kind == TermKind::XRegister {
-            let register = XRegister::decode_with_tag(reader, tag)?;
-            let ty = usize::decode(reader)?;
+            let register = XRegister::decode_with_tag_limited(reader, tag, limits)?;
+            let ty = usize::decode_limited(reader, limits)?;
             Ok(Self::X { register, ty })
         } else {
-            let register = YRegister::decode_with_tag(reader, tag)?;
-            let ty = usize::decode(reader)?;
+            let register = YRegister::decode_with_tag_limited(reader, tag, limits)?;
+            let ty = usize::decode_limited(reader, limits)?;
             Ok(Self::Y { register, ty })
         }
     }
@@ -267,19 +451,81 @@ impl Encode for TypedRegister {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode)]
+impl Visit for TypedRegister {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Encode)]
 pub enum Register {
     X(XRegister),
     Y(YRegister),
     Typed(TypedRegister),
 }
 
+impl Register {
+    /// Makes an x register without type information.
+    pub fn x(value: usize) -> Self {
+        Self::X(XRegister { value, ty: None })
+    }
+
+    /// Makes a y register without type information.
+    pub fn y(value: usize) -> Self {
+        Self::Y(YRegister { value, ty: None })
+    }
+
+    /// Returns the same register without any type information.
+    pub fn untyped(&self) -> Self {
+        match self {
+            Self::X(x) | Self::Typed(TypedRegister::X { register: x, .. }) => Self::X(XRegister {
+                value: x.value,
+                ty: None,
+            }),
+            Self::Y(x) | Self::Typed(TypedRegister::Y { register: x, .. }) => Self::Y(YRegister {
+                value: x.value,
+                ty: None,
+            }),
+        }
+    }
+}
+
+impl From<Register> for Term {
+    fn from(x: Register) -> Self {
+        match x {
+            Register::X(x) => Self::XRegister(x),
+            Register::Y(x) => Self::YRegister(x),
+            Register::Typed(x) => Self::TypedRegister(x),
+        }
+    }
+}
+
+impl Visit for Register {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_register(self);
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_register_mut(self);
+    }
+}
+
 impl Decode for Register {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         match TermKind::from_tag(tag) {
-            TermKind::XRegister => Decode::decode_with_tag(reader, tag).map(Self::X),
-            TermKind::YRegister => Decode::decode_with_tag(reader, tag).map(Self::Y),
-            TermKind::TypedRegister => Decode::decode_with_tag(reader, tag).map(Self::Typed),
+            TermKind::XRegister => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::X)
+            }
+            TermKind::YRegister => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Y)
+            }
+            TermKind::TypedRegister => {
+                Decode::decode_with_tag_limited(reader, tag, limits).map(Self::Typed)
+            }
             actual => Err(DecodeError::UnexpectedTerm {
                 actual,
                 expected: vec![
@@ -293,10 +539,13 @@ impl Decode for Register {
 }
 
 impl Decode for usize {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Usize])?;
-        let value = usize::try_from(decode_integer(tag, reader)?)?;
-        Ok(value)
+        decode_index(TermKind::Usize, decode_integer(tag, reader, limits)?)
     }
 }
 
@@ -306,10 +555,20 @@ impl Encode for usize {
     }
 }
 
+impl Visit for usize {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 impl Decode for char {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Character])?;
-        let value = u32::try_from(decode_integer(tag, reader)?)?;
+        let value = decode_index(TermKind::Character, decode_integer(tag, reader, limits)?)?;
         char::from_u32(value).ok_or(DecodeError::InvalidUnicodeCodepoint { value })
     }
 }
@@ -324,16 +583,75 @@ impl Encode for char {
     }
 }
 
+impl Visit for char {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
+/// A float encoded inline in bytecode.
+///
+/// Only very old BEAM files use this encoding (newer ones store floats in the literal table).
+/// Floats are compared by their bit patterns, so `NaN` is equal to itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Float {
+    pub value: f64,
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.value.to_bits() == other.value.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl std::hash::Hash for Float {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+    }
+}
+
+impl Decode for Float {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        _limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        TermKind::from_tag(tag).expect(&[TermKind::Float])?;
+        let value = reader.read_f64::<BigEndian>()?;
+        Ok(Self { value })
+    }
+}
+
+impl Encode for Float {
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_u8(TermKind::Float.tag())?;
+        writer.write_f64::<BigEndian>(self.value)?;
+        Ok(())
+    }
+}
+
+impl Visit for Float {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Literal {
     pub value: usize,
 }
 
 impl Decode for Literal {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Literal])?;
         Ok(Self {
-            value: usize::decode(reader)?,
+            value: usize::decode_limited(reader, limits)?,
         })
     }
 }
@@ -345,10 +663,24 @@ impl Encode for Literal {
     }
 }
 
+impl Visit for Literal {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_literal(self);
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_literal_mut(self);
+    }
+}
+
 impl Decode for BigInt {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Integer])?;
-        let value = decode_integer(tag, reader)?;
+        let value = decode_integer(tag, reader, limits)?;
         Ok(value)
     }
 }
@@ -359,15 +691,25 @@ impl Encode for BigInt {
     }
 }
 
+impl Visit for BigInt {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Atom {
     pub value: usize,
 }
 
 impl Decode for Atom {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Atom])?;
-        let value = usize::try_from(decode_integer(tag, reader)?)?;
+        let value = decode_index(TermKind::Atom, decode_integer(tag, reader, limits)?)?;
         Ok(Self { value })
     }
 }
@@ -378,6 +720,16 @@ impl Encode for Atom {
     }
 }
 
+impl Visit for Atom {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_atom(self);
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_atom_mut(self);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct XRegister {
     pub value: usize,
@@ -385,9 +737,13 @@ pub struct XRegister {
 }
 
 impl Decode for XRegister {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::XRegister])?;
-        let value = usize::try_from(decode_integer(tag, reader)?)?;
+        let value = decode_index(TermKind::XRegister, decode_integer(tag, reader, limits)?)?;
         Ok(Self { value, ty: None })
     }
 }
@@ -398,6 +754,12 @@ impl Encode for XRegister {
     }
 }
 
+impl Visit for XRegister {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct YRegister {
     pub value: usize,
@@ -405,9 +767,13 @@ pub struct YRegister {
 }
 
 impl Decode for YRegister {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::YRegister])?;
-        let value = usize::try_from(decode_integer(tag, reader)?)?;
+        let value = decode_index(TermKind::YRegister, decode_integer(tag, reader, limits)?)?;
         Ok(Self { value, ty: None })
     }
 }
@@ -418,9 +784,19 @@ impl Encode for YRegister {
     }
 }
 
+impl Visit for YRegister {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 impl Decode for Vec<YRegister> {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
-        let list = List::decode_with_tag(reader, tag)?;
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        let list = List::decode_with_tag_limited(reader, tag, limits)?;
         Ok(list.items)
     }
 }
@@ -434,15 +810,25 @@ impl Encode for Vec<YRegister> {
     }
 }
 
+impl Visit for Vec<YRegister> {
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    fn visit_mut<V: VisitorMut>(&mut self, _visitor: &mut V) {}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Label {
     pub value: usize,
 }
 
 impl Decode for Label {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Label])?;
-        let value = usize::try_from(decode_integer(tag, reader)?)?;
+        let value = decode_index(TermKind::Label, decode_integer(tag, reader, limits)?)?;
         Ok(Self { value })
     }
 }
@@ -453,18 +839,34 @@ impl Encode for Label {
     }
 }
 
+impl Visit for Label {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit_label(self);
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visitor.visit_label_mut(self);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct List<T = Term> {
     pub items: Vec<T>,
 }
 
 impl<T: Decode> Decode for List<T> {
-    fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
+    fn decode_with_tag_limited<R: Read>(
+        reader: &mut R,
+        tag: u8,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::List])?;
 
-        let size = usize::decode(reader)?;
+        let size = usize::decode_limited(reader, limits)?;
+        limits.check_list_length(size)?;
+        let limits = limits.nested()?;
         let items = (0..size)
-            .map(|_| T::decode(reader))
+            .map(|_| T::decode_limited(reader, &limits))
             .collect::<Result<_, _>>()?;
         Ok(Self { items })
     }
@@ -481,26 +883,82 @@ impl<T: Encode> Encode for List<T> {
     }
 }
 
-fn decode_integer<R: Read>(tag: u8, reader: &mut R) -> Result<BigInt, DecodeError> {
+impl<T: Visit> Visit for List<T> {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        for x in &self.items {
+            x.visit(visitor);
+        }
+    }
+
+    fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        for x in &mut self.items {
+            x.visit_mut(visitor);
+        }
+    }
+}
+
+/// Converts a decoded integer into an unsigned integer (e.g., an atom index).
+pub(crate) fn decode_index<T>(kind: TermKind, value: BigInt) -> Result<T, DecodeError>
+where
+    T: TryFrom<BigInt, Error = num::bigint::TryFromBigIntError<BigInt>>,
+{
+    if value.sign() == num::bigint::Sign::Minus {
+        return Err(DecodeError::NegativeIndex { kind, value });
+    }
+    T::try_from(value).map_err(|e| DecodeError::IndexTooLarge {
+        kind,
+        value: e.into_original(),
+    })
+}
+
+pub(crate) fn decode_integer<R: Read>(
+    tag: u8,
+    reader: &mut R,
+    limits: &DecodeLimits,
+) -> Result<BigInt, DecodeError> {
     if (tag & 0b1000) == 0 {
         Ok(BigInt::from(tag >> 4))
     } else if (tag & 0b1_0000) == 0 {
         let v = u64::from(reader.read_u8()?);
         Ok(BigInt::from((u64::from(tag) & 0b1110_0000) << 3 | v))
-    } else if (tag >> 5) != 0b111 {
-        let byte_size = usize::from(tag >> 5) + 2;
-        let mut buf = vec![0; byte_size];
-        reader.read_exact(&mut buf)?;
-        Ok(BigInt::from_signed_bytes_be(&buf))
     } else {
-        let byte_size = usize::decode(reader)?;
-        let mut buf = vec![0; byte_size + 9];
-        reader.read_exact(&mut buf)?;
+        let byte_size = if (tag >> 5) != 0b111 {
+            usize::from(tag >> 5) + 2
+        } else {
+            decode_integer_length(reader, limits)?.saturating_add(9)
+        };
+        limits.check_integer_size(byte_size)?;
+
+        // Doesn't allocate `byte_size` bytes upfront as it may be much larger than the input.
+        let mut buf = Vec::new();
+        reader.take(byte_size as u64).read_to_end(&mut buf)?;
+        if buf.len() != byte_size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
         Ok(BigInt::from_signed_bytes_be(&buf))
     }
 }
 
-fn encode_integer<W: Write>(tag: u8, value: &BigInt, writer: &mut W) -> Result<(), EncodeError> {
+// Decodes the length prefix (a `u` term) of an integer longer than 8 bytes.
+//
+// The prefix itself never needs the long form, so it is rejected instead of recursing.
+pub(crate) fn decode_integer_length<R: Read>(
+    reader: &mut R,
+    limits: &DecodeLimits,
+) -> Result<usize, DecodeError> {
+    let tag = reader.read_u8()?;
+    TermKind::from_tag(tag).expect(&[TermKind::Usize])?;
+    if tag & 0b1111_1000 == 0b1111_1000 {
+        return Err(DecodeError::NestedIntegerLength);
+    }
+    decode_index(TermKind::Usize, decode_integer(tag, reader, limits)?)
+}
+
+pub(crate) fn encode_integer<W: Write>(
+    tag: u8,
+    value: &BigInt,
+    writer: &mut W,
+) -> Result<(), EncodeError> {
     if let Ok(v) = i16::try_from(value.clone()) {
         if v < 0 {
             let bytes = v.to_be_bytes();
@@ -524,7 +982,9 @@ fn encode_integer_bytes<W: Write>(
     bytes: &[u8],
     writer: &mut W,
 ) -> Result<(), EncodeError> {
-    assert!(bytes.len() >= 2, "bug");
+    if bytes.len() < 2 {
+        return Err(EncodeError::TooShortIntegerBytes { size: bytes.len() });
+    }
 
     if bytes.len() <= 8 {
         writer.write_u8(((bytes.len() - 2) << 5) as u8 | 0b0001_1000 | tag)?;
@@ -555,7 +1015,8 @@ mod tests {
             (&[88, 248, 164, 147, 83], -123432109),
         ];
         for (input, expected) in data {
-            let decoded = decode_integer(input[0], &mut &input[1..]).expect("decode failure");
+            let decoded = decode_integer(input[0], &mut &input[1..], &DecodeLimits::default())
+                .expect("decode failure");
             assert_eq!(decoded, BigInt::from(*expected));
 
             let mut encoded = Vec::new();
@@ -566,7 +1027,8 @@ mod tests {
         let data: &[(&[u8], u64)] =
             &[(&[248, 0, 0, 128, 0, 0, 0, 0, 0, 0, 0], 9223372036854775808)];
         for (input, expected) in data {
-            let decoded = decode_integer(input[0], &mut &input[1..]).expect("decode failure");
+            let decoded = decode_integer(input[0], &mut &input[1..], &DecodeLimits::default())
+                .expect("decode failure");
             assert_eq!(decoded, BigInt::from(*expected));
 
             let mut encoded = Vec::new();
@@ -574,4 +1036,96 @@ mod tests {
             assert_eq!(encoded, *input);
         }
     }
+
+    #[test]
+    fn decode_invalid_index_fails() {
+        // -1 as an atom index.
+        let input = [0b0001_1010, 255, 255];
+        assert!(matches!(
+            Atom::decode(&mut &input[..]),
+            Err(DecodeError::NegativeIndex {
+                kind: TermKind::Atom,
+                ..
+            })
+        ));
+
+        // 2^32 as a character.
+        let input = [0b0111_1110, 1, 0, 0, 0, 0];
+        assert!(matches!(
+            char::decode(&mut &input[..]),
+            Err(DecodeError::IndexTooLarge {
+                kind: TermKind::Character,
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            encode_integer_bytes(0, &[1], &mut Vec::new()),
+            Err(EncodeError::TooShortIntegerBytes { size: 1 })
+        ));
+    }
+
+    #[test]
+    fn decode_limits_work() {
+        let limits = DecodeLimits {
+            max_integer_bytes: 8,
+            max_list_length: 2,
+            max_instructions: usize::MAX,
+            max_nesting: 1,
+            max_literal_table_bytes: usize::MAX,
+        };
+
+        // An integer claiming to have 2^32 + 9 bytes.
+        let input = [0b1111_1001, 0b0111_1000, 1, 0, 0, 0, 0];
+        assert!(matches!(
+            BigInt::decode_limited(&mut &input[..], &limits),
+            Err(DecodeError::TooLargeInteger { .. })
+        ));
+        assert!(BigInt::decode(&mut &input[..]).is_err());
+
+        // [[], [], []]
+        let input = [
+            0b0001_0111,
+            48,
+            0b0001_0111,
+            0,
+            0b0001_0111,
+            0,
+            0b0001_0111,
+            0,
+        ];
+        assert!(matches!(
+            Term::decode_limited(&mut &input[..], &limits),
+            Err(DecodeError::TooLongList { length: 3 })
+        ));
+
+        // [[]]
+        let input = [0b0001_0111, 16, 0b0001_0111, 0];
+        assert!(matches!(
+            Term::decode_limited(&mut &input[..], &limits),
+            Err(DecodeError::TooDeepNesting { max: 0 })
+        ));
+        assert!(Term::decode(&mut &input[..]).is_ok());
+
+        // An integer whose length prefix is again in the long form (repeatedly).
+        let mut input = vec![0b1111_1001];
+        input.resize(1 << 20, 0b1111_1000);
+        for limits in [limits, DecodeLimits::default()] {
+            assert!(matches!(
+                Term::decode_limited(&mut &input[..], &limits),
+                Err(DecodeError::NestedIntegerLength)
+            ));
+        }
+    }
+
+    #[test]
+    fn decode_encode_float_works() {
+        let input = [0b0000_0111, 64, 4, 0, 0, 0, 0, 0, 0];
+        let decoded = Term::decode(&mut &input[..]).expect("decode failure");
+        assert_eq!(decoded, Term::Float(Float { value: 2.5 }));
+
+        let mut encoded = Vec::new();
+        decoded.encode(&mut encoded).expect("encode failure");
+        assert_eq!(encoded, input);
+    }
 }
@@ -0,0 +1,415 @@
+//! Upgrading deprecated instructions.
+//!
+//! Old compilers emitted instructions which have been replaced by more general ones
+//! (and which recent versions of the loader no longer accept).
+//! [`upgrade()`] rewrites them into their modern equivalents:
+//!
+//! | Deprecated                                | Upgraded                              |
+//! |-------------------------------------------|---------------------------------------|
+//! | `m_plus`, `int_band`, ... (arithmetic)    | `gc_bif2` (`erlang:'+'/2`, ...)        |
+//! | `int_bnot`                                | `gc_bif1` (`erlang:bnot/1`)            |
+//! | `make_fun`, `make_fun2`                   | `make_fun3`                           |
+//! | `put_tuple` followed by `put`s            | `put_tuple2`                          |
+//! | `put_string`                              | `move` of a literal                   |
+//! | `put_literal`                             | `move` of a literal                   |
+//!
+//! The import, literal and lambda tables are extended as needed.
+//! The `live` operands of the generated `gc_bif*` instructions are computed by the
+//! [liveness analysis](crate::liveness::Liveness).
+//!
+//! The other deprecated instructions (`is_constant` and the old binary syntax instructions)
+//! have no mechanical translation and are reported as [`UpgradeError`]s.
+use crate::etf::Value;
+use crate::function::split_functions;
+use crate::instruction::{GcBif1, GcBif2, Instruction, MakeFun3, Move};
+use crate::liveness::Liveness;
+use crate::module::{Lambda, Module};
+use crate::normalize::fold_put_tuple;
+use crate::term::{Atom, Label, List, Literal, Term};
+
+/// The result of [`upgrade()`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpgradeReport {
+    pub upgraded: Vec<Upgraded>,
+    pub not_upgraded: Vec<UpgradeError>,
+}
+
+/// An upgraded instruction sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgraded {
+    /// The index of the first original instruction in [`Code::instructions`](crate::module::Code)
+    /// (before the upgrade).
+    pub index: usize,
+
+    /// The original instructions.
+    pub old: Vec<Instruction>,
+
+    /// The instruction which replaced `old`.
+    pub new: Instruction,
+}
+
+/// A deprecated instruction which could not be upgraded.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("instruction {index}: {kind}")]
+pub struct UpgradeError {
+    /// The index of the instruction in [`Code::instructions`](crate::module::Code)
+    /// (before the upgrade).
+    pub index: usize,
+
+    pub instruction: Instruction,
+    pub kind: UpgradeErrorKind,
+}
+
+/// Kinds of [`UpgradeError`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UpgradeErrorKind {
+    #[error("no modern equivalent")]
+    NoEquivalent,
+
+    #[error("unexpected operands")]
+    InvalidOperands,
+
+    #[error("put_tuple is not followed by {arity} put instructions")]
+    IncompletePutTuple { arity: usize },
+
+    #[error("no function starts at label {label:?}")]
+    UndefinedLambda { label: Label },
+
+    #[error("no lambda table entry {index}")]
+    UndefinedLambdaIndex { index: usize },
+
+    #[error("string table (StrT chunk) does not contain {offset}..{}", offset + len)]
+    OutOfStringTable { offset: usize, len: usize },
+}
+
+/// Upgrades the deprecated instructions in the code of `module`.
+///
+/// Instructions which cannot be upgraded are left as-is and reported in
+/// [`UpgradeReport::not_upgraded`].
+pub fn upgrade(module: &mut Module) -> UpgradeReport {
+    let old = std::mem::take(&mut module.code.instructions);
+    let functions = split_functions(&old);
+    let mut report = UpgradeReport::default();
+    let mut instructions = Vec::with_capacity(old.len());
+    let mut positions = Vec::new();
+    let mut i = 0;
+    while i < old.len() {
+        match upgrade_instruction(module, &functions, &old, i) {
+            Ok(Some((new, n))) => {
+                positions.push(instructions.len());
+                instructions.push(new.clone());
+                report.upgraded.push(Upgraded {
+                    index: i,
+                    old: old[i..i + n].to_vec(),
+                    new,
+                });
+                i += n;
+                continue;
+            }
+            Ok(None) => {}
+            Err(kind) => report.not_upgraded.push(UpgradeError {
+                index: i,
+                instruction: old[i].clone(),
+                kind,
+            }),
+        }
+        instructions.push(old[i].clone());
+        i += 1;
+    }
+
+    fix_gc_bif_live(&mut instructions, &positions);
+    for (upgraded, &position) in report.upgraded.iter_mut().zip(&positions) {
+        upgraded.new = instructions[position].clone();
+    }
+    module.code.opcode_max = instructions
+        .iter()
+        .map(|x| u32::from(x.opcode()))
+        .fold(module.code.opcode_max, u32::max);
+    module.code.instructions = instructions;
+    report
+}
+
+// Returns the upgraded instruction and the number of the original instructions it replaces,
+// or `None` if `instructions[i]` is not deprecated.
+fn upgrade_instruction(
+    module: &mut Module,
+    functions: &[crate::function::Function],
+    instructions: &[Instruction],
+    i: usize,
+) -> Result<Option<(Instruction, usize)>, UpgradeErrorKind> {
+    let new = match &instructions[i] {
+        Instruction::MPlus(x) => arithmetic(module, "+", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::MMinus(x) => arithmetic(module, "-", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::MTimes(x) => arithmetic(module, "*", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::MDiv(x) => arithmetic(module, "/", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntDiv(x) => arithmetic(module, "div", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntRem(x) => arithmetic(module, "rem", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntBand(x) => {
+            arithmetic(module, "band", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?
+        }
+        Instruction::IntBor(x) => arithmetic(module, "bor", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntBxor(x) => {
+            arithmetic(module, "bxor", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?
+        }
+        Instruction::IntBsl(x) => arithmetic(module, "bsl", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntBsr(x) => arithmetic(module, "bsr", [&x.arg1, &x.arg2, &x.arg3, &x.arg4])?,
+        Instruction::IntBnot(x) => {
+            let (Some(label), Some(destination)) = (as_label(&x.arg1), x.arg3.to_register()) else {
+                return Err(UpgradeErrorKind::InvalidOperands);
+            };
+            Instruction::GcBif1(GcBif1 {
+                arg1: Term::Label(label),
+                arg2: Term::Usize(0),
+                arg3: Term::Usize(module.intern_import("erlang", "bnot", 1)),
+                arg4: x.arg2.clone(),
+                arg5: destination.into(),
+            })
+        }
+        Instruction::MakeFun(x) => {
+            let (Some(label), Some(old_uniq), Some(num_free)) =
+                (as_label(&x.arg1), as_usize(&x.arg2), as_usize(&x.arg3))
+            else {
+                return Err(UpgradeErrorKind::InvalidOperands);
+            };
+            let lambda = match module
+                .lambdas
+                .iter()
+                .position(|x| x.label == label && x.num_free == num_free)
+            {
+                Some(lambda) => lambda,
+                None => {
+                    let function = functions
+                        .iter()
+                        .find(|x| x.entry_label == Some(label))
+                        .ok_or(UpgradeErrorKind::UndefinedLambda { label })?;
+                    module.lambdas.push(Lambda {
+                        function: function.name,
+                        arity: function.arity,
+                        label,
+                        index: module.lambdas.len(),
+                        num_free,
+                        old_uniq: old_uniq as u32,
+                    });
+                    module.lambdas.len() - 1
+                }
+            };
+            make_fun3(lambda, num_free)
+        }
+        Instruction::MakeFun2(x) => {
+            let index = as_usize(&x.arg1).ok_or(UpgradeErrorKind::InvalidOperands)?;
+            let lambda = module
+                .lambdas
+                .get(index)
+                .ok_or(UpgradeErrorKind::UndefinedLambdaIndex { index })?;
+            make_fun3(index, lambda.num_free)
+        }
+        Instruction::PutTuple(x) => {
+            let arity = as_usize(&x.arg1).ok_or(UpgradeErrorKind::InvalidOperands)?;
+            let new = fold_put_tuple(&instructions[i..])
+                .ok_or(UpgradeErrorKind::IncompletePutTuple { arity })?;
+            return Ok(Some((Instruction::PutTuple2(new), arity + 1)));
+        }
+        Instruction::PutString(x) => {
+            let (Some(len), Some(offset), Some(dst)) =
+                (as_usize(&x.arg1), as_usize(&x.arg2), x.arg3.to_register())
+            else {
+                return Err(UpgradeErrorKind::InvalidOperands);
+            };
+            let bytes = module
+                .other_chunk(b"StrT")
+                .and_then(|x| x.get(offset..offset.checked_add(len)?))
+                .ok_or(UpgradeErrorKind::OutOfStringTable { offset, len })?;
+            let src = if bytes.is_empty() {
+                Term::Atom(Atom { value: 0 })
+            } else {
+                let value = Value::list(bytes.iter().map(|&b| Value::integer(b)));
+                let etf = value.to_bytes().expect("unreachable");
+                Term::Literal(module.intern_literal(&etf))
+            };
+            Instruction::Move(Move { src, dst })
+        }
+        Instruction::PutLiteral(x) => {
+            let (Some(value), Some(dst)) = (as_usize(&x.arg1), x.arg2.to_register()) else {
+                return Err(UpgradeErrorKind::InvalidOperands);
+            };
+            Instruction::Move(Move {
+                src: Term::Literal(Literal { value }),
+                dst,
+            })
+        }
+        Instruction::IsConstant(_)
+        | Instruction::BsStartMatch(_)
+        | Instruction::BsGetInteger(_)
+        | Instruction::BsGetFloat(_)
+        | Instruction::BsGetBinary(_)
+        | Instruction::BsTestTail(_)
+        | Instruction::BsRestore(_)
+        | Instruction::BsInit(_)
+        | Instruction::BsFinal(_)
+        | Instruction::BsFinal2(_)
+        | Instruction::BsBitsToBytes(_)
+        | Instruction::BsBitsToBytes2(_) => return Err(UpgradeErrorKind::NoEquivalent),
+        _ => return Ok(None),
+    };
+    Ok(Some((new, 1)))
+}
+
+// Translates `{arith, Op, Fail, Src1, Src2, Dst}` into `gc_bif2` (with a dummy `live` operand).
+fn arithmetic(
+    module: &mut Module,
+    op: &str,
+    [fail, arg1, arg2, dst]: [&Term; 4],
+) -> Result<Instruction, UpgradeErrorKind> {
+    let (Some(label), Some(destination)) = (as_label(fail), dst.to_register()) else {
+        return Err(UpgradeErrorKind::InvalidOperands);
+    };
+    Ok(Instruction::GcBif2(GcBif2 {
+        arg1: Term::Label(label),
+        arg2: Term::Usize(0),
+        arg3: Term::Usize(module.intern_import("erlang", op, 2)),
+        arg4: arg1.clone(),
+        arg5: arg2.clone(),
+        arg6: destination.into(),
+    }))
+}
+
+// The free variables are passed in `x0..x{num_free-1}` and the fun is returned in `x0`.
+fn make_fun3(lambda: usize, num_free: usize) -> Instruction {
+    Instruction::MakeFun3(MakeFun3 {
+        arg1: Term::Usize(lambda),
+        arg2: Term::x(0),
+        arg3: Term::List(List {
+            items: (0..num_free).map(Term::x).collect(),
+        }),
+    })
+}
+
+// Sets the `live` operands of the generated `gc_bif*` instructions at `positions`.
+fn fix_gc_bif_live(instructions: &mut [Instruction], positions: &[usize]) {
+    for function in split_functions(instructions) {
+        let targets = positions
+            .iter()
+            .copied()
+            .filter(|&i| {
+                function.range.contains(&i)
+                    && matches!(
+                        instructions[i],
+                        Instruction::GcBif1(_) | Instruction::GcBif2(_)
+                    )
+            })
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            continue;
+        }
+        let offset = function.range.start;
+        let liveness = Liveness::analyze(function.instructions(instructions));
+        let mismatches = liveness.check_live_operands(function.instructions(instructions));
+        for mismatch in mismatches {
+            let i = offset + mismatch.index;
+            if !targets.contains(&i) {
+                continue;
+            }
+            match &mut instructions[i] {
+                Instruction::GcBif1(x) => x.arg2 = Term::Usize(mismatch.required),
+                Instruction::GcBif2(x) => x.arg2 = Term::Usize(mismatch.required),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+fn as_label(term: &Term) -> Option<Label> {
+    match term {
+        Term::Label(x) => Some(*x),
+        _ => None,
+    }
+}
+
+fn as_usize(term: &Term) -> Option<usize> {
+    match term {
+        Term::Usize(x) => Some(*x),
+        Term::Integer(x) => x.try_into().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{IntBand, IsConstant, MakeFun, Opcode, Put, PutTuple, Return};
+
+    #[test]
+    fn upgrade_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("bar", 2);
+        function
+            .push(IntBand {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::x(0),
+                arg3: Term::x(1),
+                arg4: Term::x(2),
+            })
+            .push(PutTuple {
+                arg1: Term::Usize(2),
+                arg2: Term::x(0),
+            })
+            .push(Put { arg1: Term::x(2) })
+            .push(Put { arg1: Term::x(1) })
+            .push(Return {});
+        let mut function = builder.function("baz", 1);
+        let fail = function.func_info_label();
+        let entry = function.entry_label();
+        function
+            .push(IsConstant {
+                label: fail,
+                arg1: Term::x(0),
+            })
+            .push(MakeFun {
+                arg1: Term::Label(entry),
+                arg2: Term::Usize(0),
+                arg3: Term::Usize(1),
+            })
+            .push(Return {});
+        let mut module = builder.build();
+
+        let report = upgrade(&mut module);
+        assert_eq!(
+            report
+                .upgraded
+                .iter()
+                .map(|x| (x.index, x.old.len()))
+                .collect::<Vec<_>>(),
+            [(4, 1), (5, 3), (14, 1)]
+        );
+        assert_eq!(
+            report.upgraded[0].new,
+            Instruction::GcBif2(GcBif2 {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(2),
+                arg3: Term::Usize(0),
+                arg4: Term::x(0),
+                arg5: Term::x(1),
+                arg6: Term::x(2),
+            })
+        );
+        assert_eq!(
+            module.imports[0].function,
+            Atom {
+                value: module.atoms.iter().position(|x| x == "band").unwrap() + 1
+            }
+        );
+        assert_eq!(module.lambdas.len(), 1);
+        assert_eq!(module.lambdas[0].label, entry);
+        assert_eq!(module.lambdas[0].arity, 1);
+        assert_eq!(report.not_upgraded.len(), 1);
+        assert_eq!(report.not_upgraded[0].index, 13);
+        assert_eq!(report.not_upgraded[0].kind, UpgradeErrorKind::NoEquivalent);
+        assert_eq!(module.code.instructions.len(), 15);
+        assert_eq!(u32::from(MakeFun3::CODE), module.code.opcode_max);
+
+        let mut buf = Vec::new();
+        module.to_writer(&mut buf).unwrap();
+        assert_eq!(Module::from_reader(&buf[..]).unwrap(), module);
+    }
+}
@@ -0,0 +1,752 @@
+//! Bytecode validation.
+//!
+//! This module checks the instructions of functions for the same class of errors as
+//! OTP's `beam_validator`, so that broken code can be detected before it is rejected
+//! (with a less helpful message) by the loader.
+//!
+//! The validator performs a forward data flow analysis over the
+//! [control flow graph](crate::cfg::Cfg) of each function and tracks the following state:
+//!
+//! - the initialized x, y and floating point registers,
+//! - the size of the allocated stack frame, and
+//! - the active `try` / `catch` tags.
+//!
+//! At a label reached from several places, a register is regarded as initialized only if
+//! it is initialized on every incoming path, and the stack frame and tags must agree.
+//! Unreachable instructions are not checked except for their labels.
+use crate::cfg::Cfg;
+use crate::function::{split_functions, Function};
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::term::{Allocation, FloatingPointRegister, Label, Register, XRegister, YRegister};
+use crate::{Visit, Visitor};
+use std::collections::BTreeSet;
+
+/// Validation error.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{function}: instruction {index}: {kind}")]
+pub struct ValidationError {
+    /// The name and arity of the function (e.g., `"foo/2"`).
+    pub function: String,
+
+    /// The index of the offending instruction in [`Code::instructions`](crate::module::Code).
+    pub index: usize,
+
+    pub kind: ValidationErrorKind,
+}
+
+/// Kinds of [`ValidationError`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationErrorKind {
+    #[error("uninitialized register: {register:?}")]
+    UninitializedRegister { register: Register },
+
+    #[error("uninitialized floating point register: {register:?}")]
+    UninitializedFloatRegister { register: FloatingPointRegister },
+
+    #[error("invalid live operand {live}: {register:?} is not initialized")]
+    InvalidLive { live: usize, register: XRegister },
+
+    #[error("no stack frame is allocated")]
+    NoStackFrame,
+
+    #[error("stack frame is already allocated")]
+    StackFrameAlreadyAllocated,
+
+    #[error("stack frame is not deallocated")]
+    StackFrameNotDeallocated,
+
+    #[error("deallocating {deallocated} stack slots, but {allocated} slots are allocated")]
+    DeallocateMismatch {
+        allocated: usize,
+        deallocated: usize,
+    },
+
+    #[error("{register:?} is out of the stack frame of size {frame_size}")]
+    YRegisterOutOfFrame {
+        register: YRegister,
+        frame_size: usize,
+    },
+
+    #[error("inconsistent stack frames at label {label:?}")]
+    InconsistentStackFrame { label: Label },
+
+    #[error("undefined label: {label:?}")]
+    UndefinedLabel { label: Label },
+
+    #[error("{register:?} does not hold the innermost try/catch tag")]
+    UnbalancedTryCatch { register: Register },
+
+    #[error("try/catch tags are still active")]
+    ActiveTryCatch,
+
+    #[error("inconsistent try/catch tags at label {label:?}")]
+    InconsistentTryCatch { label: Label },
+
+    #[error("floating point register used by a non-float instruction: {register:?}")]
+    UnexpectedFloatRegister { register: FloatingPointRegister },
+
+    #[error("fmove without floating point register operands")]
+    InvalidFloatMove,
+
+    #[error("malformed operands")]
+    InvalidOperands,
+}
+
+/// Validates all functions of `module`.
+pub fn validate(module: &Module) -> Result<(), Vec<ValidationError>> {
+    let errors = split_functions(&module.code.instructions)
+        .iter()
+        .flat_map(|function| validate_function(module, function))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validates a function of `module` (typically obtained by [`split_functions()`]).
+///
+/// Labels referred to by local calls must be defined somewhere in the module.
+/// Any other label must be defined in the function itself.
+pub fn validate_function(module: &Module, function: &Function) -> Vec<ValidationError> {
+    let name = format!(
+        "{}/{}",
+        module.atom_name(function.name).unwrap_or("?"),
+        function.arity
+    );
+    let module_labels = module
+        .code
+        .instructions
+        .iter()
+        .filter_map(|x| match x {
+            Instruction::Label(x) => Some(Label { value: x.literal }),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut validator = FunctionValidator::new(function.instructions(&module.code.instructions));
+    validator.check_labels(&module_labels);
+    validator.analyze(function);
+    validator
+        .errors
+        .into_iter()
+        .map(|(index, kind)| ValidationError {
+            function: name.clone(),
+            index: function.range.start + index,
+            kind,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Try(usize),
+    Catch(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    x_registers: BTreeSet<usize>,
+    y_registers: BTreeSet<usize>,
+    float_registers: BTreeSet<usize>,
+    stack_frame: Option<usize>,
+    tags: Vec<Tag>,
+}
+
+impl State {
+    fn new(arity: usize) -> Self {
+        Self {
+            x_registers: (0..arity).collect(),
+            y_registers: BTreeSet::new(),
+            float_registers: BTreeSet::new(),
+            stack_frame: None,
+            tags: Vec::new(),
+        }
+    }
+
+    // Returns `true` if `self` has been changed.
+    fn merge(&mut self, other: &Self) -> bool {
+        let old = self.clone();
+        self.x_registers.retain(|x| other.x_registers.contains(x));
+        self.y_registers.retain(|x| other.y_registers.contains(x));
+        self.float_registers
+            .retain(|x| other.float_registers.contains(x));
+        *self != old
+    }
+}
+
+struct FunctionValidator<'a> {
+    instructions: &'a [Instruction],
+    cfg: Cfg,
+    errors: Vec<(usize, ValidationErrorKind)>,
+}
+
+impl<'a> FunctionValidator<'a> {
+    fn new(instructions: &'a [Instruction]) -> Self {
+        Self {
+            instructions,
+            cfg: Cfg::new(instructions),
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, index: usize, kind: ValidationErrorKind) {
+        if !self.errors.contains(&(index, kind.clone())) {
+            self.errors.push((index, kind));
+        }
+    }
+
+    fn check_labels(&mut self, module_labels: &BTreeSet<Label>) {
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            let labels = match instruction {
+                Instruction::Call(x) => vec![x.label],
+                Instruction::CallLast(x) => x.arg2.to_label().into_iter().collect(),
+                Instruction::CallOnly(x) => vec![x.label],
+                _ => instruction.branch_targets(),
+            };
+            for label in labels {
+                let defined = match instruction {
+                    Instruction::Call(_) | Instruction::CallLast(_) | Instruction::CallOnly(_) => {
+                        module_labels.contains(&label)
+                    }
+                    _ => self.cfg.label_to_block.contains_key(&label),
+                };
+                if !defined {
+                    self.error(index, ValidationErrorKind::UndefinedLabel { label });
+                }
+            }
+        }
+    }
+
+    fn analyze(&mut self, function: &Function) {
+        let mut entries: Vec<Option<State>> = vec![None; self.cfg.blocks.len()];
+        let initial = State::new(function.arity);
+        if !entries.is_empty() {
+            entries[0] = Some(initial.clone());
+        }
+        if let Some(&i) = function
+            .entry_label
+            .and_then(|label| self.cfg.label_to_block.get(&label))
+        {
+            entries[i] = Some(initial);
+        }
+
+        // Computes the entry states of the blocks. Errors are reported after the fixpoint is reached.
+        let mut worklist = (0..entries.len())
+            .filter(|&i| entries[i].is_some())
+            .collect::<BTreeSet<_>>();
+        let mut merge_errors = Vec::new();
+        while let Some(i) = worklist.pop_first() {
+            let Some(state) = entries[i].clone() else {
+                continue;
+            };
+            for (j, out) in self.successor_states(i, state, &mut Vec::new()) {
+                let changed = match &mut entries[j] {
+                    None => {
+                        entries[j] = Some(out);
+                        true
+                    }
+                    Some(entry) => {
+                        let label = self.cfg.blocks[j].labels.first().copied();
+                        if entry.stack_frame != out.stack_frame {
+                            if let Some(label) = label {
+                                merge_errors.push((
+                                    self.cfg.blocks[j].range.start,
+                                    ValidationErrorKind::InconsistentStackFrame { label },
+                                ));
+                            }
+                        }
+                        if entry.tags != out.tags {
+                            if let Some(label) = label {
+                                merge_errors.push((
+                                    self.cfg.blocks[j].range.start,
+                                    ValidationErrorKind::InconsistentTryCatch { label },
+                                ));
+                            }
+                        }
+                        entry.merge(&out)
+                    }
+                };
+                if changed {
+                    worklist.insert(j);
+                }
+            }
+        }
+
+        for (index, kind) in merge_errors {
+            self.error(index, kind);
+        }
+        for (i, entry) in entries.into_iter().enumerate() {
+            if let Some(state) = entry {
+                let mut errors = Vec::new();
+                self.successor_states(i, state, &mut errors);
+                for (index, kind) in errors {
+                    self.error(index, kind);
+                }
+            }
+        }
+        self.errors.sort_by_key(|(index, _)| *index);
+    }
+
+    // Executes the `i`-th block and returns the states passed to its successors.
+    fn successor_states(
+        &self,
+        i: usize,
+        mut state: State,
+        errors: &mut Vec<(usize, ValidationErrorKind)>,
+    ) -> Vec<(usize, State)> {
+        let block = &self.cfg.blocks[i];
+        let mut branch_state = state.clone();
+        for index in block.range.clone() {
+            branch_state = execute(&self.instructions[index], index, &mut state, errors);
+        }
+
+        let mut successors = Vec::new();
+        let last = block.range.clone().last().map(|j| &self.instructions[j]);
+        let branch_blocks = last
+            .map(|x| x.branch_targets())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|label| self.cfg.label_to_block.get(&label).copied())
+            .collect::<BTreeSet<_>>();
+        for &j in &block.successors {
+            if branch_blocks.contains(&j) {
+                successors.push((j, branch_state.clone()));
+            }
+            if j == i + 1 && !last.is_some_and(|x| x.is_terminator()) {
+                successors.push((j, state.clone()));
+            }
+        }
+        successors
+    }
+}
+
+// Executes `instruction` updating `state`, and returns the state passed to its branch target
+// (if any).
+fn execute(
+    instruction: &Instruction,
+    index: usize,
+    state: &mut State,
+    errors: &mut Vec<(usize, ValidationErrorKind)>,
+) -> State {
+    let mut error = |kind| errors.push((index, kind));
+
+    // Operands.
+    let uses = instruction.explicit_uses();
+    for register in &uses.registers {
+        match register {
+            Register::X(x) if !state.x_registers.contains(&x.value) => {
+                error(ValidationErrorKind::UninitializedRegister {
+                    register: register.clone(),
+                });
+            }
+            Register::Y(y)
+                if check_y_register(*y, state, &mut error)
+                    && !state.y_registers.contains(&y.value) =>
+            {
+                error(ValidationErrorKind::UninitializedRegister {
+                    register: register.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+    for register in &uses.float_registers {
+        if !state.float_registers.contains(&register.value) {
+            error(ValidationErrorKind::UninitializedFloatRegister {
+                register: *register,
+            });
+        }
+    }
+    if let Some(live) = instruction.live() {
+        if let Some(value) = (0..live).find(|x| !state.x_registers.contains(x)) {
+            error(ValidationErrorKind::InvalidLive {
+                live,
+                register: XRegister { value, ty: None },
+            });
+        }
+    }
+    check_float_operands(instruction, &mut error);
+
+    // Stack frame and try/catch tags.
+    let deallocate = match instruction {
+        Instruction::Allocate(x) => allocate(&x.stack_need, state, &mut error),
+        Instruction::AllocateHeap(x) => allocate(&x.stack_need, state, &mut error),
+        Instruction::AllocateZero(x) => well_formed(x.arg1.to_allocation(), &mut error)
+            .and_then(|stack_need| allocate(&stack_need, state, &mut error)),
+        Instruction::AllocateHeapZero(x) => allocate(&x.stack_need, state, &mut error),
+        Instruction::Deallocate(x) => Some(x.n),
+        Instruction::CallLast(x) => well_formed(x.arg3.to_usize(), &mut error),
+        Instruction::CallExtLast(x) => Some(x.deallocate),
+        Instruction::ApplyLast(x) => well_formed(x.arg2.to_usize(), &mut error),
+        Instruction::Return(_) | Instruction::CallOnly(_) | Instruction::CallExtOnly(_) => {
+            if state.stack_frame.is_some() {
+                error(ValidationErrorKind::StackFrameNotDeallocated);
+            }
+            if !state.tags.is_empty() {
+                error(ValidationErrorKind::ActiveTryCatch);
+            }
+            None
+        }
+        Instruction::Trim(x) => {
+            let Some(n) = well_formed(x.arg1.to_usize(), &mut error) else {
+                return state.clone();
+            };
+            match state.stack_frame {
+                None => error(ValidationErrorKind::NoStackFrame),
+                Some(size) if size < n => error(ValidationErrorKind::DeallocateMismatch {
+                    allocated: size,
+                    deallocated: n,
+                }),
+                Some(size) => {
+                    state.stack_frame = Some(size - n);
+                    state.y_registers = state
+                        .y_registers
+                        .iter()
+                        .filter_map(|y| y.checked_sub(n))
+                        .collect();
+                }
+            }
+            return state.clone();
+        }
+        Instruction::Try(x) => push_tag(&x.register, Tag::Try, state, &mut error),
+        Instruction::Catch(x) => well_formed(x.arg1.to_register(), &mut error)
+            .and_then(|register| push_tag(&register, Tag::Catch, state, &mut error)),
+        Instruction::TryEnd(x) => pop_tag(&x.register, Tag::Try, state, &mut error),
+        Instruction::TryCase(x) => pop_tag(&x.register, Tag::Try, state, &mut error),
+        Instruction::CatchEnd(x) => well_formed(x.arg1.to_register(), &mut error)
+            .and_then(|register| pop_tag(&register, Tag::Catch, state, &mut error)),
+        _ => None,
+    };
+    if let Some(n) = deallocate {
+        match state.stack_frame {
+            None => error(ValidationErrorKind::NoStackFrame),
+            Some(size) if size != n => error(ValidationErrorKind::DeallocateMismatch {
+                allocated: size,
+                deallocated: n,
+            }),
+            Some(_) => {}
+        }
+        if !state.tags.is_empty() {
+            error(ValidationErrorKind::ActiveTryCatch);
+        }
+        state.stack_frame = None;
+        state.y_registers.clear();
+    }
+
+    // Results.
+    let defs = instruction.defs();
+    if let Some(n) = defs.x_registers_from {
+        state.x_registers.retain(|&x| x < n);
+    }
+    if instruction.explicit_defs().x_registers_from.is_some() {
+        // Calls do not preserve floating point registers.
+        state.float_registers.clear();
+    }
+    // A failing instruction does not write its destination.
+    let mut branch_state = state.clone();
+    for register in &defs.registers {
+        match register {
+            Register::X(x) => {
+                state.x_registers.insert(x.value);
+            }
+            Register::Y(y) => {
+                if check_y_register(*y, state, &mut error) {
+                    state.y_registers.insert(y.value);
+                }
+            }
+            Register::Typed(_) => unreachable!(),
+        }
+    }
+    for register in &defs.float_registers {
+        state.float_registers.insert(register.value);
+    }
+    if matches!(instruction, Instruction::Try(_) | Instruction::Catch(_)) {
+        // The exception handler starts with the tag, but the x registers are unknown.
+        branch_state = state.clone();
+        branch_state.x_registers.clear();
+    }
+    branch_state
+}
+
+fn check_y_register(
+    y: YRegister,
+    state: &State,
+    error: &mut impl FnMut(ValidationErrorKind),
+) -> bool {
+    match state.stack_frame {
+        None => {
+            error(ValidationErrorKind::NoStackFrame);
+            false
+        }
+        Some(frame_size) if frame_size <= y.value => {
+            error(ValidationErrorKind::YRegisterOutOfFrame {
+                register: YRegister {
+                    value: y.value,
+                    ty: None,
+                },
+                frame_size,
+            });
+            false
+        }
+        Some(_) => true,
+    }
+}
+
+fn allocate(
+    stack_need: &Allocation,
+    state: &mut State,
+    error: &mut impl FnMut(ValidationErrorKind),
+) -> Option<usize> {
+    if state.stack_frame.is_some() {
+        error(ValidationErrorKind::StackFrameAlreadyAllocated);
+    }
+    let size = stack_need.words();
+    state.stack_frame = Some(size);
+    state.y_registers.clear();
+    None
+}
+
+// Reports `InvalidOperands` if an operand could not be interpreted.
+fn well_formed<T>(operand: Option<T>, error: &mut impl FnMut(ValidationErrorKind)) -> Option<T> {
+    if operand.is_none() {
+        error(ValidationErrorKind::InvalidOperands);
+    }
+    operand
+}
+
+fn push_tag(
+    register: &Register,
+    tag: fn(usize) -> Tag,
+    state: &mut State,
+    error: &mut impl FnMut(ValidationErrorKind),
+) -> Option<usize> {
+    if let Register::Y(y) = register.untyped() {
+        state.tags.push(tag(y.value));
+    } else {
+        error(ValidationErrorKind::UnbalancedTryCatch {
+            register: register.clone(),
+        });
+    }
+    None
+}
+
+fn pop_tag(
+    register: &Register,
+    tag: fn(usize) -> Tag,
+    state: &mut State,
+    error: &mut impl FnMut(ValidationErrorKind),
+) -> Option<usize> {
+    let expected = match register.untyped() {
+        Register::Y(y) => Some(tag(y.value)),
+        _ => None,
+    };
+    if expected.is_some() && state.tags.last() == expected.as_ref() {
+        state.tags.pop();
+    } else {
+        error(ValidationErrorKind::UnbalancedTryCatch {
+            register: register.clone(),
+        });
+    }
+    None
+}
+
+fn check_float_operands(instruction: &Instruction, error: &mut impl FnMut(ValidationErrorKind)) {
+    #[derive(Default)]
+    struct Collector(Vec<FloatingPointRegister>);
+    impl Visitor for Collector {
+        fn visit_float_register(&mut self, register: &FloatingPointRegister) {
+            self.0.push(*register);
+        }
+    }
+
+    match instruction {
+        Instruction::Fmove(x) => {
+            let mut collector = Collector::default();
+            x.arg1.visit(&mut collector);
+            x.arg2.visit(&mut collector);
+            if collector.0.is_empty() {
+                error(ValidationErrorKind::InvalidFloatMove);
+            }
+        }
+        Instruction::Fconv(_)
+        | Instruction::Fadd(_)
+        | Instruction::Fsub(_)
+        | Instruction::Fmul(_)
+        | Instruction::Fdiv(_)
+        | Instruction::Fnegate(_) => {}
+        _ => {
+            let mut collector = Collector::default();
+            instruction.visit(&mut collector);
+            for register in collector.0 {
+                error(ValidationErrorKind::UnexpectedFloatRegister { register });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        self, Allocate, Call, Deallocate, FuncInfo, IntCodeEnd, Jump, Move, Return, TestHeap, Trim,
+        Try, TryCase, TryEnd,
+    };
+    use crate::module::Code;
+    use crate::term::{Atom, Term};
+
+    fn label(literal: usize) -> Instruction {
+        Instruction::Label(instruction::Label { literal })
+    }
+
+    fn func_info(function: usize, arity: usize) -> Instruction {
+        Instruction::FuncInfo(FuncInfo {
+            module: Atom { value: 1 },
+            function: Atom { value: function },
+            arity,
+        })
+    }
+
+    fn x(value: usize) -> Register {
+        Register::X(XRegister { value, ty: None })
+    }
+
+    fn y(value: usize) -> Register {
+        Register::Y(YRegister { value, ty: None })
+    }
+
+    fn module(instructions: Vec<Instruction>) -> Module {
+        Module {
+            atoms: vec!["m".to_owned(), "good".to_owned(), "bad".to_owned()],
+            imports: Vec::new(),
+            exports: Vec::new(),
+            locals: Vec::new(),
+            lambdas: Vec::new(),
+            literals: Vec::new(),
+            code: Code {
+                instructions,
+                ..Default::default()
+            },
+            other_chunks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn validate_works() {
+        let good = vec![
+            label(1),
+            func_info(2, 1),
+            label(2),
+            Instruction::Allocate(Allocate {
+                stack_need: Allocation::Words(1),
+                live: 1,
+            }),
+            Instruction::Try(Try {
+                register: y(0),
+                label: Label { value: 3 },
+            }),
+            Instruction::Call(Call {
+                arity: 1,
+                label: Label { value: 2 },
+            }),
+            Instruction::TryEnd(TryEnd { register: y(0) }),
+            Instruction::Deallocate(Deallocate { n: 1 }),
+            Instruction::Return(Return {}),
+            label(3),
+            Instruction::TryCase(TryCase { register: y(0) }),
+            Instruction::Deallocate(Deallocate { n: 1 }),
+            Instruction::Return(Return {}),
+        ];
+        let mut instructions = good.clone();
+        instructions.push(Instruction::IntCodeEnd(IntCodeEnd {}));
+        assert_eq!(validate(&module(instructions)), Ok(()));
+
+        let bad = vec![
+            label(4),
+            func_info(3, 1),
+            label(5),
+            Instruction::TestHeap(TestHeap {
+                heap_need: Allocation::Words(2),
+                live: 2,
+            }),
+            Instruction::Allocate(Allocate {
+                stack_need: Allocation::Words(1),
+                live: 1,
+            }),
+            Instruction::Move(Move {
+                src: Term::YRegister(YRegister { value: 0, ty: None }),
+                dst: x(0),
+            }),
+            Instruction::Jump(Jump {
+                label: Label { value: 99 },
+            }),
+            label(6),
+            Instruction::Return(Return {}),
+        ];
+        let mut instructions = good;
+        instructions.extend(bad);
+        instructions.push(Instruction::IntCodeEnd(IntCodeEnd {}));
+        let errors = validate(&module(instructions)).unwrap_err();
+        let kinds = errors
+            .iter()
+            .map(|e| (e.index, e.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    16,
+                    ValidationErrorKind::InvalidLive {
+                        live: 2,
+                        register: XRegister { value: 1, ty: None }
+                    }
+                ),
+                (
+                    18,
+                    ValidationErrorKind::UninitializedRegister { register: y(0) }
+                ),
+                (
+                    19,
+                    ValidationErrorKind::UndefinedLabel {
+                        label: Label { value: 99 }
+                    }
+                ),
+            ]
+        );
+        assert_eq!(errors[0].function, "bad/1");
+        assert_eq!(
+            errors[0].to_string(),
+            "bad/1: instruction 16: invalid live operand 2: XRegister { value: 1, ty: None } is not initialized"
+        );
+    }
+
+    #[test]
+    fn validate_invalid_operands_works() {
+        let instructions = vec![
+            label(1),
+            func_info(2, 1),
+            label(2),
+            Instruction::Allocate(Allocate {
+                stack_need: Allocation::Words(1),
+                live: 1,
+            }),
+            Instruction::Trim(Trim {
+                arg1: Term::x(0),
+                arg2: Term::Usize(0),
+            }),
+            Instruction::Deallocate(Deallocate { n: 1 }),
+            Instruction::Return(Return {}),
+            Instruction::IntCodeEnd(IntCodeEnd {}),
+        ];
+        let errors = validate(&module(instructions)).unwrap_err();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.index, e.kind.clone()))
+                .collect::<Vec<_>>(),
+            [(4, ValidationErrorKind::InvalidOperands)]
+        );
+    }
+}
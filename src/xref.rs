@@ -0,0 +1,303 @@
+//! Cross reference checks.
+//!
+//! [`Xref`] collects the [call graph](crate::call_graph::CallGraph) and the export tables of
+//! many modules, and reports problems similar to those reported by OTP's `xref`:
+//!
+//! - calls to functions which are not defined (or not exported) by the analyzed modules,
+//! - exported functions which are not called from any other analyzed module,
+//! - calls to functions registered as deprecated, and
+//! - local functions which are not reachable from the exported functions of their module.
+//!
+//! Calls to modules which are not analyzed (e.g., `erlang` or `lists` unless they are added)
+//! are never reported as undefined.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::call_graph::Mfa;
+//! use beamcode::xref::Xref;
+//!
+//! let mut xref = Xref::new();
+//! xref.add_directory("_build/default/lib/foo/ebin")?;
+//! xref.add_deprecated(Mfa::new("foo", "old_api", 1));
+//! let report = xref.analyze();
+//! for edge in &report.undefined_calls {
+//!     println!("{} calls undefined function {}", edge.caller, edge.callee);
+//! }
+//! # Ok::<(), beamcode::DecodeError>(())
+//! ```
+use crate::call_graph::{CallGraph, Edge, EdgeKind, Mfa};
+use crate::function::split_functions;
+use crate::module::Module;
+use crate::DecodeError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Cross reference checker.
+#[derive(Debug, Clone, Default)]
+pub struct Xref {
+    graph: CallGraph,
+    modules: BTreeMap<String, ModuleFunctions>,
+    deprecated: BTreeSet<Mfa>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ModuleFunctions {
+    defined: BTreeSet<Mfa>,
+    exported: BTreeSet<Mfa>,
+}
+
+impl Xref {
+    /// Makes a checker without modules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `module` to the analyzed modules.
+    pub fn add_module(&mut self, module: &Module) {
+        let name = module.name().unwrap_or_default();
+        let mfa =
+            |function, arity| Mfa::new(name, module.atom_name(function).unwrap_or_default(), arity);
+        let functions = ModuleFunctions {
+            defined: split_functions(&module.code.instructions)
+                .iter()
+                .map(|x| mfa(x.name, x.arity))
+                .collect(),
+            exported: module
+                .exports
+                .iter()
+                .map(|x| mfa(x.function, x.arity))
+                .collect(),
+        };
+        self.modules.insert(name.to_owned(), functions);
+        self.graph.add_module(module);
+    }
+
+    /// Adds all `.beam` files in `dir` (not recursively) to the analyzed modules.
+    pub fn add_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<(), DecodeError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|x| x == "beam") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        for path in paths {
+            self.add_module(&Module::from_file(path)?);
+        }
+        Ok(())
+    }
+
+    /// Registers `mfa` as a deprecated function.
+    pub fn add_deprecated(&mut self, mfa: Mfa) {
+        self.deprecated.insert(mfa);
+    }
+
+    /// Returns the call graph of the analyzed modules.
+    pub fn call_graph(&self) -> &CallGraph {
+        &self.graph
+    }
+
+    /// Runs all the checks.
+    pub fn analyze(&self) -> XrefReport {
+        XrefReport {
+            undefined_calls: self.undefined_calls(),
+            unused_exports: self.unused_exports(),
+            deprecated_calls: self.deprecated_calls(),
+            unused_locals: self.unused_locals(),
+        }
+    }
+
+    /// Returns the calls to functions which are not defined by the (analyzed) callee module.
+    ///
+    /// Local calls and fun creations refer to the functions defined in the module, while
+    /// external calls (including those to the same module) require the callee to be exported.
+    pub fn undefined_calls(&self) -> Vec<Edge> {
+        self.graph
+            .edges
+            .iter()
+            .filter(|x| {
+                let Some(callee) = self.modules.get(&x.callee.module) else {
+                    return false;
+                };
+                match x.kind {
+                    EdgeKind::Local | EdgeKind::MakeFun => !callee.defined.contains(&x.callee),
+                    EdgeKind::External | EdgeKind::Bif => !callee.exported.contains(&x.callee),
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the exported functions which are not called from the other analyzed modules.
+    ///
+    /// `module_info/0` and `module_info/1` (added by the compiler) are excluded.
+    pub fn unused_exports(&self) -> Vec<Mfa> {
+        let called = self
+            .graph
+            .edges
+            .iter()
+            .filter(|x| x.caller.module != x.callee.module)
+            .map(|x| &x.callee)
+            .collect::<BTreeSet<_>>();
+        self.modules
+            .values()
+            .flat_map(|x| &x.exported)
+            .filter(|x| !called.contains(x) && x.function != "module_info")
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the calls to the deprecated functions (see [`Xref::add_deprecated()`]).
+    pub fn deprecated_calls(&self) -> Vec<Edge> {
+        self.graph
+            .edges
+            .iter()
+            .filter(|x| self.deprecated.contains(&x.callee))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the non-exported functions which are not reachable from the exported functions
+    /// of the same module (through local calls and fun creations).
+    pub fn unused_locals(&self) -> Vec<Mfa> {
+        let reachable = self
+            .graph
+            .locally_reachable(self.modules.values().flat_map(|x| &x.exported).cloned());
+        self.modules
+            .values()
+            .flat_map(|x| &x.defined)
+            .filter(|x| !reachable.contains(x))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The result of [`Xref::analyze()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XrefReport {
+    pub undefined_calls: Vec<Edge>,
+    pub unused_exports: Vec<Mfa>,
+    pub deprecated_calls: Vec<Edge>,
+    pub unused_locals: Vec<Mfa>,
+}
+
+impl XrefReport {
+    /// Returns `true` if no problems are found.
+    pub fn is_empty(&self) -> bool {
+        self.undefined_calls.is_empty()
+            && self.unused_exports.is_empty()
+            && self.deprecated_calls.is_empty()
+            && self.unused_locals.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use crate::instruction::{Call, CallExtOnly, CallOnly, Return};
+
+    #[test]
+    fn xref_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("main", 0);
+        function.export();
+        let missing = function.import("bar", "missing", 0);
+        let old = function.import("bar", "old", 0);
+        let unknown = function.import("baz", "qux", 0);
+        function
+            .push(CallExtOnly {
+                arity: 0,
+                destination: missing,
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: old,
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: unknown,
+            });
+        let mut function = builder.function("dead", 0);
+        let dead = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("deader", 0);
+        function.push(CallOnly {
+            arity: 0,
+            label: dead,
+        });
+        let foo = builder.build();
+
+        let mut builder = ModuleBuilder::new("bar");
+        let mut function = builder.function("old", 0);
+        function.export().push(Return {});
+        let mut function = builder.function("unused", 0);
+        function.export().push(Return {});
+        let bar = builder.build();
+
+        let mut xref = Xref::new();
+        xref.add_module(&foo);
+        xref.add_module(&bar);
+        xref.add_deprecated(Mfa::new("bar", "old", 0));
+        let report = xref.analyze();
+        assert_eq!(
+            report
+                .undefined_calls
+                .iter()
+                .map(|x| x.callee.to_string())
+                .collect::<Vec<_>>(),
+            ["bar:missing/0"]
+        );
+        assert_eq!(
+            report
+                .deprecated_calls
+                .iter()
+                .map(|x| x.caller.to_string())
+                .collect::<Vec<_>>(),
+            ["foo:main/0"]
+        );
+        assert_eq!(
+            report.unused_exports,
+            [Mfa::new("bar", "unused", 0), Mfa::new("foo", "main", 0)]
+        );
+        assert_eq!(
+            report.unused_locals,
+            [Mfa::new("foo", "dead", 0), Mfa::new("foo", "deader", 0)]
+        );
+    }
+
+    #[test]
+    fn undefined_calls_within_module_works() {
+        let mut builder = ModuleBuilder::new("foo");
+        let mut function = builder.function("hidden", 0);
+        let hidden = function.entry_label();
+        function.push(Return {});
+        let mut function = builder.function("main", 0);
+        let hidden_ext = function.import("foo", "hidden", 0);
+        let main_ext = function.import("foo", "main", 0);
+        function
+            .export()
+            .push(Call {
+                arity: 0,
+                label: hidden,
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: hidden_ext,
+            })
+            .push(CallExtOnly {
+                arity: 0,
+                destination: main_ext,
+            });
+        let foo = builder.build();
+
+        let mut xref = Xref::new();
+        xref.add_module(&foo);
+        let undefined = xref.undefined_calls();
+        assert_eq!(undefined.len(), 1);
+        assert_eq!(undefined[0].callee, Mfa::new("foo", "hidden", 0));
+        assert_eq!(undefined[0].kind, EdgeKind::External);
+    }
+}